@@ -0,0 +1,150 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DOCUMENTS: usize = 200;
+
+// Spread over a few sections with headings, code and cross links so parsing has real work to do
+fn write_corpus(source: &Path) {
+	for i in 0..DOCUMENTS {
+		let dir = source.join(format!("section-{}", i % 10));
+		fs::create_dir_all(&dir).unwrap();
+		fs::write(dir.join(format!("page-{}.md", i)), page(i)).unwrap();
+	}
+}
+
+fn page(i: usize) -> String {
+	let mut body = format!(
+		"---\ntitle: Page {i}\ntags: [bench, group-{}]\n---\n# Page {i}\n\n",
+		i % 5
+	);
+	for section in 0..8 {
+		body.push_str(&format!(
+			"## Section {section}\n\nSome *text* with a [[Page {}]] link and `inline code`.\n\n```rust\nfn page_{i}_{section}() -> usize {{\n    {section}\n}}\n```\n\n",
+			(i + section) % DOCUMENTS
+		));
+	}
+	body
+}
+
+// Rebuilds happen in the background, so the bench watches the output for the change to land
+fn wait_for(path: &Path, done: impl Fn(&str) -> bool) {
+	let started = Instant::now();
+	while !fs::read_to_string(path).is_ok_and(|html| done(&html)) {
+		assert!(
+			started.elapsed() < Duration::from_secs(60),
+			"{} was never rebuilt",
+			path.display()
+		);
+		thread::sleep(Duration::from_millis(2));
+	}
+}
+
+fn build(c: &mut Criterion) {
+	let root = std::env::temp_dir().join(format!("rum-bench-{}", std::process::id()));
+	let source = root.join("docs");
+	write_corpus(&source);
+
+	let mut group = c.benchmark_group(format!("build_{}_documents", DOCUMENTS));
+	group.sample_size(10);
+	for (name, args) in [
+		("sequential", &["--jobs", "1"][..]),
+		("parallel", &["--parallel"][..]),
+	] {
+		let output = root.join(name);
+		group.bench_function(name, |b| {
+			b.iter(|| {
+				let status = Command::new(env!("CARGO_BIN_EXE_rum"))
+					.args(["--quiet", "build", "--source"])
+					.arg(&source)
+					.arg("--output")
+					.arg(&output)
+					.args(args)
+					.status()
+					.unwrap();
+				assert!(status.success());
+			})
+		});
+	}
+	group.finish();
+
+	fs::remove_dir_all(&root).ok();
+}
+
+// One save can fire several events, and the server drops those arriving mid-rebuild, so the
+// stragglers of one save get to finish before the next is timed
+fn timed_saves(iters: u64, mut save: impl FnMut()) -> Duration {
+	let mut total = Duration::ZERO;
+	for _ in 0..iters {
+		thread::sleep(Duration::from_millis(250));
+		let started = Instant::now();
+		save();
+		total += started.elapsed();
+	}
+	total
+}
+
+// `rum dev` on the same corpus, from saving a file to its page being written. Editing a page
+// re-parses just that page, adding one rebuilds the whole site.
+fn rebuild(c: &mut Criterion) {
+	let root = std::env::temp_dir().join(format!("rum-bench-dev-{}", std::process::id()));
+	let source = root.join("docs");
+	let output = root.join("dist");
+	write_corpus(&source);
+
+	let port = 40000 + (std::process::id() % 20000) as u16;
+	let mut server = Command::new(env!("CARGO_BIN_EXE_rum"))
+		.args(["--quiet", "dev", "--source"])
+		.arg(&source)
+		.arg("--output")
+		.arg(&output)
+		.args(["--port", &port.to_string()])
+		.spawn()
+		.unwrap();
+	wait_for(&output.join("section-0/page-0.html"), |_| true);
+
+	let mut group = c.benchmark_group(format!("rebuild_{}_documents", DOCUMENTS));
+	group.sample_size(10);
+	let mut revision = 0;
+	group.bench_function("incremental", |b| {
+		b.iter_custom(|iters| {
+			timed_saves(iters, || {
+				revision += 1;
+				let marker = format!("revision-{}", revision);
+				fs::write(
+					source.join("section-0/page-0.md"),
+					format!("{}{}\n", page(0), marker),
+				)
+				.unwrap();
+				wait_for(&output.join("section-0/page-0.html"), |html| {
+					html.contains(&marker)
+				});
+			})
+		})
+	});
+	group.bench_function("full", |b| {
+		b.iter_custom(|iters| {
+			timed_saves(iters, || {
+				revision += 1;
+				let name = format!("section-0/extra-{}", revision);
+				fs::write(
+					source.join(&name).with_extension("md"),
+					"---\ntitle: Extra\n---\nAdded.\n",
+				)
+				.unwrap();
+				wait_for(&output.join(&name).with_extension("html"), |_| true);
+			})
+		})
+	});
+	group.finish();
+
+	server.kill().ok();
+	server.wait().ok();
+	fs::remove_dir_all(&root).ok();
+}
+
+criterion_group!(benches, build, rebuild);
+criterion_main!(benches);
@@ -0,0 +1,73 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use regex::Regex;
+use std::hint::black_box;
+use std::sync::LazyLock;
+
+const DOCUMENTS: usize = 1000;
+
+// Same patterns as ContentProcessor, which is out of reach from a bench of a binary crate
+const WIKI_LINK: &str = r"\[\[([^\]|#]+)(?:#([^\]|]+))?(?:\|([^\]]+))?\]\]";
+const SHORTCODE: &str = r"\{\{([^}]+)\}\}";
+const MD_LINK: &str = r"\[([^\]]+)\]\(([^)]+)\)";
+
+static WIKI_LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(WIKI_LINK).unwrap());
+static SHORTCODE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(SHORTCODE).unwrap());
+static MD_LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(MD_LINK).unwrap());
+
+fn page(i: usize) -> String {
+	let mut body = format!("---\ntitle: Page {i}\n---\n# Page {i}\n\n");
+	for section in 0..8 {
+		body.push_str(&format!(
+			"## Section {section}\n\nSee [[Page {}|the next page]], [the docs](page-{}.md) and {{{{ youtube id=\"abc\" }}}}.\n\n",
+			(i + section) % DOCUMENTS,
+			(i + 1) % DOCUMENTS
+		));
+	}
+	body
+}
+
+fn matches(content: &str, wiki: &Regex, shortcode: &Regex, md_link: &Regex) -> usize {
+	wiki.find_iter(content).count()
+		+ shortcode.find_iter(content).count()
+		+ md_link.find_iter(content).count()
+}
+
+// What every process_content and extract_links call paid before the patterns became statics
+fn regexes(c: &mut Criterion) {
+	let corpus: Vec<String> = (0..DOCUMENTS).map(page).collect();
+
+	let mut group = c.benchmark_group(format!("regex_{}_documents", DOCUMENTS));
+	group.sample_size(10);
+	group.bench_function("compiled_per_call", |b| {
+		b.iter(|| {
+			corpus
+				.iter()
+				.map(|content| {
+					let wiki = Regex::new(WIKI_LINK).unwrap();
+					let shortcode = Regex::new(SHORTCODE).unwrap();
+					let md_link = Regex::new(MD_LINK).unwrap();
+					matches(black_box(content), &wiki, &shortcode, &md_link)
+				})
+				.sum::<usize>()
+		})
+	});
+	group.bench_function("static", |b| {
+		b.iter(|| {
+			corpus
+				.iter()
+				.map(|content| {
+					matches(
+						black_box(content),
+						&WIKI_LINK_REGEX,
+						&SHORTCODE_REGEX,
+						&MD_LINK_REGEX,
+					)
+				})
+				.sum::<usize>()
+		})
+	});
+	group.finish();
+}
+
+criterion_group!(benches, regexes);
+criterion_main!(benches);
@@ -0,0 +1,191 @@
+use anyhow::{bail, Context, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::diagnostics::{Diagnostic, DiagnosticLevel, DiagnosticSink, PrettyDiagnosticSink};
+
+// The names the log file has always used
+fn level_name(level: DiagnosticLevel) -> &'static str {
+	match level {
+		DiagnosticLevel::Error => "error",
+		DiagnosticLevel::Warning => "warn",
+		DiagnosticLevel::Info => "info",
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+	Ndjson,
+	GithubActions,
+}
+
+impl LogFormat {
+	pub fn parse(name: &str) -> Result<Self> {
+		match name {
+			"ndjson" => Ok(LogFormat::Ndjson),
+			"github-actions" => Ok(LogFormat::GithubActions),
+			other => bail!(
+				"Unknown log format '{}', expected \"ndjson\" or \"github-actions\"",
+				other
+			),
+		}
+	}
+}
+
+// Build warnings and errors go to the console sink, and are mirrored into a file for CI tooling
+pub struct BuildLog {
+	sink: Option<(Mutex<File>, LogFormat)>,
+	console: Box<dyn DiagnosticSink>,
+	warnings: AtomicUsize,
+	errors: AtomicUsize,
+}
+
+impl Default for BuildLog {
+	fn default() -> Self {
+		Self {
+			sink: None,
+			console: Box::new(PrettyDiagnosticSink::new()),
+			warnings: AtomicUsize::new(0),
+			errors: AtomicUsize::new(0),
+		}
+	}
+}
+
+impl BuildLog {
+	pub fn create(path: &Path, format: LogFormat) -> Result<Self> {
+		let file = File::create(path)
+			.with_context(|| format!("Failed to create log file {}", path.display()))?;
+		Ok(Self {
+			sink: Some((Mutex::new(file), format)),
+			..Self::default()
+		})
+	}
+
+	pub fn with_sink(mut self, console: Box<dyn DiagnosticSink>) -> Self {
+		self.console = console;
+		self
+	}
+
+	pub fn warn(&self, code: &str, file: Option<&Path>, message: &str) {
+		self.report(Diagnostic {
+			level: DiagnosticLevel::Warning,
+			path: file.map(Path::to_path_buf),
+			code: Some(code.to_string()),
+			message: message.to_string(),
+		});
+	}
+
+	pub fn report(&self, diag: Diagnostic) {
+		self.record(&diag);
+		self.console.report(diag);
+	}
+
+	pub fn warning_count(&self) -> usize {
+		self.warnings.load(Ordering::Relaxed)
+	}
+
+	pub fn error_count(&self) -> usize {
+		self.errors.load(Ordering::Relaxed)
+	}
+
+	// Counted and written to the file only, for errors that propagate and get printed by main
+	pub fn record(&self, diag: &Diagnostic) {
+		match diag.level {
+			DiagnosticLevel::Error => self.errors.fetch_add(1, Ordering::Relaxed),
+			DiagnosticLevel::Warning => self.warnings.fetch_add(1, Ordering::Relaxed),
+			DiagnosticLevel::Info => 0,
+		};
+		let Some((sink, format)) = &self.sink else {
+			return;
+		};
+
+		let file = diag.path.as_deref();
+		let entry = match format {
+			LogFormat::Ndjson => serde_json::json!({
+				"level": level_name(diag.level),
+				"file": file.map(|f| f.to_string_lossy().to_string()),
+				"code": diag.code,
+				"message": diag.message,
+				"line": None::<usize>,
+			})
+			.to_string(),
+			LogFormat::GithubActions => github_annotation(diag.level, file, None, &diag.message),
+		};
+
+		// A broken log file must never hide the diagnostic itself, which already went to stderr
+		let mut sink = sink.lock().unwrap_or_else(|e| e.into_inner());
+		if let Err(e) = writeln!(sink, "{}", entry) {
+			warn!("Failed to write build log: {}", e);
+		}
+	}
+}
+
+fn github_annotation(
+	level: DiagnosticLevel,
+	file: Option<&Path>,
+	line: Option<usize>,
+	message: &str,
+) -> String {
+	let command = match level {
+		DiagnosticLevel::Error => "error",
+		DiagnosticLevel::Warning => "warning",
+		DiagnosticLevel::Info => "notice",
+	};
+
+	let mut properties = Vec::new();
+	if let Some(file) = file {
+		properties.push(format!("file={}", escape_property(&file.to_string_lossy())));
+	}
+	if let Some(line) = line {
+		properties.push(format!("line={}", line));
+	}
+
+	if properties.is_empty() {
+		format!("::{}::{}", command, escape_data(message))
+	} else {
+		format!(
+			"::{} {}::{}",
+			command,
+			properties.join(","),
+			escape_data(message)
+		)
+	}
+}
+
+fn escape_data(value: &str) -> String {
+	value
+		.replace('%', "%25")
+		.replace('\r', "%0D")
+		.replace('\n', "%0A")
+}
+
+fn escape_property(value: &str) -> String {
+	escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_github_annotation() {
+		let annotation = github_annotation(
+			DiagnosticLevel::Warning,
+			Some(Path::new("guide/setup.md")),
+			Some(3),
+			"Failed to parse: bad\nfrontmatter",
+		);
+		assert_eq!(
+			annotation,
+			"::warning file=guide/setup.md,line=3::Failed to parse: bad%0Afrontmatter"
+		);
+		assert_eq!(
+			github_annotation(DiagnosticLevel::Error, None, None, "100% broken"),
+			"::error::100%25 broken"
+		);
+	}
+}
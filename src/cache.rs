@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing::debug;
+
+use crate::config::Config;
+use crate::content::Document;
+
+const MANIFEST: &str = ".rum-cache.json";
+const CACHE_DIR: &str = ".rum-cache";
+
+// SHA-256 hex digests a cached document was parsed with, any mismatch means a re-parse
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+	pub content_hash: String,
+	pub config_hash: String,
+	pub template_hash: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+	files: BTreeMap<PathBuf, CacheEntry>,
+}
+
+// Parsed documents from the last successful build, keyed by the SHA-256 of their source
+pub struct BuildCache {
+	output_dir: PathBuf,
+	config_hash: String,
+	template_hash: String,
+	previous: Manifest,
+	current: Mutex<BTreeMap<PathBuf, CacheEntry>>,
+	parsed: AtomicUsize,
+}
+
+impl BuildCache {
+	pub fn load(output_dir: &Path, config: &Config, template: &str) -> Result<Self> {
+		// The rum version goes into the config hash, a new release may parse differently
+		let mut hasher = Sha256::new();
+		hasher.update(env!("CARGO_PKG_VERSION"));
+		hasher.update(toml::to_string(config)?);
+		let config_hash = format!("{:x}", hasher.finalize());
+		let template_hash = format!("{:x}", Sha256::digest(template));
+
+		let previous = fs::read_to_string(output_dir.join(MANIFEST))
+			.ok()
+			.and_then(|manifest| serde_json::from_str::<Manifest>(&manifest).ok())
+			.unwrap_or_default();
+
+		Ok(Self {
+			output_dir: output_dir.to_path_buf(),
+			config_hash,
+			template_hash,
+			previous,
+			current: Mutex::new(BTreeMap::new()),
+			parsed: AtomicUsize::new(0),
+		})
+	}
+
+	// Empties the output directory but keeps the cache for the next build
+	pub fn clean(output_dir: &Path) -> Result<()> {
+		for entry in fs::read_dir(output_dir)? {
+			let path = entry?.path();
+			if path.ends_with(MANIFEST) || path.ends_with(CACHE_DIR) {
+				continue;
+			}
+			if path.is_dir() {
+				fs::remove_dir_all(&path)?;
+			} else {
+				fs::remove_file(&path)?;
+			}
+		}
+		Ok(())
+	}
+
+	pub fn document(
+		&self,
+		path: &Path,
+		relative_path: &Path,
+		parse: impl FnOnce() -> Result<Document>,
+	) -> Result<Document> {
+		let source =
+			fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+		let entry = CacheEntry {
+			content_hash: format!("{:x}", Sha256::digest(&source)),
+			config_hash: self.config_hash.clone(),
+			template_hash: self.template_hash.clone(),
+		};
+		let sidecar = self.sidecar(relative_path, &entry.content_hash);
+
+		let cached = if self.previous.files.get(relative_path) == Some(&entry) {
+			fs::read_to_string(&sidecar)
+				.ok()
+				.and_then(|json| serde_json::from_str::<Document>(&json).ok())
+		} else {
+			None
+		};
+
+		let document = match cached {
+			Some(document) => document,
+			None => {
+				self.parsed.fetch_add(1, Ordering::Relaxed);
+				let document = parse()?;
+				fs::create_dir_all(self.output_dir.join(CACHE_DIR))?;
+				fs::write(&sidecar, serde_json::to_string(&document)?)?;
+				document
+			}
+		};
+
+		self.current
+			.lock()
+			.unwrap()
+			.insert(relative_path.to_path_buf(), entry);
+		Ok(document)
+	}
+
+	// Number of documents that had to be parsed rather than read from the cache
+	pub fn parsed(&self) -> usize {
+		self.parsed.load(Ordering::Relaxed)
+	}
+
+	// Documents read back from the cache instead of parsed
+	pub fn reused(&self) -> usize {
+		self.current
+			.lock()
+			.unwrap()
+			.len()
+			.saturating_sub(self.parsed())
+	}
+
+	// Only called after a successful build, a failed one leaves the previous manifest in place
+	pub fn save(&self) -> Result<()> {
+		let manifest = Manifest {
+			files: self.current.lock().unwrap().clone(),
+		};
+		debug!(
+			"Cached {} documents, {} parsed",
+			manifest.files.len(),
+			self.parsed()
+		);
+		fs::create_dir_all(&self.output_dir)?;
+		fs::write(
+			self.output_dir.join(MANIFEST),
+			serde_json::to_string(&manifest)?,
+		)?;
+
+		// Sidecars of deleted or since changed files are no longer reachable
+		let live: Vec<_> = manifest
+			.files
+			.iter()
+			.map(|(path, entry)| self.sidecar(path, &entry.content_hash))
+			.collect();
+		if let Ok(entries) = fs::read_dir(self.output_dir.join(CACHE_DIR)) {
+			for entry in entries.flatten() {
+				if entry.path().is_file() && !live.contains(&entry.path()) {
+					fs::remove_file(entry.path())?;
+				}
+			}
+		}
+		Ok(())
+	}
+
+	pub fn is_cache_path(relative: &Path) -> bool {
+		relative.starts_with(MANIFEST) || relative.starts_with(CACHE_DIR)
+	}
+
+	// Named after both path and content so a sidecar can never belong to another version of the file
+	fn sidecar(&self, relative_path: &Path, hash: &str) -> PathBuf {
+		let mut hasher = Sha256::new();
+		hasher.update(relative_path.to_string_lossy().as_bytes());
+		hasher.update(hash);
+		self.output_dir
+			.join(CACHE_DIR)
+			.join(format!("{:x}.json", hasher.finalize()))
+	}
+}
@@ -0,0 +1,510 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::content::{document_locale, translation_key, Document};
+use crate::diagnostics::{Diagnostic, DiagnosticLevel, DiagnosticSink};
+use crate::generator::NavigationItem;
+use crate::graph::DocumentGraph;
+use crate::schema::FrontmatterError;
+
+#[derive(Debug, Clone)]
+pub struct LinkError {
+	pub source_path: PathBuf,
+	pub target: String,
+	pub context: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+	Warning,
+	Error,
+}
+
+impl Severity {
+	fn as_str(self) -> &'static str {
+		match self {
+			Severity::Warning => "warning",
+			Severity::Error => "error",
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct LintIssue {
+	pub path: PathBuf,
+	pub severity: Severity,
+	pub kind: &'static str,
+	pub field: String,
+	pub message: String,
+}
+
+pub fn lint_links(errors: &[LinkError]) -> Vec<LintIssue> {
+	errors
+		.iter()
+		.map(|error| LintIssue {
+			path: error.source_path.clone(),
+			severity: Severity::Error,
+			kind: "broken-link",
+			field: "links".to_string(),
+			message: format!("{} does not resolve to a page", error.target),
+		})
+		.collect()
+}
+
+pub fn lint_frontmatter_errors(errors: &[FrontmatterError]) -> Vec<LintIssue> {
+	errors
+		.iter()
+		.map(|error| LintIssue {
+			path: error.document_path.clone(),
+			severity: Severity::Warning,
+			kind: "schema",
+			field: error.field.clone(),
+			message: error.message.clone(),
+		})
+		.collect()
+}
+
+pub fn lint_tags(documents: &[Document]) -> Vec<LintIssue> {
+	let mut issues = Vec::new();
+	for doc in documents {
+		let Some(tags) = &doc.frontmatter.tags else {
+			continue;
+		};
+		if tags.is_empty() {
+			issues.push(LintIssue {
+				path: doc.relative_path.clone(),
+				severity: Severity::Warning,
+				kind: "empty-tags",
+				field: "tags".to_string(),
+				message: "tags is set but lists nothing".to_string(),
+			});
+		} else if tags.iter().any(|tag| tag.trim().is_empty()) {
+			issues.push(LintIssue {
+				path: doc.relative_path.clone(),
+				severity: Severity::Warning,
+				kind: "empty-tag",
+				field: "tags".to_string(),
+				message: "contains a blank tag".to_string(),
+			});
+		}
+	}
+	issues
+}
+
+// Drafts are left out of normal builds, so a published page linking to one ends up with a dead link.
+// Backlinks name the linking page by title, or by source path when it has none.
+pub fn lint_draft_links(documents: &[Document]) -> Vec<LintIssue> {
+	let mut issues = Vec::new();
+	for draft in documents
+		.iter()
+		.filter(|d| d.frontmatter.draft == Some(true))
+	{
+		for backlink in &draft.backlinks {
+			let source = documents.iter().find(|d| {
+				d.frontmatter.title.as_deref() == Some(backlink.as_str())
+					|| d.relative_path.to_string_lossy() == backlink.as_str()
+			});
+			let Some(source) = source.filter(|s| s.frontmatter.draft != Some(true)) else {
+				continue;
+			};
+			issues.push(LintIssue {
+				path: source.relative_path.clone(),
+				severity: Severity::Warning,
+				kind: "draft-link",
+				field: "links".to_string(),
+				message: format!(
+					"links to draft {}, which is only built with --drafts",
+					draft.relative_path.display()
+				),
+			});
+		}
+	}
+	issues
+}
+
+// Default locale pages with no translation, per configured locale, as paths without the locale directory
+pub fn untranslated_pages(documents: &[Document], config: &Config) -> Vec<(String, Vec<PathBuf>)> {
+	let default_locale = &config.i18n.default_locale;
+	let originals: Vec<&Path> = documents
+		.iter()
+		.filter(|d| document_locale(d, config) == default_locale)
+		.map(translation_key)
+		.collect();
+
+	let mut report = Vec::new();
+	for locale in config.i18n.locales.iter().filter(|l| *l != default_locale) {
+		let translated: HashSet<&Path> = documents
+			.iter()
+			.filter(|d| document_locale(d, config) == locale)
+			.map(translation_key)
+			.collect();
+		let mut missing: Vec<PathBuf> = originals
+			.iter()
+			.filter(|key| !translated.contains(*key))
+			.map(|key| key.to_path_buf())
+			.collect();
+		missing.sort();
+		report.push((locale.clone(), missing));
+	}
+	report
+}
+
+pub fn print_untranslated_report(report: &[(String, Vec<PathBuf>)]) {
+	for (locale, missing) in report {
+		println!("{}: {} untranslated page(s)", locale, missing.len());
+		for path in missing {
+			println!("  {}", path.display());
+		}
+	}
+}
+
+// Titles are compared case-insensitively, every document sharing one gets an issue
+pub fn lint_duplicate_titles(documents: &[Document]) -> Vec<LintIssue> {
+	let mut by_title: HashMap<String, Vec<&Document>> = HashMap::new();
+	for doc in documents {
+		if let Some(title) = &doc.frontmatter.title {
+			by_title
+				.entry(title.trim().to_lowercase())
+				.or_default()
+				.push(doc);
+		}
+	}
+
+	let mut issues = Vec::new();
+	for doc in documents {
+		let Some(title) = &doc.frontmatter.title else {
+			continue;
+		};
+		let others: Vec<String> = by_title[&title.trim().to_lowercase()]
+			.iter()
+			.filter(|other| other.relative_path != doc.relative_path)
+			.map(|other| other.relative_path.display().to_string())
+			.collect();
+		if !others.is_empty() {
+			issues.push(LintIssue {
+				path: doc.relative_path.clone(),
+				severity: Severity::Warning,
+				kind: "duplicate-title",
+				field: "title".to_string(),
+				message: format!("\"{}\" is also used by {}", title, others.join(", ")),
+			});
+		}
+	}
+	issues
+}
+
+// A field counts as missing when it is absent, null, blank or a list with nothing but blanks
+pub fn lint_required_fields(documents: &[Document], required: &[String]) -> Vec<LintIssue> {
+	let mut issues = Vec::new();
+	for doc in documents {
+		let frontmatter = serde_json::to_value(&doc.frontmatter).unwrap_or_default();
+		for field in required {
+			// author is read into authors, so requiring either means the same
+			let key = if field == "author" {
+				"authors"
+			} else {
+				field.as_str()
+			};
+			if !is_present(frontmatter.get(key)) {
+				issues.push(LintIssue {
+					path: doc.relative_path.clone(),
+					severity: Severity::Error,
+					kind: "missing-field",
+					field: field.clone(),
+					message: "required by lint.required_fields".to_string(),
+				});
+			}
+		}
+	}
+	issues
+}
+
+fn is_present(value: Option<&serde_json::Value>) -> bool {
+	match value {
+		None | Some(serde_json::Value::Null) => false,
+		Some(serde_json::Value::String(value)) => !value.trim().is_empty(),
+		Some(serde_json::Value::Array(values)) => values.iter().any(|v| is_present(Some(v))),
+		Some(_) => true,
+	}
+}
+
+pub fn print_lint_report(issues: &[LintIssue]) {
+	let mut by_file: BTreeMap<&Path, Vec<&LintIssue>> = BTreeMap::new();
+	for issue in issues {
+		by_file.entry(&issue.path).or_default().push(issue);
+	}
+
+	let kind_width = issues.iter().map(|i| i.kind.len()).max().unwrap_or(0);
+	let field_width = issues.iter().map(|i| i.field.len()).max().unwrap_or(0);
+	for (path, issues) in by_file {
+		println!("{}", path.display());
+		for issue in issues {
+			println!(
+				"  {:<7}  {:<kind_width$}  {:<field_width$}  {}",
+				issue.severity.as_str(),
+				issue.kind,
+				issue.field,
+				issue.message
+			);
+		}
+	}
+}
+
+// `--output-format json`, the kind doubles as the diagnostic code
+pub fn report_lint_issues(issues: &[LintIssue], sink: &dyn DiagnosticSink) {
+	for issue in issues {
+		sink.report(Diagnostic {
+			level: match issue.severity {
+				Severity::Warning => DiagnosticLevel::Warning,
+				Severity::Error => DiagnosticLevel::Error,
+			},
+			path: Some(issue.path.clone()),
+			code: Some(issue.kind.to_string()),
+			message: format!("{}: {}", issue.field, issue.message),
+		});
+	}
+}
+
+pub fn report_untranslated(report: &[(String, Vec<PathBuf>)], sink: &dyn DiagnosticSink) {
+	for (locale, missing) in report {
+		for path in missing {
+			sink.report(Diagnostic {
+				level: DiagnosticLevel::Info,
+				path: Some(path.clone()),
+				code: Some("untranslated".to_string()),
+				message: format!("No {} translation", locale),
+			});
+		}
+	}
+}
+
+// Pages holds the navigation's leaf items in order, as returned by NavigationTree::flatten
+pub fn find_orphans(graph: &DocumentGraph, pages: &[&NavigationItem]) -> Vec<LintIssue> {
+	let reachable: HashSet<&Path> = pages.iter().map(|item| item.path.as_path()).collect();
+
+	let mut issues = Vec::new();
+	for doc in graph.orphans() {
+		if doc.frontmatter.hidden == Some(true) {
+			issues.push(LintIssue {
+				path: doc.relative_path.clone(),
+				severity: Severity::Warning,
+				kind: "hidden-orphan",
+				field: "hidden".to_string(),
+				message: "hidden and unreachable, consider deleting".to_string(),
+			});
+		} else if !reachable.contains(doc.relative_path.as_path()) {
+			issues.push(LintIssue {
+				path: doc.relative_path.clone(),
+				severity: Severity::Warning,
+				kind: "orphan",
+				field: "links".to_string(),
+				message: "not reachable from navigation or any link".to_string(),
+			});
+		}
+	}
+
+	issues
+}
+
+// SARIF 2.1.0 log for editors and code scanning, issues are about a page as a whole so results
+// point at the file without a region
+pub fn to_sarif(issues: &[LintIssue], source_dir: &Path) -> String {
+	let results: Vec<_> = issues
+		.iter()
+		.map(|issue| {
+			let uri = source_dir
+				.join(&issue.path)
+				.to_string_lossy()
+				.replace('\\', "/");
+			serde_json::json!({
+				"ruleId": issue.kind,
+				"level": issue.severity.as_str(),
+				"message": { "text": format!("{}: {}", issue.field, issue.message) },
+				"locations": [{
+					"physicalLocation": { "artifactLocation": { "uri": uri } },
+				}],
+			})
+		})
+		.collect();
+
+	serde_json::json!({
+		"$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+		"version": "2.1.0",
+		"runs": [{
+			"tool": {
+				"driver": {
+					"name": "rum",
+					"version": env!("CARGO_PKG_VERSION"),
+				},
+			},
+			"results": results,
+		}],
+	})
+	.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::content::Frontmatter;
+
+	fn doc(path: &str, frontmatter: Frontmatter) -> Document {
+		Document {
+			frontmatter,
+			path: PathBuf::from(path),
+			relative_path: PathBuf::from(path),
+			..Default::default()
+		}
+	}
+
+	fn titled(path: &str, title: &str) -> Document {
+		doc(
+			path,
+			Frontmatter {
+				title: Some(title.to_string()),
+				..Default::default()
+			},
+		)
+	}
+
+	#[test]
+	fn test_lint_tags() {
+		let tagged = |tags: &[&str]| Frontmatter {
+			tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+			..Default::default()
+		};
+		let documents = vec![
+			doc("ok.md", tagged(&["rust"])),
+			doc("none.md", Frontmatter::default()),
+			doc("empty.md", tagged(&[])),
+			doc("blank.md", tagged(&["rust", "  "])),
+		];
+
+		let issues = lint_tags(&documents);
+		let kinds: Vec<_> = issues
+			.iter()
+			.map(|i| (i.path.to_str().unwrap(), i.kind))
+			.collect();
+		assert_eq!(
+			kinds,
+			vec![("empty.md", "empty-tags"), ("blank.md", "empty-tag")]
+		);
+		assert!(issues.iter().all(|i| i.severity == Severity::Warning));
+	}
+
+	#[test]
+	fn test_lint_draft_links() {
+		let mut draft = titled("wip.md", "Work in progress");
+		draft.frontmatter.draft = Some(true);
+		draft.backlinks = vec![
+			"Guide".to_string(),
+			"notes.md".to_string(),
+			"Other draft".to_string(),
+		];
+		let mut other_draft = titled("other.md", "Other draft");
+		other_draft.frontmatter.draft = Some(true);
+		let documents = vec![
+			titled("guide.md", "Guide"),
+			doc("notes.md", Frontmatter::default()),
+			other_draft,
+			draft,
+		];
+
+		let issues = lint_draft_links(&documents);
+		let paths: Vec<_> = issues.iter().map(|i| i.path.to_str().unwrap()).collect();
+		assert_eq!(paths, vec!["guide.md", "notes.md"]);
+		assert!(issues[0].message.contains("wip.md"));
+		assert!(issues.iter().all(|i| i.severity == Severity::Warning));
+	}
+
+	#[test]
+	fn test_lint_duplicate_titles() {
+		let documents = vec![
+			titled("a.md", "Setup"),
+			titled("b.md", "Usage"),
+			titled("guide/setup.md", " setup "),
+			doc("untitled.md", Frontmatter::default()),
+		];
+
+		let issues = lint_duplicate_titles(&documents);
+		assert_eq!(issues.len(), 2);
+		assert_eq!(issues[0].path, Path::new("a.md"));
+		assert_eq!(
+			issues[0].message,
+			"\"Setup\" is also used by guide/setup.md"
+		);
+		assert_eq!(issues[1].path, Path::new("guide/setup.md"));
+		assert!(issues[1].message.ends_with("is also used by a.md"));
+	}
+
+	#[test]
+	fn test_lint_required_fields() {
+		let mut complete = titled("complete.md", "Complete");
+		complete.frontmatter.authors = Some(vec!["night0721".to_string()]);
+		complete
+			.frontmatter
+			.extra
+			.insert("owner".to_string(), serde_yaml::Value::from("docs-team"));
+		let mut blank = titled("blank.md", "Blank");
+		blank.frontmatter.authors = Some(vec![" ".to_string()]);
+
+		let required = vec![
+			"title".to_string(),
+			"author".to_string(),
+			"owner".to_string(),
+		];
+		let issues = lint_required_fields(&[complete, blank], &required);
+		let missing: Vec<_> = issues
+			.iter()
+			.map(|i| (i.path.to_str().unwrap(), i.field.as_str()))
+			.collect();
+		assert_eq!(missing, vec![("blank.md", "author"), ("blank.md", "owner")]);
+		assert!(issues.iter().all(|i| i.severity == Severity::Error));
+	}
+
+	#[test]
+	fn test_lint_links() {
+		let issues = lint_links(&[LinkError {
+			source_path: PathBuf::from("index.md"),
+			target: "missing.html".to_string(),
+			context: "See [missing](missing.html)".to_string(),
+		}]);
+		assert_eq!(issues[0].kind, "broken-link");
+		assert_eq!(issues[0].severity, Severity::Error);
+		assert_eq!(issues[0].message, "missing.html does not resolve to a page");
+	}
+
+	#[test]
+	fn test_to_sarif() {
+		let mut issues = lint_links(&[LinkError {
+			source_path: PathBuf::from("index.md"),
+			target: "missing.html".to_string(),
+			context: "See [missing](missing.html)".to_string(),
+		}]);
+		issues.push(LintIssue {
+			path: PathBuf::from("guide/old.md"),
+			severity: Severity::Warning,
+			kind: "hidden-orphan",
+			field: "hidden".to_string(),
+			message: "hidden and unreachable, consider deleting".to_string(),
+		});
+		let sarif: serde_json::Value =
+			serde_json::from_str(&to_sarif(&issues, Path::new("docs"))).unwrap();
+		let results = &sarif["runs"][0]["results"];
+
+		assert_eq!(results[0]["ruleId"], "broken-link");
+		assert_eq!(results[0]["level"], "error");
+		assert_eq!(
+			results[0]["message"]["text"],
+			"links: missing.html does not resolve to a page"
+		);
+		assert_eq!(
+			results[0]["locations"][0]["physicalLocation"],
+			serde_json::json!({ "artifactLocation": { "uri": "docs/index.md" } })
+		);
+
+		assert_eq!(results[1]["ruleId"], "hidden-orphan");
+		assert_eq!(results[1]["level"], "warning");
+	}
+}
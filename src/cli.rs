@@ -1,11 +1,21 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::{Parser, Subcommand};
+use notify::{RecursiveMode, Watcher};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{error, info, warn};
 
+use crate::buildlog::{BuildLog, LogFormat};
+use crate::check::{self, Severity};
 use crate::config::Config;
+use crate::diagnostics::{diagnostic_sink, Diagnostic, DiagnosticLevel};
+use crate::diff;
+use crate::export::Exporter;
 use crate::generator::Generator;
-use crate::server::DevServer;
+use crate::linkcheck::{self, LinkChecker};
+use crate::scaffold;
+use crate::server::{self, DevServer, StaticServer};
 
 #[derive(Parser)]
 #[command(name = "rum")]
@@ -14,6 +24,18 @@ use crate::server::DevServer;
 pub struct Cli {
 	#[command(subcommand)]
 	pub command: Commands,
+
+	/// Show debug output
+	#[arg(short, long, global = true)]
+	pub verbose: bool,
+
+	/// Only show warnings and errors
+	#[arg(short, long, global = true, conflicts_with = "verbose")]
+	pub quiet: bool,
+
+	/// Diagnostics format (text, json), json writes one object per line to stdout
+	#[arg(long, global = true, default_value = "text")]
+	pub output_format: String,
 }
 
 #[derive(Subcommand)]
@@ -28,13 +50,65 @@ pub enum Commands {
 		#[arg(short, long, default_value = "dist")]
 		output: PathBuf,
 
-		/// Export formats (html, pdf, man)
+		/// Export formats, comma-separated (html, pdf, man, latex, epub, json)
 		#[arg(short, long, default_value = "html")]
 		format: String,
 
 		/// Configuration file
 		#[arg(short, long)]
 		config: Option<PathBuf>,
+
+		/// Also write build warnings and errors to this file
+		#[arg(long)]
+		log_file: Option<PathBuf>,
+
+		/// Log file format (ndjson, github-actions)
+		#[arg(long, default_value = "ndjson")]
+		log_format: String,
+
+		/// Keep rebuilding on source changes without starting a server
+		#[arg(short, long)]
+		watch: bool,
+
+		/// Parse and render in parallel regardless of build.parallel_threshold
+		#[arg(long)]
+		parallel: bool,
+
+		/// Number of worker threads for parallel parsing and rendering (default: one per core)
+		#[arg(short, long)]
+		jobs: Option<usize>,
+
+		/// Print the build summary as JSON
+		#[arg(long)]
+		json: bool,
+
+		/// Also write document counts and phase timings to build-stats.json in the output directory
+		#[arg(long)]
+		stats_json: bool,
+
+		/// Reuse parsed documents from the previous build for unchanged sources
+		#[arg(long)]
+		incremental: bool,
+
+		/// Directory with a custom base.html template
+		#[arg(long)]
+		template_dir: Option<PathBuf>,
+
+		/// Fail the build on broken internal links instead of warning
+		#[arg(long)]
+		strict: bool,
+
+		/// Write content.json and navigation.json instead of HTML pages
+		#[arg(long)]
+		no_html: bool,
+
+		/// Also write a print/<slug>.html copy of every page without the sidebar
+		#[arg(long)]
+		print: bool,
+
+		/// Include documents marked draft: true
+		#[arg(long, alias = "include-drafts")]
+		drafts: bool,
 	},
 
 	/// Start development server
@@ -47,9 +121,121 @@ pub enum Commands {
 		#[arg(short, long, default_value_t = 3000)]
 		port: u16,
 
+		/// Output directory (default: a temporary directory removed on exit)
+		#[arg(short, long)]
+		output: Option<PathBuf>,
+
+		/// Configuration file
+		#[arg(short, long)]
+		config: Option<PathBuf>,
+
+		/// Directory with a custom base.html template
+		#[arg(long)]
+		template_dir: Option<PathBuf>,
+
+		/// Include documents marked draft: true
+		#[arg(long, alias = "include-drafts")]
+		drafts: bool,
+	},
+
+	/// Serve an already built output directory without rebuilding
+	Serve {
+		/// Output directory of the last build (default: dist/)
+		#[arg(short, long, default_value = "dist")]
+		dir: PathBuf,
+
+		/// Port to serve on
+		#[arg(short, long, default_value_t = 8080)]
+		port: u16,
+
+		/// PEM certificate chain, serves over HTTPS together with --tls-key
+		#[arg(long, requires = "tls_key")]
+		tls_cert: Option<PathBuf>,
+
+		/// PEM private key for --tls-cert
+		#[arg(long, requires = "tls_cert")]
+		tls_key: Option<PathBuf>,
+
+		/// Configuration file
+		#[arg(short, long)]
+		config: Option<PathBuf>,
+	},
+
+	/// Check documentation for problems
+	Check {
+		/// Source directory (default: docs/)
+		#[arg(short, long, default_value = "docs")]
+		source: PathBuf,
+
+		/// Output format (text, sarif)
+		#[arg(short, long, default_value = "text")]
+		format: String,
+
+		/// Configuration file
+		#[arg(short, long)]
+		config: Option<PathBuf>,
+	},
+
+	/// Validate content without building
+	Lint {
+		/// Source directory (default: docs/)
+		#[arg(short, long, default_value = "docs")]
+		source: PathBuf,
+
+		/// Configuration file
+		#[arg(short, long)]
+		config: Option<PathBuf>,
+
+		/// Fail on warnings as well as errors
+		#[arg(long)]
+		strict: bool,
+
+		/// List pages missing a translation in each configured locale instead
+		#[arg(long)]
+		i18n: bool,
+	},
+
+	/// Check that external links still resolve
+	CheckLinks {
+		/// Source directory (default: docs/)
+		#[arg(short, long, default_value = "docs")]
+		source: PathBuf,
+
+		/// Configuration file
+		#[arg(short, long)]
+		config: Option<PathBuf>,
+	},
+
+	/// Export the links between documents as a graph
+	Graph {
+		/// Source directory (default: docs/)
+		#[arg(short, long, default_value = "docs")]
+		source: PathBuf,
+
 		/// Configuration file
 		#[arg(short, long)]
 		config: Option<PathBuf>,
+
+		/// Output file (default: stdout)
+		#[arg(short, long)]
+		output: Option<PathBuf>,
+
+		/// Output format (dot, json)
+		#[arg(short, long, default_value = "dot")]
+		format: String,
+	},
+
+	/// Show which pages differ between two build outputs
+	Diff {
+		/// Output directory of the earlier build
+		old: PathBuf,
+
+		/// Output directory of the newer build
+		new: PathBuf,
+
+		/// Output format (summary, unified-diff)
+		#[arg(short, long, default_value = "summary")]
+		format: String,
 	},
 
 	/// Initialize a new Rum project
@@ -57,78 +243,333 @@ pub enum Commands {
 		/// Directory to initialize
 		#[arg(default_value = ".")]
 		dir: PathBuf,
+
+		/// Project template (minimal, full, api)
+		#[arg(short, long, default_value = "full")]
+		template: String,
+
+		/// List the available project templates
+		#[arg(long)]
+		list_templates: bool,
+	},
+
+	/// Install generated man pages into the system man path
+	InstallMan {
+		/// Installation prefix (default: /usr/local)
+		#[arg(short, long)]
+		prefix: Option<PathBuf>,
+
+		/// Output directory of the last build (default: dist/)
+		#[arg(short, long, default_value = "dist")]
+		output: PathBuf,
+
+		/// List the copy operations without performing them
+		#[arg(long)]
+		dry_run: bool,
+
+		/// Configuration file
+		#[arg(short, long)]
+		config: Option<PathBuf>,
 	},
 }
 
 impl Cli {
 	pub async fn run(self) -> Result<()> {
+		let sink = diagnostic_sink(&self.output_format)?;
+		let json_output = self.output_format == "json";
 		match self.command {
 			Commands::Build {
 				source,
 				output,
 				format,
 				config,
+				log_file,
+				log_format,
+				watch,
+				parallel,
+				jobs,
+				json,
+				stats_json,
+				incremental,
+				template_dir,
+				strict,
+				drafts,
+				no_html,
+				print,
 			} => {
+				let log_format = LogFormat::parse(&log_format)?;
+				if let Some(jobs) = jobs {
+					if jobs == 0 {
+						bail!("--jobs must be at least 1");
+					}
+					rayon::ThreadPoolBuilder::new()
+						.num_threads(jobs)
+						.build_global()?;
+				}
+				let log = match &log_file {
+					Some(path) => BuildLog::create(path, log_format)?,
+					None => BuildLog::default(),
+				}
+				.with_sink(sink);
+
 				let output_clone = output.clone();
-				let generator = Generator::new(source, output, config)?;
-				generator.build(&format).await?;
-				println!("Build complete. Output: {}", output_clone.display());
+				let mut generator = Generator::new(source.clone(), output, config)?
+					.with_log(log)
+					.with_parallel(parallel)
+					.with_incremental(incremental)
+					.with_strict(strict)
+					.with_include_drafts(drafts)
+					.with_production(true)
+					.with_no_html(no_html)
+					.with_print(print)
+					.with_template_dir(template_dir.as_deref())?;
+				if watch {
+					generator = generator.with_documents(Arc::default());
+				}
+				let summary = match generator.build(&format).await {
+					Ok(summary) => summary,
+					Err(e) => {
+						let diag = Diagnostic {
+							level: DiagnosticLevel::Error,
+							path: None,
+							code: None,
+							message: format!("{:#}", e),
+						};
+						// main prints the error on stderr, tooling reading stdout needs it there too
+						if json_output {
+							generator.log().report(diag);
+						} else {
+							generator.log().record(&diag);
+						}
+						return Err(e);
+					}
+				};
+
+				if json {
+					println!("{}", serde_json::to_string(&summary)?);
+				} else {
+					info!("Build complete. Output: {}", output_clone.display());
+					summary.print();
+				}
+				if stats_json {
+					std::fs::write(
+						output_clone.join("build-stats.json"),
+						serde_json::to_string_pretty(&summary.stats)?,
+					)?;
+				}
+				summary.check_warnings()?;
+
+				if watch {
+					watch_and_rebuild(&generator, &source, &format).await?;
+				}
 			}
 			Commands::Dev {
 				source,
 				port,
+				output,
 				config,
+				template_dir,
+				drafts,
 			} => {
-				let server = DevServer::new(source, port, config)?;
+				let server = DevServer::new(source, port, config, template_dir, drafts)?
+					.with_output_dir(output)
+					.with_output_format(self.output_format);
 				server.serve().await?;
 			}
-			Commands::Init { dir } => {
-				// Create docs directory
-				let docs_dir = dir.join("docs");
-				fs::create_dir_all(&docs_dir)?;
-				fs::create_dir_all(docs_dir.join("latest"))?;
-
-				// Create example docs
-				let example_content = r#"---
-title: Welcome to Rum
-tags: [getting-started]
----
-
-# Welcome to Rum
-This is your first documentation page. Edit this file to get started!
-
-## Getting Started
-1. Edit this file
-2. Configure the site in \`rum.toml\`
-3. Add more `.md` files to the `docs/` directory
-4. Run `rum dev` to preview
-5. Run `rum build` to generate static site
-
-## Shortcodes
-Use shortcodes for special content:
-{{note}}
-This is a note block!
-{{/note}}
-"#;
-				fs::write(docs_dir.join("index.md"), example_content)?;
-
-				let latest_content = r#"---
-title: Latest Version
-version: latest
-tags: [docs]
----
-
-# Documentation for Latest Version
-
-Lorem ipsum dolor sit amet, consectetur adipiscing elit. Sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.
-"#;
-				fs::write(docs_dir.join("latest").join("index.md"), latest_content)?;
-				let config = Config::default();
-				config.save(&dir.join("rum.toml"))?;
-
-				println!("Initialized project in {}", dir.display());
+			Commands::Serve {
+				dir,
+				port,
+				tls_cert,
+				tls_key,
+				config,
+			} => {
+				let config = Config::load(config.as_deref())?;
+				let mut server = StaticServer::new(dir, port)
+					.with_directory_listing(config.server.directory_listing);
+				if let (Some(cert), Some(key)) = (tls_cert, tls_key) {
+					server = server.with_tls(cert, key);
+				}
+				server.serve().await?;
+			}
+			Commands::Check {
+				source,
+				format,
+				config,
+			} => {
+				if !matches!(format.as_str(), "text" | "sarif") {
+					bail!(
+						"Unknown check format '{}', expected \"text\" or \"sarif\"",
+						format
+					);
+				}
+				let generator = Generator::new(source.clone(), PathBuf::from("dist"), config)?;
+				let issues = generator.check()?;
+
+				if format == "sarif" {
+					println!("{}", check::to_sarif(&issues, &source));
+				} else {
+					for issue in &issues {
+						println!("{}: {}", issue.path.display(), issue.message);
+					}
+				}
+				if !issues.is_empty() {
+					bail!("Check found {} issue(s)", issues.len());
+				}
+				if format == "text" {
+					info!("No issues found");
+				}
+			}
+			Commands::Lint {
+				source,
+				config,
+				strict,
+				i18n,
+			} => {
+				// Drafts are linted too, links to them from published pages get a warning
+				let generator = Generator::new(source, PathBuf::from("dist"), config)?
+					.with_include_drafts(true);
+				if i18n {
+					let report = generator.untranslated()?;
+					if json_output {
+						check::report_untranslated(&report, sink.as_ref());
+					} else {
+						check::print_untranslated_report(&report);
+					}
+					let missing: usize = report.iter().map(|(_, pages)| pages.len()).sum();
+					if strict && missing > 0 {
+						bail!("Found {} untranslated page(s)", missing);
+					}
+					return Ok(());
+				}
+				let issues = generator.lint()?;
+				if json_output {
+					check::report_lint_issues(&issues, sink.as_ref());
+				} else {
+					check::print_lint_report(&issues);
+				}
+
+				let errors = issues
+					.iter()
+					.filter(|i| i.severity == Severity::Error)
+					.count();
+				let warnings = issues.len() - errors;
+				if errors > 0 || (strict && warnings > 0) {
+					bail!("Lint found {} error(s) and {} warning(s)", errors, warnings);
+				}
+				if warnings > 0 {
+					warn!("Lint found {} warning(s)", warnings);
+				} else {
+					info!("No issues found");
+				}
+			}
+			Commands::CheckLinks { source, config } => {
+				let generator = Generator::new(source, PathBuf::from("dist"), config)?;
+				let sources = generator.external_links()?;
+				info!("Checking {} external link(s)", sources.len());
+
+				let checker = LinkChecker::new(&generator.config().link_check)?;
+				let results = checker.check_all(sources.keys().cloned().collect()).await;
+				linkcheck::print_report(&results, &sources);
+
+				let broken = results.iter().filter(|(_, s)| s.is_broken()).count();
+				if broken > 0 {
+					bail!("Found {} broken external link(s)", broken);
+				}
+			}
+			Commands::Graph {
+				source,
+				config,
+				output,
+				format,
+			} => {
+				let generator = Generator::new(source, PathBuf::from("dist"), config)?;
+				let graph = generator.export_graph(&format)?;
+				match output {
+					Some(path) => {
+						fs::write(&path, graph)?;
+						info!("Wrote graph to {}", path.display());
+					}
+					None => print!("{}", graph),
+				}
+			}
+			Commands::Diff { old, new, format } => {
+				let build_diff = diff::diff_builds(&old, &new)?;
+				match format.as_str() {
+					"summary" => diff::print_summary(&build_diff),
+					"unified-diff" => diff::print_unified(&build_diff, &old, &new)?,
+					other => bail!(
+						"Unknown diff format '{}', expected \"summary\" or \"unified-diff\"",
+						other
+					),
+				}
+			}
+			Commands::Init {
+				dir,
+				template,
+				list_templates,
+			} => {
+				if list_templates {
+					for template in scaffold::TEMPLATES {
+						println!("{:<10} {}", template.name, template.description);
+					}
+					return Ok(());
+				}
+
+				let template = scaffold::find_template(&template)?;
+				template.write(&dir)?;
+
+				info!("Initialized {} project in {}", template.name, dir.display());
+			}
+			Commands::InstallMan {
+				prefix,
+				output,
+				dry_run,
+				config,
+			} => {
+				let config = Config::load(config.as_deref())?;
+				let prefix = prefix
+					.or(config.man.install_prefix)
+					.unwrap_or_else(|| PathBuf::from("/usr/local"));
+				let exporter = Exporter::new(&output);
+				exporter.install_man_pages(&prefix, dry_run)?;
 			}
 		}
 		Ok(())
 	}
 }
+
+async fn watch_and_rebuild(generator: &Generator, source: &Path, formats: &str) -> Result<()> {
+	let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+	let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+		if let Ok(event) = event {
+			let _ = tx.send(event);
+		}
+	})?;
+	watcher.watch(source, RecursiveMode::Recursive)?;
+	let source = fs::canonicalize(source)?;
+	info!("Watching for changes...");
+
+	// Rebuilds run one after another and update the output in place
+	while let Some(batch) = server::next_batch(&mut rx).await {
+		let changed = server::changed_paths(&batch, &source);
+		if changed.is_empty() {
+			continue;
+		}
+		let result = if formats == "html" && batch.iter().all(|e| e.kind.is_modify()) {
+			generator.build_incremental(&changed).await
+		} else {
+			generator.generate(formats).await.map(|_| Vec::new())
+		};
+
+		match result {
+			Ok(written) => {
+				for path in written {
+					info!("Rebuilt {}", path.display());
+				}
+			}
+			Err(e) => error!("Rebuild error: {:#}", e),
+		}
+	}
+
+	Ok(())
+}
@@ -0,0 +1,115 @@
+use anyhow::{bail, Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::config::CollectionConfig;
+
+// `[[collections]]` globs, a document can belong to any number of them
+#[derive(Debug, Clone)]
+pub struct Collections {
+	set: GlobSet,
+	names: Vec<String>,
+}
+
+impl Collections {
+	pub fn new(collections: &[CollectionConfig]) -> Result<Self> {
+		let mut builder = GlobSetBuilder::new();
+		let mut names = Vec::new();
+		let mut seen = HashSet::new();
+		for collection in collections {
+			let name = &collection.name;
+			// The name becomes the index's directory in the output
+			if name.is_empty()
+				|| !name
+					.chars()
+					.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+			{
+				bail!(
+					"Invalid collection name '{}', use letters, digits, '-' and '_'",
+					name
+				);
+			}
+			if !seen.insert(name.as_str()) {
+				bail!("Collection '{}' is defined more than once", name);
+			}
+			if !matches!(
+				collection.sort_by.as_str(),
+				"order" | "date" | "title" | "filename"
+			) {
+				bail!(
+					"Unknown sort_by '{}' for collection '{}', expected \"order\", \"date\", \"title\" or \"filename\"",
+					collection.sort_by,
+					name
+				);
+			}
+			builder.add(
+				GlobBuilder::new(collection.glob.trim_start_matches('/'))
+					.literal_separator(true)
+					.build()
+					.with_context(|| {
+						format!(
+							"Invalid glob '{}' for collection '{}'",
+							collection.glob, name
+						)
+					})?,
+			);
+			names.push(name.clone());
+		}
+		Ok(Self {
+			set: builder.build()?,
+			names,
+		})
+	}
+
+	// Paths are relative to the source directory, names come back in config order
+	pub fn matching(&self, path: &Path) -> Vec<String> {
+		let mut matches = self.set.matches(path);
+		matches.sort_unstable();
+		matches
+			.into_iter()
+			.map(|idx| self.names[idx].clone())
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn collection(name: &str, glob: &str) -> CollectionConfig {
+		CollectionConfig {
+			name: name.to_string(),
+			glob: glob.to_string(),
+			sort_by: "order".to_string(),
+			template: None,
+		}
+	}
+
+	#[test]
+	fn test_matching() {
+		let collections = Collections::new(&[
+			collection("blog", "blog/**/*.md"),
+			collection("top", "*.md"),
+			collection("releases", "/blog/releases/*.md"),
+		])
+		.unwrap();
+		assert_eq!(
+			collections.matching(Path::new("blog/2024/launch.md")),
+			["blog"]
+		);
+		assert_eq!(
+			collections.matching(Path::new("blog/releases/v1.md")),
+			["blog", "releases"]
+		);
+		assert_eq!(collections.matching(Path::new("index.md")), ["top"]);
+		assert!(collections.matching(Path::new("guide/setup.md")).is_empty());
+
+		assert!(Collections::new(&[collection("a/b", "*.md")]).is_err());
+		assert!(Collections::new(&[collection("a", "*.md"), collection("a", "b/*")]).is_err());
+		assert!(Collections::new(&[collection("a", "[")]).is_err());
+		let mut unsorted = collection("a", "*.md");
+		unsorted.sort_by = "random".to_string();
+		assert!(Collections::new(&[unsorted]).is_err());
+	}
+}
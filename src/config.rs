@@ -1,9 +1,17 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tracing::warn;
+
+static ENV_VAR_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
 	pub site: SiteConfig,
 	pub navigation: NavigationConfig,
@@ -11,10 +19,71 @@ pub struct Config {
 	pub search: SearchConfig,
 	pub export: ExportConfig,
 	#[serde(default)]
-	pub plugins: Vec<String>,
+	pub seo: SeoConfig,
+	#[serde(default)]
+	pub social: SocialConfig,
+	#[serde(default)]
+	pub build: BuildConfig,
+	#[serde(default)]
+	pub man: ManConfig,
+	#[serde(default)]
+	pub pdf: PdfConfig,
+	#[serde(default)]
+	pub feed: FeedConfig,
+	#[serde(default)]
+	pub sitemap: SitemapConfig,
+	#[serde(default)]
+	pub assets: AssetsConfig,
+	#[serde(default)]
+	pub lint: LintConfig,
+	#[serde(default)]
+	pub server: ServerConfig,
+	#[serde(default)]
+	pub output: OutputConfig,
+	#[serde(default)]
+	pub display: DisplayConfig,
+	#[serde(default)]
+	pub repository: RepositoryConfig,
+	#[serde(default)]
+	pub urls: UrlsConfig,
+	#[serde(default)]
+	pub i18n: I18nConfig,
+	#[serde(default)]
+	pub pagination: PaginationConfig,
+	#[serde(default)]
+	pub link_check: LinkCheckConfig,
+	#[serde(default)]
+	pub security: SecurityConfig,
+	#[serde(default)]
+	pub content: ContentConfig,
+	#[serde(default)]
+	pub syntax: SyntaxConfig,
+	#[serde(default)]
+	pub mermaid: MermaidConfig,
+	#[serde(default)]
+	pub toc: TocConfig,
+	#[serde(default)]
+	pub collections: Vec<CollectionConfig>,
+	#[serde(default)]
+	pub plugins: PluginsConfig,
+	#[serde(default)]
+	pub validation: ValidationConfig,
+}
+
+// A `[[collections]]` entry, documents matching the glob get a listing at /{name}/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CollectionConfig {
+	pub name: String,
+	pub glob: String, // relative to the source directory, e.g. "blog/**/*.md"
+	#[serde(default = "default_sort_by")]
+	pub sort_by: String,
+	// A template from the template directory to render the index with instead of base.html
+	pub template: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SiteConfig {
 	pub title: String,
 	pub description: String,
@@ -22,18 +91,81 @@ pub struct SiteConfig {
 	pub base_url: Option<String>,
 	#[serde(default)]
 	pub versions: Vec<String>,
+	// Alias to the version it stands for, e.g. latest = "v2.1.0"
+	#[serde(default)]
+	pub version_aliases: BTreeMap<String, String>,
 	pub default_version: Option<String>,
+	pub twitter_handle: Option<String>,
+	#[serde(default = "default_language")]
+	pub language: String,
+	// With more than one version, `/` sends visitors on to the newest one
+	#[serde(default = "default_true")]
+	pub redirect_root: bool,
+}
+
+impl SiteConfig {
+	// The version `/` redirects to, versions are already sorted newest first on load
+	pub fn root_redirect(&self) -> Option<&str> {
+		if !self.redirect_root || self.versions.len() < 2 {
+			return None;
+		}
+		self.versions.first().map(String::as_str)
+	}
+
+	// Newest first, an alias sorts as the version it points to and anything else goes last
+	pub fn sort_versions(&mut self) {
+		let key =
+			|version: &String| parse_version(self.version_aliases.get(version).unwrap_or(version));
+		for version in &self.versions {
+			// A plain latest without an alias is the unreleased docs, not a misnamed release
+			if key(version).is_none() && version != "latest" {
+				warn!(
+					"Version {} is not a semantic version, listing it last",
+					version
+				);
+			}
+		}
+		self.versions.sort_by_key(|v| std::cmp::Reverse(key(v)));
+	}
+}
+
+// Accepts a leading v, so v1.10.0 and 1.10.0 are the same version
+pub fn parse_version(version: &str) -> Option<semver::Version> {
+	semver::Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct NavigationConfig {
 	#[serde(default)]
 	pub sidebar: SidebarConfig,
 	#[serde(default)]
 	pub breadcrumbs: bool,
+	#[serde(default)]
+	pub sections: Vec<SectionConfig>,
+	#[serde(default = "default_true")]
+	pub prev_next: bool,
+	// Glob pattern to priority, e.g. "api/**" = 2, for documents without a frontmatter order
+	#[serde(default)]
+	pub order: BTreeMap<String, u32>,
+	// Sidebar levels shown, counting the top one, None shows every level
+	pub max_depth: Option<usize>,
+	// Opens the current page's directories past max_depth, so it is always in the sidebar
+	#[serde(default = "default_true")]
+	pub expand_active_path: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SectionConfig {
+	pub dir: String,
+	pub title: String,
+	pub icon: Option<String>,
+	pub order: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct SidebarConfig {
 	#[serde(default = "default_true")]
 	pub enabled: bool,
@@ -43,20 +175,101 @@ pub struct SidebarConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ThemeConfig {
 	pub default_theme: Option<String>,       // "light" or "dark"
 	pub syntax_highlighting: Option<String>, // "prism" or "highlight"
 	pub custom_css: Option<PathBuf>,
+	// Templates to use instead of the built-in ones, relative to the config file. `--template-dir` wins.
+	pub template_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyntaxConfig {
+	#[serde(default = "default_true")]
+	pub enabled: bool,
+	// syntect theme used for build-time highlighting, e.g. "base16-ocean.dark", "InspiredGitHub"
+	#[serde(default = "default_syntect_theme")]
+	pub theme: String,
+	// syntect themes for syntax-light.css and syntax-dark.css, switched with the site theme
+	#[serde(default = "default_syntax_theme_light")]
+	pub theme_light: String,
+	#[serde(default = "default_syntax_theme_dark")]
+	pub theme_dark: String,
+}
+
+impl Default for SyntaxConfig {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			theme: default_syntect_theme(),
+			theme_light: default_syntax_theme_light(),
+			theme_dark: default_syntax_theme_dark(),
+		}
+	}
+}
+
+// Needs a build with the wasm-plugins feature, see PLUGIN_ABI.md for what a module exports
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PluginsConfig {
+	// Shortcode modules, relative to the config file and tried in order
+	#[serde(default)]
+	pub wasm: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ValidationConfig {
+	// A draft 7 JSON Schema every document's frontmatter is checked against, relative to the config file
+	pub schema: Option<PathBuf>,
+	// Custom frontmatter keys, any other key rum does not know is warned about as a likely typo
+	#[serde(default)]
+	pub extra_fields: Vec<String>,
+}
+
+// Site-wide default, a document's `toc` frontmatter field overrides it
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct TocConfig {
+	#[serde(default)]
+	pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MermaidConfig {
+	#[serde(default = "default_mermaid_renderer")]
+	pub renderer: String, // "api" (mermaid.ink) or "cli" (mmdc)
+	pub cli_path: Option<PathBuf>,
+}
+
+impl Default for MermaidConfig {
+	fn default() -> Self {
+		Self {
+			renderer: default_mermaid_renderer(),
+			cli_path: None,
+		}
+	}
+}
+
+fn default_mermaid_renderer() -> String {
+	"api".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SearchConfig {
 	#[serde(default = "default_true")]
 	pub enabled: bool,
 	pub engine: Option<String>, // "fuse" or "lunr"
+	#[serde(default = "default_index_fields")]
+	pub index_fields: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ExportConfig {
 	#[serde(default = "default_true")]
 	pub html: bool,
@@ -66,6 +279,553 @@ pub struct ExportConfig {
 	pub man: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeoConfig {
+	#[serde(default = "default_true")]
+	pub twitter_cards: bool,
+}
+
+impl Default for SeoConfig {
+	fn default() -> Self {
+		Self {
+			twitter_cards: true,
+		}
+	}
+}
+
+// OpenGraph and Twitter Card tags for link previews
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SocialConfig {
+	#[serde(default = "default_og_type")]
+	pub og_type: String,
+	pub twitter_handle: Option<String>,
+}
+
+impl Default for SocialConfig {
+	fn default() -> Self {
+		Self {
+			og_type: default_og_type(),
+			twitter_handle: None,
+		}
+	}
+}
+
+fn default_og_type() -> String {
+	"article".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildConfig {
+	#[serde(default)]
+	pub minify: bool,
+	#[serde(default)]
+	pub source_maps: bool,
+	#[serde(default = "default_parallel_threshold")]
+	pub parallel_threshold: usize,
+	pub asciidoctor_path: Option<PathBuf>,
+	pub rst2html_path: Option<PathBuf>,
+	// Fill in last-modified dates from each file's latest commit
+	#[serde(default)]
+	pub git: bool,
+	#[serde(default = "default_date_format")]
+	pub date_format: String, // strftime
+	#[serde(default = "default_sort_by")]
+	pub sort_by: String, // "order", "date", "title" or "filename"
+	// Globs relative to the source directory, merged with .rumignore in the project root
+	#[serde(default)]
+	pub ignore: Vec<String>,
+	#[serde(default)]
+	pub environments: EnvironmentsConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EnvironmentsConfig {
+	// Applies to `rum build`, not to the dev server
+	#[serde(default)]
+	pub production: EnvironmentConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EnvironmentConfig {
+	// Drafts built with --drafts get noindex, keeping previews out of search engines
+	#[serde(default)]
+	pub noindex_drafts: bool,
+}
+
+impl Default for BuildConfig {
+	fn default() -> Self {
+		Self {
+			minify: false,
+			source_maps: false,
+			parallel_threshold: default_parallel_threshold(),
+			asciidoctor_path: None,
+			rst2html_path: None,
+			git: false,
+			date_format: default_date_format(),
+			sort_by: default_sort_by(),
+			ignore: Vec::new(),
+			environments: EnvironmentsConfig::default(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManConfig {
+	pub install_prefix: Option<PathBuf>,
+	#[serde(default = "default_man_section")]
+	pub section: u8,
+}
+
+impl Default for ManConfig {
+	fn default() -> Self {
+		Self {
+			install_prefix: None,
+			section: default_man_section(),
+		}
+	}
+}
+
+fn default_man_section() -> u8 {
+	7
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PdfConfig {
+	pub chromium_path: Option<PathBuf>,
+	#[serde(default = "default_paper_size")]
+	pub paper_size: String,
+	#[serde(default = "default_margin_mm")]
+	pub margin_mm: f64,
+	#[serde(default = "default_true")]
+	pub print_background: bool,
+}
+
+impl Default for PdfConfig {
+	fn default() -> Self {
+		Self {
+			chromium_path: None,
+			paper_size: default_paper_size(),
+			margin_mm: default_margin_mm(),
+			print_background: true,
+		}
+	}
+}
+
+fn default_paper_size() -> String {
+	"A4".to_string()
+}
+
+fn default_margin_mm() -> f64 {
+	15.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeedConfig {
+	#[serde(default)]
+	pub enabled: bool,
+	#[serde(default = "default_feed_format")]
+	pub format: String, // "rss2" or "atom"
+	#[serde(default = "default_feed_max_items")]
+	pub max_items: usize,
+	#[serde(default = "default_feed_output_path")]
+	pub output_path: String,
+}
+
+impl Default for FeedConfig {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			format: default_feed_format(),
+			max_items: default_feed_max_items(),
+			output_path: default_feed_output_path(),
+		}
+	}
+}
+
+// Extra stylesheets and scripts, relative to the project root, copied next to the bundled ones
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct AssetsConfig {
+	#[serde(default)]
+	pub extra_css: Vec<String>,
+	#[serde(default)]
+	pub extra_js: Vec<String>,
+	// Hash the bundled style.css and app.js into their file names for long-lived caching
+	#[serde(default)]
+	pub fingerprint: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SitemapConfig {
+	#[serde(default)]
+	pub enabled: bool,
+	pub base_url: Option<String>,
+	#[serde(default = "default_sitemap_changefreq")]
+	pub changefreq: String,
+	#[serde(default = "default_sitemap_priority")]
+	pub priority: f32,
+	#[serde(default)]
+	pub compress: bool,
+}
+
+impl Default for SitemapConfig {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			base_url: None,
+			changefreq: default_sitemap_changefreq(),
+			priority: default_sitemap_priority(),
+			compress: false,
+		}
+	}
+}
+
+fn default_sitemap_changefreq() -> String {
+	"weekly".to_string()
+}
+
+fn default_sitemap_priority() -> f32 {
+	0.5
+}
+
+fn default_feed_format() -> String {
+	"rss2".to_string()
+}
+
+fn default_feed_max_items() -> usize {
+	20
+}
+
+fn default_feed_output_path() -> String {
+	"feed.xml".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct LintConfig {
+	#[serde(default)]
+	pub warn_orphans: bool,
+	pub max_warnings: Option<usize>,
+	#[serde(default)]
+	pub warnings_as_errors: bool,
+	// Frontmatter fields `rum lint` expects in every document
+	#[serde(default)]
+	pub required_fields: Vec<String>,
+}
+
+// `rum check-links`, requests to one host run one at a time within the overall concurrency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LinkCheckConfig {
+	#[serde(default = "default_link_check_concurrency")]
+	pub concurrency: usize,
+	#[serde(default = "default_link_check_timeout")]
+	pub timeout_secs: u64,
+	#[serde(default = "default_link_check_retries")]
+	pub retries: u32,
+}
+
+impl Default for LinkCheckConfig {
+	fn default() -> Self {
+		Self {
+			concurrency: default_link_check_concurrency(),
+			timeout_secs: default_link_check_timeout(),
+			retries: default_link_check_retries(),
+		}
+	}
+}
+
+// Raw HTML in pages is cleaned with ammonia, the lists below add to what it allows by default.
+// allowed_attributes maps a tag to its extra attributes, "*" applies them to every tag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SecurityConfig {
+	#[serde(default = "default_true")]
+	pub sanitize_html: bool,
+	#[serde(default)]
+	pub allowed_tags: Vec<String>,
+	#[serde(default)]
+	pub allowed_attributes: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for SecurityConfig {
+	fn default() -> Self {
+		Self {
+			sanitize_html: true,
+			allowed_tags: vec![],
+			allowed_attributes: BTreeMap::new(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+	pub basic_auth: Option<BasicAuthConfig>,
+	// `rum serve` lists directories without an index.html, off as it exposes the output layout
+	#[serde(default)]
+	pub directory_listing: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BasicAuthConfig {
+	pub username: String,
+	pub password: String,
+}
+
+impl BasicAuthConfig {
+	// Expand ${VAR} references so credentials can stay out of rum.toml. An unset variable is an
+	// error, an empty password would leave the preview open to anyone guessing the username.
+	pub fn resolved_password(&self) -> Result<String> {
+		for caps in ENV_VAR_REGEX.captures_iter(&self.password) {
+			if std::env::var(&caps[1]).is_err() {
+				bail!(
+					"server.basic_auth.password refers to ${{{}}}, which is not set",
+					&caps[1]
+				);
+			}
+		}
+		Ok(ENV_VAR_REGEX
+			.replace_all(&self.password, |caps: &regex::Captures| {
+				std::env::var(&caps[1]).unwrap_or_default()
+			})
+			.to_string())
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+	#[serde(default = "default_structure")]
+	pub structure: String, // "mirror" or "flat"
+}
+
+impl Default for OutputConfig {
+	fn default() -> Self {
+		Self {
+			structure: default_structure(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DisplayConfig {
+	#[serde(default = "default_true")]
+	pub show_date: bool,
+	#[serde(default = "default_true")]
+	pub show_reading_time: bool,
+	#[serde(default = "default_words_per_minute")]
+	pub words_per_minute: usize,
+}
+
+impl Default for DisplayConfig {
+	fn default() -> Self {
+		Self {
+			show_date: true,
+			show_reading_time: true,
+			words_per_minute: default_words_per_minute(),
+		}
+	}
+}
+
+// Where the sources live, for "Edit this page" links
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepositoryConfig {
+	pub url: Option<String>,
+	#[serde(default = "default_repository_branch")]
+	pub branch: String,
+	#[serde(rename = "type", default = "default_repository_type")]
+	pub kind: String, // "github", "gitlab" or "gitea"
+	#[serde(default = "default_repository_docs_dir")]
+	pub docs_dir: String,
+}
+
+impl Default for RepositoryConfig {
+	fn default() -> Self {
+		Self {
+			url: None,
+			branch: default_repository_branch(),
+			kind: default_repository_type(),
+			docs_dir: default_repository_docs_dir(),
+		}
+	}
+}
+
+fn default_repository_branch() -> String {
+	"main".to_string()
+}
+
+fn default_repository_type() -> String {
+	"github".to_string()
+}
+
+fn default_repository_docs_dir() -> String {
+	"docs".to_string()
+}
+
+// Output path pattern with :slug, :title, :date, :version and :path tokens, empty keeps output.structure
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct UrlsConfig {
+	#[serde(default)]
+	pub pattern: String,
+}
+
+// Translations live under source_dir/{locale}/, pages outside those directories are in default_locale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct I18nConfig {
+	#[serde(default = "default_locale")]
+	pub default_locale: String,
+	#[serde(default)]
+	pub locales: Vec<String>,
+}
+
+impl Default for I18nConfig {
+	fn default() -> Self {
+		Self {
+			default_locale: default_locale(),
+			locales: Vec::new(),
+		}
+	}
+}
+
+// Directories without an index page get generated listings, page_size documents per page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PaginationConfig {
+	#[serde(default = "default_page_size")]
+	pub page_size: usize,
+}
+
+impl Default for PaginationConfig {
+	fn default() -> Self {
+		Self {
+			page_size: default_page_size(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContentConfig {
+	#[serde(default = "default_anchor_style")]
+	pub anchor_style: String, // "slug" or "sequential"
+	#[serde(default = "default_code_types")]
+	pub code_types: Vec<String>,
+	#[serde(default)]
+	pub notebook_include_outputs: bool,
+	#[serde(default)]
+	pub heading_offset: i8,
+	pub preprocess_command: Option<String>,
+	#[serde(default = "default_preprocess_timeout")]
+	pub preprocess_timeout: u64, // seconds
+}
+
+impl Default for ContentConfig {
+	fn default() -> Self {
+		Self {
+			anchor_style: default_anchor_style(),
+			code_types: default_code_types(),
+			notebook_include_outputs: false,
+			heading_offset: 0,
+			preprocess_command: None,
+			preprocess_timeout: default_preprocess_timeout(),
+		}
+	}
+}
+
+fn default_anchor_style() -> String {
+	"slug".to_string()
+}
+
+fn default_syntect_theme() -> String {
+	"base16-ocean.dark".to_string()
+}
+
+fn default_syntax_theme_light() -> String {
+	"InspiredGitHub".to_string()
+}
+
+fn default_syntax_theme_dark() -> String {
+	"base16-ocean.dark".to_string()
+}
+
+fn default_preprocess_timeout() -> u64 {
+	10
+}
+
+fn default_parallel_threshold() -> usize {
+	50
+}
+
+pub fn default_date_format() -> String {
+	"%B %d, %Y".to_string()
+}
+
+fn default_sort_by() -> String {
+	"order".to_string()
+}
+
+fn default_language() -> String {
+	"en".to_string()
+}
+
+fn default_index_fields() -> Vec<String> {
+	["title", "content", "path", "version"]
+		.iter()
+		.map(|field| field.to_string())
+		.collect()
+}
+
+fn default_code_types() -> Vec<String> {
+	vec![
+		"path".to_string(),
+		"command".to_string(),
+		"type".to_string(),
+	]
+}
+
+fn default_structure() -> String {
+	"mirror".to_string()
+}
+
+fn default_link_check_concurrency() -> usize {
+	8
+}
+
+fn default_link_check_timeout() -> u64 {
+	10
+}
+
+fn default_link_check_retries() -> u32 {
+	1
+}
+
+fn default_page_size() -> usize {
+	20
+}
+
+fn default_locale() -> String {
+	"en".to_string()
+}
+
+fn default_words_per_minute() -> usize {
+	200
+}
+
 fn default_true() -> bool {
 	true
 }
@@ -79,7 +839,11 @@ impl Default for Config {
 				author: None,
 				base_url: None,
 				versions: vec!["latest".to_string()],
+				version_aliases: BTreeMap::new(),
 				default_version: Some("latest".to_string()),
+				twitter_handle: None,
+				language: default_language(),
+				redirect_root: true,
 			},
 			navigation: NavigationConfig {
 				sidebar: SidebarConfig {
@@ -88,35 +852,70 @@ impl Default for Config {
 					custom_order: None,
 				},
 				breadcrumbs: true,
+				sections: vec![],
+				prev_next: true,
+				order: BTreeMap::new(),
+				max_depth: None,
+				expand_active_path: true,
 			},
 			theme: ThemeConfig {
 				default_theme: Some("dark".to_string()),
 				syntax_highlighting: Some("prism".to_string()),
 				custom_css: None,
+				template_dir: None,
 			},
 			search: SearchConfig {
 				enabled: true,
 				engine: Some("fuse".to_string()),
+				index_fields: default_index_fields(),
 			},
 			export: ExportConfig {
 				html: true,
 				pdf: false,
 				man: false,
 			},
-			plugins: vec![],
+			seo: SeoConfig::default(),
+			social: SocialConfig::default(),
+			build: BuildConfig::default(),
+			man: ManConfig::default(),
+			pdf: PdfConfig::default(),
+			feed: FeedConfig::default(),
+			sitemap: SitemapConfig::default(),
+			assets: AssetsConfig::default(),
+			lint: LintConfig::default(),
+			server: ServerConfig::default(),
+			output: OutputConfig::default(),
+			display: DisplayConfig::default(),
+			repository: RepositoryConfig::default(),
+			urls: UrlsConfig::default(),
+			i18n: I18nConfig::default(),
+			pagination: PaginationConfig::default(),
+			link_check: LinkCheckConfig::default(),
+			security: SecurityConfig::default(),
+			content: ContentConfig::default(),
+			syntax: SyntaxConfig::default(),
+			mermaid: MermaidConfig::default(),
+			toc: TocConfig::default(),
+			collections: vec![],
+			plugins: PluginsConfig::default(),
+			validation: ValidationConfig::default(),
 		}
 	}
 }
 
 impl Config {
 	pub fn load(path: Option<&Path>) -> Result<Self> {
+		let mut config = Self::read(path)?;
+		config.site.sort_versions();
+		Ok(config)
+	}
+
+	fn read(path: Option<&Path>) -> Result<Self> {
 		let default_config = Config::default();
 
 		if let Some(config_path) = path {
 			if config_path.exists() {
-				let content = fs::read_to_string(config_path)?;
-				let config: Config = toml::from_str(&content)?;
-				return Ok(config);
+				return Self::parse(config_path);
 			}
 		}
 
@@ -125,17 +924,156 @@ impl Config {
 		let config_path = current_dir.join("rum.toml");
 
 		if config_path.exists() {
-			let content = fs::read_to_string(&config_path)?;
-			let config: Config = toml::from_str(&content)?;
-			return Ok(config);
+			return Self::parse(&config_path);
 		}
 
 		Ok(default_config)
 	}
 
+	// Unknown keys are an error, a misspelt one would otherwise be ignored without a word
+	fn parse(path: &Path) -> Result<Self> {
+		let content = fs::read_to_string(path)?;
+		let table: toml::Table = toml::from_str(&content)
+			.with_context(|| format!("Invalid config {}", path.display()))?;
+		check_renamed_fields(&table)
+			.with_context(|| format!("Invalid config {}", path.display()))?;
+		// Parsed again from the text, so errors point at a line
+		toml::from_str(&content).with_context(|| format!("Invalid config {}", path.display()))
+	}
+
 	pub fn save(&self, path: &Path) -> Result<()> {
 		let toml = toml::to_string_pretty(self)?;
 		fs::write(path, toml)?;
 		Ok(())
 	}
+
+	// Works on the parsed table rather than a Config, which would already be filled with defaults.
+	// Tables merge key by key, any other value in the override replaces the base one.
+	pub fn merge(base: &Config, override_: toml::Table) -> Result<Config> {
+		check_renamed_fields(&override_)?;
+		let toml::Value::Table(mut merged) = toml::Value::try_from(base)? else {
+			unreachable!("Config serializes to a table");
+		};
+		merge_tables(&mut merged, override_);
+		Ok(toml::Value::Table(merged).try_into()?)
+	}
+}
+
+// Old key and the one that replaced it
+const RENAMED_FIELDS: &[(&str, &str)] =
+	&[("navigation.show_breadcrumbs", "navigation.breadcrumbs")];
+
+fn check_renamed_fields(table: &toml::Table) -> Result<()> {
+	for (old, new) in RENAMED_FIELDS {
+		let (parents, key) = old.rsplit_once('.').unwrap_or(("", old));
+		let parent = parents
+			.split('.')
+			.filter(|p| !p.is_empty())
+			.try_fold(table, |table, name| table.get(name)?.as_table());
+		if parent.is_some_and(|parent| parent.contains_key(key)) {
+			bail!(
+				"`{}` has been renamed to `{}`, update the config file",
+				old,
+				new
+			);
+		}
+	}
+	Ok(())
+}
+
+fn merge_tables(base: &mut toml::Table, override_: toml::Table) {
+	for (key, value) in override_ {
+		match (base.get_mut(&key), value) {
+			(Some(toml::Value::Table(base)), toml::Value::Table(value)) => {
+				merge_tables(base, value)
+			}
+			(_, value) => {
+				base.insert(key, value);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_sort_versions() {
+		let mut site = Config::default().site;
+		site.versions = [
+			"v1.9.0",
+			"nightly",
+			"v1.10.0",
+			"latest",
+			"2.0.0-beta.1",
+			"v0.9",
+		]
+		.map(String::from)
+		.to_vec();
+		site.version_aliases
+			.insert("latest".to_string(), "v1.10.0".to_string());
+		site.sort_versions();
+		assert_eq!(
+			site.versions,
+			[
+				"2.0.0-beta.1",
+				"v1.10.0",
+				"latest",
+				"v1.9.0",
+				"nightly",
+				"v0.9"
+			]
+		);
+
+		assert!(parse_version("v1.9.0") < parse_version("v1.10.0"));
+		assert_eq!(parse_version("1.10.0"), parse_version("v1.10.0"));
+		assert_eq!(parse_version("v1.9"), None);
+	}
+
+	#[test]
+	fn test_unknown_fields() {
+		let path = std::env::temp_dir().join(format!("rum-config-{}.toml", std::process::id()));
+		Config::default().save(&path).unwrap();
+		let saved = fs::read_to_string(&path).unwrap();
+		assert!(Config::load(Some(&path)).is_ok());
+
+		fs::write(
+			&path,
+			saved.replace("[site]\n", "[site]\ntile = \"Docs\"\n"),
+		)
+		.unwrap();
+		let error = format!("{:#}", Config::load(Some(&path)).unwrap_err());
+		assert!(error.contains("unknown field `tile`"), "{}", error);
+
+		fs::write(
+			&path,
+			saved.replace("[navigation]\n", "[navigation]\nshow_breadcrumbs = false\n"),
+		)
+		.unwrap();
+		let error = format!("{:#}", Config::load(Some(&path)).unwrap_err());
+		assert!(error.contains(
+			"`navigation.show_breadcrumbs` has been renamed to `navigation.breadcrumbs`"
+		));
+
+		let override_ = "[search]\nindex_field = []\n".parse().unwrap();
+		assert!(Config::merge(&Config::default(), override_).is_err());
+
+		fs::remove_file(&path).ok();
+	}
+
+	#[test]
+	fn test_resolved_password() {
+		let var = format!("RUM_TEST_PASSWORD_{}", std::process::id());
+		let auth = BasicAuthConfig {
+			username: "preview".to_string(),
+			password: format!("pre-${{{}}}-post", var),
+		};
+		let error = auth.resolved_password().unwrap_err().to_string();
+		assert!(error.contains(&var), "{}", error);
+
+		std::env::set_var(&var, "secret");
+		assert_eq!(auth.resolved_password().unwrap(), "pre-secret-post");
+		std::env::remove_var(&var);
+	}
 }
@@ -1,20 +1,83 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use indexmap::IndexMap;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::LazyLock;
+use std::thread;
+use std::time::{Duration, Instant};
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+use tracing::warn;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::config::{parse_version, Config, SiteConfig};
+use crate::utils::{escape_html, is_valid_lang, slugify, SlugDeduplicator};
+
+mod asciidoc;
+mod mermaid;
+mod plugins;
+mod sanitize;
+
+pub use plugins::Plugins;
+use sanitize::{sanitize, Trusted};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Document {
 	pub frontmatter: Frontmatter,
 	pub content: String,
 	pub html_content: String,
+	pub toc_html: String,
 	pub path: PathBuf,
 	pub relative_path: PathBuf,
 	pub version: Option<String>,
+	// Set for translations under source_dir/{locale}/
+	pub locale: Option<String>,
 	pub backlinks: Vec<String>,
 	pub links: Vec<String>,
+	// Page name and heading of each [[Page#Section]] link, for checking the heading exists
+	#[serde(default)]
+	pub wiki_fragments: Vec<(String, String)>,
+	// Local images the page embeds, relative to the source directory
+	pub images: Vec<PathBuf>,
+	pub word_count: usize,
+	pub reading_time_minutes: usize,
+	// Names of the `[[collections]]` whose glob matches the page
+	pub collections: Vec<String>,
+	// The root config merged with rum.toml files in the directories above the page
+	#[serde(skip)]
+	pub effective_config: Option<Config>,
+	// Problems met while parsing, the generator moves them into the build log
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub warnings: Vec<ContentWarning>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentWarning {
+	pub code: String,
+	pub message: String,
+}
+
+impl ContentWarning {
+	fn new(code: &str, message: String) -> Self {
+		Self {
+			code: code.to_string(),
+			message,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocEntry {
+	pub level: u8,
+	pub title: String,
+	pub anchor: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -22,65 +85,567 @@ pub struct Frontmatter {
 	pub title: Option<String>,
 	pub version: Option<String>,
 	pub tags: Option<Vec<String>>,
-	pub author: Option<String>,
+	// `author: Jane` is still read, as a single author
+	#[serde(default, alias = "author", deserialize_with = "deserialize_authors")]
+	pub authors: Option<Vec<String>>,
 	pub description: Option<String>,
-	pub order: Option<u32>,
+	#[serde(default, deserialize_with = "deserialize_date")]
+	pub date: Option<NaiveDate>,
+	pub last_modified: Option<DateTime<Utc>>,
+	#[serde(serialize_with = "serialize_order")]
+	pub order: Option<f64>,
+	pub image: Option<String>,
+	pub hidden: Option<bool>,
+	pub toc: Option<bool>,
+	pub draft: Option<bool>,
+	pub noindex: Option<bool>,
+	pub body_class: Option<String>,
+	pub lang: Option<String>,
+	pub heading_offset: Option<i8>,
+	pub preprocess: Option<String>,
+	pub man_section: Option<u8>,
 	#[serde(flatten)]
-	pub extra: HashMap<String, serde_yaml::Value>,
+	pub extra: IndexMap<String, serde_yaml::Value>,
+}
+
+// Whole orders are written back as integers so they round-trip without a trailing .0
+fn serialize_order<S: serde::Serializer>(
+	order: &Option<f64>,
+	serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+	match order {
+		Some(order) if order.fract() == 0.0 && order.abs() < i64::MAX as f64 => {
+			serializer.serialize_some(&(*order as i64))
+		}
+		Some(order) => serializer.serialize_some(order),
+		None => serializer.serialize_none(),
+	}
+}
+
+// Dates are written YYYY-MM-DD, a full timestamp keeps just its date part
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+	let value = value.trim();
+	NaiveDate::parse_from_str(value.get(..10).unwrap_or(value), "%Y-%m-%d").ok()
+}
+
+// An unreadable date only drops the date, not the rest of the frontmatter
+fn deserialize_date<'de, D: serde::Deserializer<'de>>(
+	deserializer: D,
+) -> std::result::Result<Option<NaiveDate>, D::Error> {
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum DateValue {
+		Text(String),
+		Other(serde::de::IgnoredAny),
+	}
+
+	Ok(match Option::<DateValue>::deserialize(deserializer)? {
+		Some(DateValue::Text(text)) => parse_date(&text),
+		_ => None,
+	})
+}
+
+// One name or a list of them
+fn deserialize_authors<'de, D: serde::Deserializer<'de>>(
+	deserializer: D,
+) -> std::result::Result<Option<Vec<String>>, D::Error> {
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Authors {
+		One(String),
+		Many(Vec<String>),
+	}
+
+	Ok(match Option::<Authors>::deserialize(deserializer)? {
+		Some(Authors::One(name)) => Some(vec![name]),
+		Some(Authors::Many(names)) => Some(names),
+		None => None,
+	})
+}
+
+// Pipes markdown through a shell command, killing it once the timeout passes
+fn run_preprocessor(
+	command: &str,
+	markdown: &str,
+	path: &Path,
+	timeout: Duration,
+) -> Result<String> {
+	let mut child = shell(command)
+		.env("RUM_DOCUMENT", path)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.with_context(|| format!("Failed to run `{}`", command))?;
+
+	// Feed and drain the pipes on their own threads so a chatty command cannot deadlock
+	let mut stdin = child
+		.stdin
+		.take()
+		.context("Preprocessor stdin unavailable")?;
+	let input = markdown.to_string();
+	let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+	let mut stdout = child
+		.stdout
+		.take()
+		.context("Preprocessor stdout unavailable")?;
+	let reader = thread::spawn(move || {
+		let mut output = String::new();
+		stdout.read_to_string(&mut output).map(|_| output)
+	});
+	let mut stderr = child
+		.stderr
+		.take()
+		.context("Preprocessor stderr unavailable")?;
+	let errors = thread::spawn(move || {
+		let mut output = String::new();
+		let _ = stderr.read_to_string(&mut output);
+		output
+	});
+
+	let started = Instant::now();
+	let status = loop {
+		if let Some(status) = child.try_wait()? {
+			break status;
+		}
+		if started.elapsed() >= timeout {
+			let _ = child.kill();
+			let _ = child.wait();
+			bail!("`{}` timed out after {}s", command, timeout.as_secs());
+		}
+		thread::sleep(Duration::from_millis(10));
+	};
+
+	// A command that exits without reading stdin breaks the pipe, which is fine
+	let _ = writer.join();
+	let output = reader
+		.join()
+		.map_err(|_| anyhow!("Preprocessor output reader panicked"))??;
+	let errors = errors.join().unwrap_or_default();
+
+	if !status.success() {
+		bail!("`{}` exited with {}: {}", command, status, errors.trim());
+	}
+	Ok(output)
+}
+
+fn shell(command: &str) -> Command {
+	if cfg!(windows) {
+		let mut shell = Command::new("cmd");
+		shell.arg("/C").arg(command);
+		shell
+	} else {
+		let mut shell = Command::new("sh");
+		shell.arg("-c").arg(command);
+		shell
+	}
+}
+
+// Converts with docutils and keeps only the <body> contents, the page template supplies the rest
+pub fn rst_to_html(content: &str, rst2html_path: &Path) -> Result<String> {
+	let mut child = match Command::new(rst2html_path)
+		.args(["--no-doc-title", "--no-section-numbering", "-", "-"])
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+	{
+		Ok(child) => child,
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => bail!(
+			"{} not found, install docutils or set build.rst2html_path in rum.toml",
+			rst2html_path.display()
+		),
+		Err(e) => {
+			return Err(e).with_context(|| format!("Failed to run {}", rst2html_path.display()))
+		}
+	};
+
+	let mut stdin = child.stdin.take().context("rst2html stdin unavailable")?;
+	let input = content.to_string();
+	let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+	let output = child.wait_with_output()?;
+	writer
+		.join()
+		.map_err(|_| anyhow!("rst2html input writer panicked"))??;
+
+	if !output.status.success() {
+		bail!(
+			"rst2html exited with {}: {}",
+			output.status,
+			String::from_utf8_lossy(&output.stderr).trim()
+		);
+	}
+	let html = String::from_utf8(output.stdout)?;
+	let body = html
+		.split_once("<body>")
+		.and_then(|(_, rest)| rest.rsplit_once("</body>"))
+		.map_or(html.as_str(), |(body, _)| body);
+	Ok(body.trim().to_string())
+}
+
+// Notebook text fields are either a single string or a list of lines
+fn notebook_text(value: &serde_json::Value) -> String {
+	match value {
+		serde_json::Value::Array(lines) => lines.iter().filter_map(|l| l.as_str()).collect(),
+		value => value.as_str().unwrap_or_default().to_string(),
+	}
+}
+
+// Highlighting language for a file name, by extension or for well-known extensionless names
+pub fn detect_language(filename: &str) -> Option<String> {
+	let name = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+	let language = match name {
+		"Dockerfile" | "Containerfile" => "dockerfile",
+		"Makefile" | "GNUmakefile" => "makefile",
+		"CMakeLists.txt" => "cmake",
+		"Gemfile" | "Rakefile" => "ruby",
+		"Jenkinsfile" => "groovy",
+		".bashrc" | ".bash_profile" | ".profile" => "bash",
+		".zshrc" => "zsh",
+		".gitignore" | ".dockerignore" => "gitignore",
+		".editorconfig" => "ini",
+		_ => {
+			let ext = name.rsplit_once('.')?.1.to_ascii_lowercase();
+			match ext.as_str() {
+				"rs" => "rust",
+				"toml" => "toml",
+				"yaml" | "yml" => "yaml",
+				"json" | "jsonc" => "json",
+				"json5" => "json5",
+				"xml" | "xsd" | "xsl" | "svg" | "plist" => "xml",
+				"html" | "htm" | "xhtml" => "html",
+				"css" => "css",
+				"scss" => "scss",
+				"sass" => "sass",
+				"less" => "less",
+				"js" | "mjs" | "cjs" => "javascript",
+				"jsx" => "jsx",
+				"ts" | "mts" | "cts" => "typescript",
+				"tsx" => "tsx",
+				"vue" => "vue",
+				"svelte" => "svelte",
+				"py" | "pyw" | "pyi" => "python",
+				"rb" | "gemspec" => "ruby",
+				"go" => "go",
+				"java" => "java",
+				"kt" | "kts" => "kotlin",
+				"scala" | "sc" => "scala",
+				"groovy" | "gradle" => "groovy",
+				"c" | "h" => "c",
+				"cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+				"cs" => "csharp",
+				"fs" | "fsx" => "fsharp",
+				"m" | "mm" => "objectivec",
+				"swift" => "swift",
+				"zig" => "zig",
+				"nim" => "nim",
+				"d" => "d",
+				"hs" => "haskell",
+				"ml" | "mli" => "ocaml",
+				"ex" | "exs" => "elixir",
+				"erl" | "hrl" => "erlang",
+				"clj" | "cljs" | "cljc" | "edn" => "clojure",
+				"lisp" | "el" => "lisp",
+				"scm" | "ss" => "scheme",
+				"lua" => "lua",
+				"pl" | "pm" => "perl",
+				"php" => "php",
+				"r" => "r",
+				"jl" => "julia",
+				"dart" => "dart",
+				"sh" | "bash" => "bash",
+				"zsh" => "zsh",
+				"fish" => "fish",
+				"ps1" | "psm1" => "powershell",
+				"bat" | "cmd" => "batch",
+				"sql" => "sql",
+				"graphql" | "gql" => "graphql",
+				"proto" => "protobuf",
+				"tf" | "tfvars" | "hcl" => "hcl",
+				"nix" => "nix",
+				"ini" | "cfg" | "conf" => "ini",
+				"properties" => "properties",
+				"env" => "bash",
+				"csv" => "csv",
+				"md" | "markdown" => "markdown",
+				"rst" => "rst",
+				"adoc" => "asciidoc",
+				"tex" | "sty" | "cls" => "latex",
+				"diff" | "patch" => "diff",
+				"dockerfile" => "dockerfile",
+				"mk" => "makefile",
+				"cmake" => "cmake",
+				"asm" | "s" => "asm",
+				"wat" => "wasm",
+				"sol" => "solidity",
+				"v" | "sv" => "verilog",
+				"vhd" | "vhdl" => "vhdl",
+				"vim" => "vim",
+				_ => return None,
+			}
+		}
+	};
+	Some(language.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceFormat {
+	Markdown,
+	AsciiDoc,
+	ReStructuredText,
+	PlainText,
+}
+
+impl SourceFormat {
+	// Notebooks are converted to markdown, so they count as markdown here
+	pub fn from_path(path: &Path) -> Self {
+		match path.extension().and_then(|e| e.to_str()) {
+			Some("adoc" | "asciidoc") => SourceFormat::AsciiDoc,
+			Some("rst") => SourceFormat::ReStructuredText,
+			Some("txt") => SourceFormat::PlainText,
+			_ => SourceFormat::Markdown,
+		}
+	}
+}
+
+static WIKI_LINK_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r"\[\[([^\]|#]+)(?:#([^\]|]+))?(?:\|([^\]]+))?\]\]").unwrap());
+static SHORTCODE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{([^}]+)\}\}").unwrap());
+static INCLUDE_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r"\{\{\s*include:\s*([^}]+?)\s*\}\}").unwrap());
+static YOUTUBE_ID_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r#"^\s+id="([A-Za-z0-9_-]+)"$"#).unwrap());
+static YOUTUBE_EMBED_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r#"youtube-nocookie\.com/embed/([A-Za-z0-9_-]+)""#).unwrap());
+const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "svg", "webp"];
+
+static MD_LINK_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r"\[([^\]]+)\]\(([^)]+)\)").unwrap());
+
+// Whether text holds an opening {{name ...}} shortcode
+fn opens_shortcode(text: &str, name: &str) -> bool {
+	SHORTCODE_REGEX.captures_iter(text).any(|caps| {
+		let inner = caps[1].trim();
+		inner.split(char::is_whitespace).next() == Some(name)
+	})
 }
 
 #[derive(Debug, Clone)]
 pub struct ContentProcessor {
-	wiki_link_regex: Regex,
-	shortcode_regex: Regex,
+	highlighter: Option<Highlighter>,
+	plugins: Plugins,
+}
+
+// syntect state for build-time highlighting, only loaded when syntax.enabled is set
+#[derive(Debug, Clone)]
+struct Highlighter {
+	syntax_set: SyntaxSet,
+	css: String,
 }
 
 impl ContentProcessor {
 	pub fn new() -> Self {
+		Self::new_with_config(&Config::default())
+	}
+
+	pub fn new_with_config(config: &Config) -> Self {
+		let highlighter = config.syntax.enabled.then(|| {
+			let mut themes = ThemeSet::load_defaults().themes;
+			let theme = themes.remove(&config.syntax.theme).unwrap_or_else(|| {
+				warn!(
+					"Unknown syntax theme '{}', using base16-ocean.dark",
+					config.syntax.theme
+				);
+				themes.remove("base16-ocean.dark").unwrap_or_default()
+			});
+			let css =
+				css_for_theme_with_class_style(&theme, ClassStyle::Spaced).unwrap_or_default();
+			Highlighter {
+				syntax_set: SyntaxSet::load_defaults_newlines(),
+				css,
+			}
+		});
+
 		Self {
-			wiki_link_regex: Regex::new(r"\[\[([^\]]+)\]\]").unwrap(),
-			shortcode_regex: Regex::new(r"\{\{([^}]+)\}\}").unwrap(),
+			highlighter,
+			plugins: Plugins::default(),
 		}
 	}
 
-	pub fn parse_document(path: &Path, base_path: &Path) -> Result<Document> {
+	pub fn with_plugins(mut self, plugins: Plugins) -> Self {
+		self.plugins = plugins;
+		self
+	}
+
+	// Stylesheet for the classes the highlighted blocks carry, empty when highlighting is off
+	pub fn syntax_css(&self) -> &str {
+		self.highlighter
+			.as_ref()
+			.map(|h| h.css.as_str())
+			.unwrap_or_default()
+	}
+
+	pub fn parse_document(
+		&self,
+		path: &Path,
+		base_path: &Path,
+		config: &Config,
+		include_stack: &mut Vec<PathBuf>,
+	) -> Result<Document> {
 		let content = fs::read_to_string(path)
 			.with_context(|| format!("Failed to read file: {}", path.display()))?;
 
-		let (frontmatter, markdown_content) = Self::extract_frontmatter(&content)?;
-
-		// Detect version from path
-		let version = Self::extract_version(path, base_path);
+		let format = SourceFormat::from_path(path);
+		let (frontmatter, markdown_content) = match path.extension().and_then(|e| e.to_str()) {
+			Some("ipynb") => Self::notebook_to_markdown(&content, config)
+				.with_context(|| format!("Invalid notebook: {}", path.display()))?,
+			// AsciiDoc keeps its metadata in header attributes unless YAML frontmatter is used
+			_ if format == SourceFormat::AsciiDoc && !content.starts_with("---") => {
+				asciidoc::extract_attributes(&content)
+			}
+			_ => Self::extract_frontmatter(&content)?,
+		};
 
-		// Process wiki links and shortcodes
-		let processed_content = Self::process_content(&markdown_content);
+		let mut warnings = Vec::new();
+		if let Some(lang) = frontmatter.lang.as_deref().filter(|l| !is_valid_lang(l)) {
+			warnings.push(ContentWarning::new(
+				"W013",
+				format!("Ignoring invalid frontmatter language '{}'", lang),
+			));
+		}
 
-		// Convert markdown to HTML
-		let html_content = Self::markdown_to_html(&processed_content);
+		// Detect locale and version from path, versions sit inside the locale directory
+		let locale = Self::extract_locale(path, base_path, &config.i18n.locales);
+		let version = match &locale {
+			Some(locale) => Self::extract_version(path, &base_path.join(locale), &config.site),
+			None => Self::extract_version(path, base_path, &config.site),
+		};
 
-		// Extract links
-		let links = Self::extract_links(&processed_content);
+		// Run the external preprocessor, the frontmatter one wins over the site-wide one
+		let markdown_content = match frontmatter
+			.preprocess
+			.as_ref()
+			.or(config.content.preprocess_command.as_ref())
+		{
+			Some(command) => run_preprocessor(
+				command,
+				&markdown_content,
+				path,
+				Duration::from_secs(config.content.preprocess_timeout),
+			)
+			.with_context(|| format!("Preprocessing failed for {}", path.display()))?,
+			None => markdown_content,
+		};
+		let word_count = markdown_content.split_ascii_whitespace().count();
+		let reading_time_minutes =
+			reading_time_minutes(word_count, config.display.words_per_minute);
 
 		let relative_path = path.strip_prefix(base_path).unwrap_or(path).to_path_buf();
+		// Taken before wiki links become Markdown ones, for markdown sources only
+		let mut wiki_fragments = Vec::new();
+		let (processed_content, html_content, toc_html, links, images) = match format {
+			// asciidoctor renders the whole body, wiki links and shortcodes are markdown only
+			SourceFormat::AsciiDoc => {
+				let html =
+					asciidoc::to_html(&markdown_content, config.build.asciidoctor_path.as_deref())
+						.with_context(|| {
+							format!("AsciiDoc conversion failed for {}", path.display())
+						})?;
+				(markdown_content, html, String::new(), vec![], vec![])
+			}
+			// A missing or failing rst2html should not stop the build, the source is shown as-is
+			SourceFormat::ReStructuredText => {
+				let rst2html = config
+					.build
+					.rst2html_path
+					.as_deref()
+					.unwrap_or(Path::new("rst2html"));
+				let html = match rst_to_html(&markdown_content, rst2html) {
+					Ok(html) => html,
+					Err(e) => {
+						warnings.push(ContentWarning::new(
+							"W012",
+							format!(
+								"ReStructuredText conversion failed, showing the source: {:#}",
+								e
+							),
+						));
+						format!("<pre>{}</pre>", escape_html(&markdown_content))
+					}
+				};
+				(markdown_content, html, String::new(), vec![], vec![])
+			}
+			_ => {
+				// Process includes, wiki links and shortcodes. Each page starts with nothing included.
+				let mut included = HashSet::new();
+				let markdown_content = Self::expand_includes(
+					&markdown_content,
+					path,
+					include_stack,
+					&mut included,
+					&mut warnings,
+				)?;
+				wiki_fragments = Self::extract_wiki_fragments(&markdown_content);
+				let processed_content =
+					Self::process_content(&markdown_content, version.as_deref());
+				let processed_content =
+					self.expand_plugin_shortcodes(&processed_content, &mut warnings);
+
+				// Convert markdown to HTML
+				let heading_offset = frontmatter
+					.heading_offset
+					.unwrap_or(config.content.heading_offset);
+				let toc = frontmatter.toc.unwrap_or(config.toc.enabled);
+				let (html_content, toc_html, removed) =
+					self.markdown_to_html(&processed_content, config, heading_offset, toc);
+				if !removed.is_empty() {
+					warnings.push(ContentWarning::new(
+						"W009",
+						format!("Removed unsafe HTML: {}", removed.join(", ")),
+					));
+				}
+
+				// Extract links
+				let links = Self::extract_links(&processed_content);
+				let images = Self::extract_images(&processed_content, &relative_path);
+				(processed_content, html_content, toc_html, links, images)
+			}
+		};
 
 		Ok(Document {
 			frontmatter,
 			content: processed_content,
 			html_content,
+			toc_html,
 			path: path.to_path_buf(),
 			relative_path,
 			version,
+			locale,
 			backlinks: vec![],
 			links,
+			wiki_fragments,
+			images,
+			word_count,
+			reading_time_minutes,
+			collections: vec![],
+			effective_config: None,
+			warnings,
 		})
 	}
 
 	fn extract_frontmatter(content: &str) -> Result<(Frontmatter, String)> {
+		// Editors on Windows may add a BOM and CRLF line endings, neither should hide the frontmatter
+		let content = content
+			.strip_prefix('\u{FEFF}')
+			.unwrap_or(content)
+			.replace("\r\n", "\n");
+		let content = content.as_str();
+
 		// Try YAML frontmatter
-		if content.starts_with("---\n") {
-			if let Some(end) = content[4..].find("\n---\n") {
-				let frontmatter_str = &content[4..end + 4];
-				let markdown = &content[end + 9..];
+		if let Some(rest) = content.strip_prefix("---\n") {
+			if let Some(end) = rest.find("\n---\n") {
+				let frontmatter_str = &rest[..end];
+				let markdown = &rest[end + 5..];
 
 				let frontmatter: Frontmatter =
 					serde_yaml::from_str(frontmatter_str).unwrap_or_default();
@@ -102,10 +667,10 @@ impl ContentProcessor {
 		}
 
 		// Try TOML frontmatter
-		if content.starts_with("+++\n") {
-			if let Some(end) = content[4..].find("\n+++\n") {
-				let frontmatter_str = &content[4..end + 4];
-				let markdown = &content[end + 9..];
+		if let Some(rest) = content.strip_prefix("+++\n") {
+			if let Some(end) = rest.find("\n+++\n") {
+				let frontmatter_str = &rest[..end];
+				let markdown = &rest[end + 5..];
 
 				if let Ok(frontmatter) = toml::from_str::<Frontmatter>(frontmatter_str) {
 					return Ok((frontmatter, markdown.to_string()));
@@ -116,14 +681,97 @@ impl ContentProcessor {
 		Ok((Frontmatter::default(), content.to_string()))
 	}
 
-	fn extract_version(path: &Path, base_path: &Path) -> Option<String> {
+	fn notebook_to_markdown(content: &str, config: &Config) -> Result<(Frontmatter, String)> {
+		let notebook: serde_json::Value = serde_json::from_str(content)?;
+		let metadata = &notebook["metadata"];
+		let language = metadata["kernelspec"]["language"]
+			.as_str()
+			.or(metadata["language_info"]["name"].as_str())
+			.unwrap_or_default();
+
+		let mut frontmatter = Frontmatter {
+			title: metadata["title"].as_str().map(|t| t.to_string()),
+			..Default::default()
+		};
+		if let Some(kernel) = metadata["kernelspec"]["display_name"].as_str() {
+			frontmatter
+				.extra
+				.insert("kernel".to_string(), serde_yaml::Value::from(kernel));
+		}
+
+		let mut markdown = String::new();
+		for cell in notebook["cells"].as_array().into_iter().flatten() {
+			let text = notebook_text(&cell["source"]);
+			match cell["cell_type"].as_str() {
+				Some("markdown") => {
+					if frontmatter.title.is_none() {
+						frontmatter.title = text
+							.lines()
+							.find_map(|l| l.strip_prefix("# "))
+							.map(|t| t.trim().to_string());
+					}
+					markdown.push_str(&text);
+				}
+				Some("code") => {
+					markdown.push_str(&format!("```{}\n{}\n```\n", language, text.trim_end()));
+					if config.content.notebook_include_outputs {
+						for output in cell["outputs"].as_array().into_iter().flatten() {
+							markdown.push_str(&Self::notebook_output_html(output));
+						}
+					}
+				}
+				_ => {}
+			}
+			markdown.push_str("\n\n");
+		}
+
+		Ok((frontmatter, markdown))
+	}
+
+	fn notebook_output_html(output: &serde_json::Value) -> String {
+		let data = &output["data"];
+
+		let html = match output["output_type"].as_str() {
+			Some("stream") => format!(
+				"<pre>{}</pre>",
+				escape_html(&notebook_text(&output["text"]))
+			),
+			Some("error") => format!(
+				"<pre>{}: {}</pre>",
+				escape_html(output["ename"].as_str().unwrap_or_default()),
+				escape_html(output["evalue"].as_str().unwrap_or_default())
+			),
+			_ if !data["text/html"].is_null() => notebook_text(&data["text/html"]),
+			_ if !data["image/png"].is_null() => format!(
+				"<img src=\"data:image/png;base64,{}\" alt=\"\">",
+				notebook_text(&data["image/png"]).trim()
+			),
+			_ if !data["text/plain"].is_null() => {
+				format!(
+					"<pre>{}</pre>",
+					escape_html(&notebook_text(&data["text/plain"]))
+				)
+			}
+			_ => return String::new(),
+		};
+
+		format!("\n<div class=\"notebook-output\">\n{}\n</div>\n", html)
+	}
+
+	// Directories named like a version, or listed in site.versions or site.version_aliases
+	fn extract_version(path: &Path, base_path: &Path, site: &SiteConfig) -> Option<String> {
 		let relative = path.strip_prefix(base_path).ok()?;
 		let components: Vec<_> = relative.components().collect();
 
 		if components.len() > 1 {
 			if let Some(component) = components.first() {
 				let version_str = component.as_os_str().to_string_lossy();
-				if version_str.starts_with('v') || version_str == "latest" {
+				if version_str.starts_with('v')
+					|| version_str == "latest"
+					|| parse_version(&version_str).is_some()
+					|| site.versions.iter().any(|v| *v == version_str)
+					|| site.version_aliases.contains_key(version_str.as_ref())
+				{
 					return Some(version_str.to_string());
 				}
 			}
@@ -132,61 +780,670 @@ impl ContentProcessor {
 		None
 	}
 
-	fn process_content(content: &str) -> String {
+	fn extract_locale(path: &Path, base_path: &Path, locales: &[String]) -> Option<String> {
+		let relative = path.strip_prefix(base_path).ok()?;
+		let mut components = relative.components();
+		let first = components.next()?.as_os_str().to_string_lossy();
+		// A file directly in the source directory has no locale
+		components.next()?;
+		locales.iter().find(|locale| **locale == first).cloned()
+	}
+
+	fn process_content(content: &str, version: Option<&str>) -> String {
 		let mut processed = content.to_string();
 
-		// Process wiki links - convert [[Page Name]] to Markdown links
-		let wiki_link_regex = Regex::new(r"\[\[([^\]]+)\]\]").unwrap();
-		processed = wiki_link_regex
+		// Process wiki links - convert [[Page Name]], [[Page Name|Text]] and [[Page Name#Section]]
+		// to Markdown links
+		processed = WIKI_LINK_REGEX
 			.replace_all(&processed, |caps: &regex::Captures| {
-				let page_name = caps.get(1).unwrap().as_str();
-				// Convert to slug for URL
-				let slug = page_name.to_lowercase().replace(' ', "-");
-				format!("[{}]({}.html)", page_name, slug)
+				let page_name = caps[1].trim();
+				// Without display text the link reads as written, section included
+				let (text, href) = match caps.get(2) {
+					Some(fragment) => (
+						format!("{}#{}", page_name, fragment.as_str().trim()),
+						format!(
+							"{}.html#{}",
+							wiki_link_slug(page_name),
+							slugify(fragment.as_str())
+						),
+					),
+					None => (
+						page_name.to_string(),
+						format!("{}.html", wiki_link_slug(page_name)),
+					),
+				};
+				let text = caps
+					.get(3)
+					.map_or(text.as_str(), |text| text.as_str().trim());
+				format!("[{}]({})", escape_html(text), href)
 			})
 			.to_string();
 
-		// Process shortcodes (basic implementation)
-		// {{note}}...{{/note}}
-		// {{youtube:ID}}
-		// etc.
+		// Block shortcodes, {{note}}...{{/note}} and friends
+		for kind in ["note", "warning", "tip", "danger"] {
+			processed = Self::expand_admonitions(&processed, kind);
+		}
+
+		// Inline shortcodes, anything unknown is left as written
+		SHORTCODE_REGEX
+			.replace_all(&processed, |caps: &regex::Captures| {
+				let shortcode = caps[1].trim();
+				if shortcode == "version" {
+					return version.unwrap_or_default().to_string();
+				}
+				if let Some(id) = shortcode
+					.strip_prefix("youtube")
+					.and_then(|args| YOUTUBE_ID_REGEX.captures(args))
+				{
+					return youtube_embed(&id[1]);
+				}
+				caps[0].to_string()
+			})
+			.to_string()
+	}
+
+	// Runs after process_content, so whatever shortcodes are left have no built-in handler.
+	// {{name args}}body{{/name}} passes the body along, a shortcode without a closing tag gets none.
+	fn expand_plugin_shortcodes(
+		&self,
+		content: &str,
+		warnings: &mut Vec<ContentWarning>,
+	) -> String {
+		if self.plugins.is_empty() {
+			return content.to_string();
+		}
+
+		let mut expanded = String::with_capacity(content.len());
+		let mut rest = content;
+		while let Some(caps) = SHORTCODE_REGEX.captures(rest) {
+			let shortcode = caps.get(0).unwrap();
+			expanded.push_str(&rest[..shortcode.start()]);
+			let after = &rest[shortcode.end()..];
+			let inner = caps[1].trim();
+			let (name, args) = inner
+				.split_once(char::is_whitespace)
+				.map_or((inner, ""), |(name, args)| (name, args.trim()));
+			// A closing tag only belongs to this shortcode when no other of the same name opens
+			// first, otherwise this one has no body and the tag closes a later one
+			let close = format!("{{{{/{}}}}}", name);
+			let (body, end) = match after.find(&close) {
+				Some(idx) if !opens_shortcode(&after[..idx], name) => {
+					(&after[..idx], shortcode.end() + idx + close.len())
+				}
+				_ => ("", shortcode.end()),
+			};
+
+			let html = if name.starts_with('/') {
+				None
+			} else {
+				self.plugins
+					.process_shortcode(name, args, body.trim())
+					.unwrap_or_else(|e| {
+						warnings.push(ContentWarning::new(
+							"W011",
+							format!("Shortcode {} left as written: {:#}", shortcode.as_str(), e),
+						));
+						None
+					})
+			};
+			match html {
+				Some(html) => {
+					expanded.push_str(&html);
+					rest = &rest[end..];
+				}
+				// Unhandled, the body stays in the page and may hold shortcodes of its own
+				None => {
+					expanded.push_str(shortcode.as_str());
+					rest = after;
+				}
+			}
+		}
+		expanded.push_str(rest);
+		expanded
+	}
+
+	// Splices in {{include: path}} files, resolved against the including file's directory and
+	// expanded recursively. The stack holds the files being expanded, meeting one again is a cycle.
+	// `included` holds every file already pasted into the page, a repeat is left out with a warning
+	fn expand_includes(
+		content: &str,
+		path: &Path,
+		include_stack: &mut Vec<PathBuf>,
+		included: &mut HashSet<PathBuf>,
+		warnings: &mut Vec<ContentWarning>,
+	) -> Result<String> {
+		let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+		if include_stack.contains(&canonical) {
+			let chain: Vec<_> = include_stack
+				.iter()
+				.chain([&canonical])
+				.map(|p| p.display().to_string())
+				.collect();
+			bail!("Circular include: {}", chain.join(" -> "));
+		}
+		if !INCLUDE_REGEX.is_match(content) {
+			return Ok(content.to_string());
+		}
+
+		include_stack.push(canonical);
+		let dir = path.parent().unwrap_or(Path::new(""));
+		let mut expand = || -> Result<String> {
+			let mut expanded = String::new();
+			let mut last = 0;
+			for caps in INCLUDE_REGEX.captures_iter(content) {
+				let shortcode = caps.get(0).unwrap();
+				expanded.push_str(&content[last..shortcode.start()]);
+				let target = dir.join(&caps[1]);
+				// A file already on the stack is a cycle, reported by the recursive call
+				let canonical = fs::canonicalize(&target).unwrap_or_else(|_| target.clone());
+				if !include_stack.contains(&canonical) && !included.insert(canonical) {
+					warnings.push(ContentWarning::new(
+						"W010",
+						format!(
+							"{} is included more than once, leaving out the repeat",
+							target.display()
+						),
+					));
+					last = shortcode.end();
+					continue;
+				}
+				let source = fs::read_to_string(&target).with_context(|| {
+					format!(
+						"Failed to include {} from {}",
+						target.display(),
+						path.display()
+					)
+				})?;
+				let (_, body) = Self::extract_frontmatter(&source)?;
+				expanded.push_str(
+					Self::expand_includes(&body, &target, include_stack, included, warnings)?
+						.trim_end(),
+				);
+				last = shortcode.end();
+			}
+			expanded.push_str(&content[last..]);
+			Ok(expanded)
+		};
+		let expanded = expand();
+		include_stack.pop();
+		expanded
+	}
 
-		processed
+	// Innermost pair first so a shortcode can be nested in one of its own kind
+	fn expand_admonitions(content: &str, kind: &str) -> String {
+		let (open, close) = (format!("{{{{{}}}}}", kind), format!("{{{{/{}}}}}", kind));
+		let title = format!("{}{}", kind[..1].to_uppercase(), &kind[1..]);
+
+		let mut content = content.to_string();
+		while let Some(end) = content.find(&close) {
+			let Some(start) = content[..end].rfind(&open) else {
+				break;
+			};
+			// Blank lines around the body let markdown inside the div be rendered
+			let body = content[start + open.len()..end].trim();
+			let html = format!(
+				"\n<div class=\"admonition {}\">\n<p class=\"admonition-title\">{}</p>\n\n{}\n\n</div>\n",
+				kind, title, body
+			);
+			content.replace_range(start..end + close.len(), &html);
+		}
+		content
 	}
 
-	fn markdown_to_html(markdown: &str) -> String {
-		use pulldown_cmark::{html, Options, Parser};
+	pub fn markdown_options() -> pulldown_cmark::Options {
+		use pulldown_cmark::Options;
 
 		let mut options = Options::empty();
 		options.insert(Options::ENABLE_STRIKETHROUGH);
 		options.insert(Options::ENABLE_TABLES);
 		options.insert(Options::ENABLE_TASKLISTS);
 		options.insert(Options::ENABLE_SMART_PUNCTUATION);
+		options
+	}
+
+	// Returns the page HTML, the table of contents for its headings when toc is set, and
+	// whatever the sanitizer removed from it
+	fn markdown_to_html(
+		&self,
+		markdown: &str,
+		config: &Config,
+		heading_offset: i8,
+		toc: bool,
+	) -> (String, String, Vec<String>) {
+		use pulldown_cmark::{html, Event, Parser};
+
+		let events: Vec<Event> = Parser::new_ext(markdown, Self::markdown_options()).collect();
+		let events = Self::shift_headings(events, heading_offset);
+		let events = Self::assign_heading_ids(events, &config.content.anchor_style);
+		let events = Self::heading_anchor_links(events);
+		let events = Self::tag_code_spans(events, &config.content.code_types);
+		let mut trusted = Trusted::default();
+		let events = Self::mermaid_diagrams(events, config, &mut trusted);
+		let events = Self::titled_code_blocks(events);
+		let events = self.highlight_code_blocks(events, &mut trusted);
+		let events = Self::trust_video_embeds(events, &mut trusted);
+
+		// Headings are read off the finished stream so the ToC links match the rendered ids
+		let toc_html = if toc {
+			Self::render_toc(&Self::table_of_contents(&events))
+		} else {
+			String::new()
+		};
 
-		let parser = Parser::new_ext(markdown, options);
 		let mut html_output = String::new();
-		html::push_html(&mut html_output, parser);
+		html::push_html(&mut html_output, events.into_iter());
+
+		// Generated fragments are set aside above, so only what the author wrote gets cleaned
+		let removed = if config.security.sanitize_html {
+			let (cleaned, removed) = sanitize(&html_output, &config.security);
+			html_output = cleaned;
+			removed
+		} else {
+			vec![]
+		};
+
+		(trusted.restore(&html_output), toc_html, removed)
+	}
+
+	fn table_of_contents(events: &[pulldown_cmark::Event]) -> Vec<TocEntry> {
+		use pulldown_cmark::{Event, Tag, TagEnd};
+
+		let mut entries = Vec::new();
+		for (idx, event) in events.iter().enumerate() {
+			let Event::Start(Tag::Heading {
+				level,
+				id: Some(anchor),
+				..
+			}) = event
+			else {
+				continue;
+			};
+			// The H1 is the page title, the contents start at H2
+			if (*level as u8) < 2 {
+				continue;
+			}
+			let title: String = events[idx + 1..]
+				.iter()
+				.take_while(|e| !matches!(e, Event::End(TagEnd::Heading(_))))
+				.filter_map(|e| match e {
+					Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+					_ => None,
+				})
+				.collect();
+			entries.push(TocEntry {
+				level: *level as u8,
+				title,
+				anchor: anchor.to_string(),
+			});
+		}
+		entries
+	}
+
+	// Deeper headings nest under the closest shallower one, skipped levels nest just one step
+	fn render_toc(entries: &[TocEntry]) -> String {
+		if entries.is_empty() {
+			return String::new();
+		}
+
+		let mut html = String::from("<nav class=\"toc\">");
+		let mut open: Vec<u8> = Vec::new();
+		for entry in entries {
+			while open.len() > 1 && open.last().is_some_and(|&level| entry.level < level) {
+				html.push_str("</li></ol>");
+				open.pop();
+			}
+			match open.last() {
+				Some(&level) if entry.level <= level => html.push_str("</li>"),
+				_ => {
+					html.push_str("<ol>");
+					open.push(entry.level);
+				}
+			}
+			html.push_str(&format!(
+				"<li><a href=\"#{}\">{}</a>",
+				escape_html(&entry.anchor),
+				escape_html(&entry.title)
+			));
+		}
+		for _ in open {
+			html.push_str("</li></ol>");
+		}
+		html.push_str("</nav>");
+		html
+	}
+
+	fn shift_headings<'a>(
+		mut events: Vec<pulldown_cmark::Event<'a>>,
+		offset: i8,
+	) -> Vec<pulldown_cmark::Event<'a>> {
+		use pulldown_cmark::{Event, HeadingLevel, Tag, TagEnd};
+
+		if offset == 0 {
+			return events;
+		}
+
+		let shift = |level: &mut HeadingLevel| {
+			let shifted = (*level as i32 + offset as i32).clamp(1, 6) as usize;
+			*level = HeadingLevel::try_from(shifted).unwrap_or(*level);
+		};
+		for event in events.iter_mut() {
+			match event {
+				Event::Start(Tag::Heading { level, .. }) => shift(level),
+				Event::End(TagEnd::Heading(level)) => shift(level),
+				_ => {}
+			}
+		}
+		events
+	}
+
+	// ```mermaid fences become inline SVG, or stay as source for a client-side renderer on failure
+	fn mermaid_diagrams<'a>(
+		events: Vec<pulldown_cmark::Event<'a>>,
+		config: &Config,
+		trusted: &mut Trusted,
+	) -> Vec<pulldown_cmark::Event<'a>> {
+		use pulldown_cmark::{CodeBlockKind, Event, Tag, TagEnd};
+
+		let mut output = Vec::with_capacity(events.len());
+		let mut diagram: Option<String> = None;
+		for event in events {
+			match (&event, &mut diagram) {
+				(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))), None)
+					if info.split_whitespace().next() == Some("mermaid") =>
+				{
+					diagram = Some(String::new());
+				}
+				(Event::Text(text), Some(code)) => code.push_str(text),
+				(Event::End(TagEnd::CodeBlock), Some(code)) => {
+					let html = match mermaid::render(code, &config.mermaid) {
+						Ok(svg) => format!("<div class=\"mermaid-diagram\">{}</div>\n", svg),
+						Err(e) => {
+							warn!("Mermaid diagram left unrendered: {:#}", e);
+							format!("<pre data-mermaid>{}</pre>\n", escape_html(code))
+						}
+					};
+					output.push(Event::Html(trusted.set_aside(html).into()));
+					diagram = None;
+				}
+				(_, Some(_)) => {}
+				(_, None) => output.push(event),
+			}
+		}
+		output
+	}
+
+	// The youtube shortcode's markup is fixed apart from a checked id, so it skips the sanitizer
+	fn trust_video_embeds<'a>(
+		events: Vec<pulldown_cmark::Event<'a>>,
+		trusted: &mut Trusted,
+	) -> Vec<pulldown_cmark::Event<'a>> {
+		use pulldown_cmark::Event;
+
+		events
+			.into_iter()
+			.map(|event| match &event {
+				Event::Html(html)
+					if YOUTUBE_EMBED_REGEX
+						.captures(html)
+						.is_some_and(|id| youtube_embed(&id[1]) == html.trim_end()) =>
+				{
+					Event::Html(trusted.set_aside(html.to_string()).into())
+				}
+				_ => event,
+			})
+			.collect()
+	}
+
+	fn titled_code_blocks<'a>(
+		events: Vec<pulldown_cmark::Event<'a>>,
+	) -> Vec<pulldown_cmark::Event<'a>> {
+		use pulldown_cmark::{CodeBlockKind, Event, Tag};
+
+		let mut output = Vec::with_capacity(events.len());
+		for event in events {
+			let Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) = &event else {
+				output.push(event);
+				continue;
+			};
+
+			// Info strings look like `toml title="Cargo.toml"` or just `title="Cargo.toml"`
+			let title = info.split_once("title=").map(|(_, rest)| {
+				let rest = rest.trim_start();
+				match rest.strip_prefix('"') {
+					Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+					None => rest.split_whitespace().next().unwrap_or_default(),
+				}
+			});
+			let Some(title) = title.filter(|t| !t.is_empty()) else {
+				output.push(event);
+				continue;
+			};
+
+			let language = info
+				.split_whitespace()
+				.next()
+				.filter(|token| !token.starts_with("title="))
+				.map(|token| token.to_string())
+				.or_else(|| detect_language(title))
+				.unwrap_or_default();
+
+			output.push(Event::Html(
+				format!("<div class=\"code-title\">{}</div>\n", escape_html(title)).into(),
+			));
+			output.push(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(
+				language.into(),
+			))));
+		}
+		output
+	}
+
+	// Fenced blocks in a language syntect knows become pre-highlighted HTML, the rest are left to Prism
+	fn highlight_code_blocks<'a>(
+		&self,
+		events: Vec<pulldown_cmark::Event<'a>>,
+		trusted: &mut Trusted,
+	) -> Vec<pulldown_cmark::Event<'a>> {
+		use pulldown_cmark::{CodeBlockKind, Event, Tag, TagEnd};
 
-		html_output
+		let Some(highlighter) = &self.highlighter else {
+			return events;
+		};
+
+		let mut output = Vec::with_capacity(events.len());
+		let mut block: Option<(&SyntaxReference, String)> = None;
+		for event in events {
+			match (&event, &mut block) {
+				(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))), None) => {
+					let syntax = info
+						.split_whitespace()
+						.next()
+						.and_then(|token| highlighter.syntax_set.find_syntax_by_token(token));
+					match syntax {
+						Some(syntax) => block = Some((syntax, String::new())),
+						None => output.push(event),
+					}
+				}
+				(Event::Text(text), Some((_, code))) => code.push_str(text),
+				(Event::End(TagEnd::CodeBlock), Some((syntax, code))) => {
+					let html = highlight_classed(code, syntax, &highlighter.syntax_set)
+						.unwrap_or_else(|e| {
+							warn!("Syntax highlighting failed: {}", e);
+							format!("<pre><code>{}</code></pre>\n", escape_html(code))
+						});
+					output.push(Event::Html(trusted.set_aside(html).into()));
+					block = None;
+				}
+				(_, Some(_)) => {}
+				(_, None) => output.push(event),
+			}
+		}
+		output
+	}
+
+	fn tag_code_spans<'a>(
+		events: Vec<pulldown_cmark::Event<'a>>,
+		code_types: &[String],
+	) -> Vec<pulldown_cmark::Event<'a>> {
+		use pulldown_cmark::Event;
+
+		events
+			.into_iter()
+			.map(|event| {
+				let Event::Code(text) = &event else {
+					return event;
+				};
+				// `{path}/etc/hosts` becomes <code class="code-path">/etc/hosts</code>
+				let tagged = text.strip_prefix('{').and_then(|rest| rest.split_once('}'));
+				match tagged {
+					Some((tag, code)) if code_types.iter().any(|t| t == tag) => Event::InlineHtml(
+						format!(
+							"<code class=\"code-{}\">{}</code>",
+							escape_html(tag),
+							escape_html(code)
+						)
+						.into(),
+					),
+					_ => event,
+				}
+			})
+			.collect()
+	}
+
+	fn assign_heading_ids<'a>(
+		mut events: Vec<pulldown_cmark::Event<'a>>,
+		anchor_style: &str,
+	) -> Vec<pulldown_cmark::Event<'a>> {
+		use pulldown_cmark::{Event, Tag, TagEnd};
+
+		// Section counters for H2 through H6
+		let mut counters = [0usize; 5];
+		// Explicit {#id} attributes are taken first, generated ones get -2, -3 and so on
+		let mut slugs = SlugDeduplicator::new();
+		for event in &events {
+			if let Event::Start(Tag::Heading { id: Some(id), .. }) = event {
+				slugs.reserve(id);
+			}
+		}
+
+		for idx in 0..events.len() {
+			let level = match &events[idx] {
+				Event::Start(Tag::Heading {
+					level, id: None, ..
+				}) => *level as usize,
+				_ => continue,
+			};
+
+			let anchor = if anchor_style == "sequential" && level >= 2 {
+				// Numbered from the H2 level down, e.g. section-1, section-1-2
+				counters[level - 2] += 1;
+				for counter in counters[level - 1..].iter_mut() {
+					*counter = 0;
+				}
+				let parts: Vec<String> = counters[..level - 1]
+					.iter()
+					.map(|c| c.to_string())
+					.collect();
+				format!("section-{}", parts.join("-"))
+			} else {
+				let text: String = events[idx + 1..]
+					.iter()
+					.take_while(|e| !matches!(e, Event::End(TagEnd::Heading(_))))
+					.filter_map(|e| match e {
+						Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+						_ => None,
+					})
+					.collect();
+				slugify(&text)
+			};
+			let anchor = if anchor.is_empty() {
+				"section".to_string()
+			} else {
+				anchor
+			};
+
+			let unique = slugs.unique(&anchor);
+			if let Event::Start(Tag::Heading { id, .. }) = &mut events[idx] {
+				*id = Some(unique.into());
+			}
+		}
+
+		events
+	}
+
+	// A trailing pilcrow link inside every heading, so readers can grab the section URL
+	fn heading_anchor_links<'a>(
+		events: Vec<pulldown_cmark::Event<'a>>,
+	) -> Vec<pulldown_cmark::Event<'a>> {
+		use pulldown_cmark::{Event, Tag, TagEnd};
+
+		let mut output = Vec::with_capacity(events.len());
+		let mut anchor: Option<String> = None;
+		for event in events {
+			match &event {
+				Event::Start(Tag::Heading { id, .. }) => {
+					anchor = id.as_ref().map(|id| id.to_string());
+				}
+				Event::End(TagEnd::Heading(_)) => {
+					if let Some(id) = anchor.take() {
+						output.push(Event::InlineHtml(
+							format!(
+								"<a class=\"anchor-link\" href=\"#{}\" aria-label=\"Link to section\">¶</a>",
+								escape_html(&id)
+							)
+							.into(),
+						));
+					}
+				}
+				_ => {}
+			}
+			output.push(event);
+		}
+		output
+	}
+
+	fn extract_images(content: &str, relative_path: &Path) -> Vec<PathBuf> {
+		use pulldown_cmark::{Event, Parser, Tag};
+
+		let mut images = Vec::new();
+		for event in Parser::new_ext(content, Self::markdown_options()) {
+			if let Event::Start(Tag::Image { dest_url, .. }) = event {
+				if let Some(image) = resolve_image(relative_path, &dest_url) {
+					if !images.contains(&image) {
+						images.push(image);
+					}
+				}
+			}
+		}
+		images
+	}
+
+	// Page and heading of every [[Page Name#Section]] link, as written
+	fn extract_wiki_fragments(content: &str) -> Vec<(String, String)> {
+		WIKI_LINK_REGEX
+			.captures_iter(content)
+			.filter_map(|cap| {
+				let fragment = cap.get(2)?;
+				Some((
+					cap[1].trim().to_string(),
+					fragment.as_str().trim().to_string(),
+				))
+			})
+			.collect()
 	}
 
 	fn extract_links(content: &str) -> Vec<String> {
 		let mut links = Vec::new();
 
-		// Extract wiki links [[Page Name]]
-		let wiki_link_regex = Regex::new(r"\[\[([^\]]+)\]\]").unwrap();
-		for cap in wiki_link_regex.captures_iter(content) {
-			if let Some(link) = cap.get(1) {
-				links.push(link.as_str().to_string());
-			}
+		// Extract wiki links [[Page Name]], only the page of [[Page Name#Section|Text]] is recorded
+		for cap in WIKI_LINK_REGEX.captures_iter(content) {
+			links.push(cap[1].trim().to_string());
 		}
 
 		// Extract Markdown links
-		let md_link_regex = Regex::new(r"\[([^\]]+)\]\(([^)]+)\)").unwrap();
-		for cap in md_link_regex.captures_iter(content) {
+		for cap in MD_LINK_REGEX.captures_iter(content) {
 			if let Some(link) = cap.get(2) {
 				let link_str = link.as_str();
-				if !link_str.starts_with("http") {
+				if is_internal_link(link_str) {
 					links.push(link_str.to_string());
 				}
 			}
@@ -196,6 +1453,124 @@ impl ContentProcessor {
 	}
 }
 
+// Stylesheet for the classes of highlighted blocks in one of syntect's bundled themes
+pub fn syntax_theme_css(name: &str) -> Result<String> {
+	let themes = ThemeSet::load_defaults().themes;
+	let Some(theme) = themes.get(name) else {
+		let names: Vec<_> = themes.keys().map(String::as_str).collect();
+		bail!(
+			"Unknown syntax theme '{}', expected one of: {}",
+			name,
+			names.join(", ")
+		);
+	};
+	css_for_theme_with_class_style(theme, ClassStyle::Spaced)
+		.map_err(|e| anyhow!("Failed to write CSS for syntax theme '{}': {}", name, e))
+}
+
+// Scope names as classes rather than inline colours, so the stylesheet decides the theme
+fn highlight_classed(
+	code: &str,
+	syntax: &SyntaxReference,
+	syntax_set: &SyntaxSet,
+) -> Result<String, syntect::Error> {
+	let mut generator =
+		ClassedHTMLGenerator::new_with_class_style(syntax, syntax_set, ClassStyle::Spaced);
+	for line in LinesWithEndings::from(code) {
+		generator.parse_html_for_line_which_includes_newline(line)?;
+	}
+	Ok(format!(
+		"<pre class=\"code\"><code>{}</code></pre>\n",
+		generator.finalize()
+	))
+}
+
+fn youtube_embed(id: &str) -> String {
+	format!(
+		"<div class=\"video-embed\"><iframe src=\"https://www.youtube-nocookie.com/embed/{}\" \
+		title=\"YouTube video\" loading=\"lazy\" allowfullscreen \
+		allow=\"accelerometer; clipboard-write; encrypted-media; gyroscope; picture-in-picture\">\
+		</iframe></div>",
+		id
+	)
+}
+
+// Rounded up so a short page still reads as one minute
+pub fn reading_time_minutes(word_count: usize, words_per_minute: usize) -> usize {
+	word_count.div_ceil(words_per_minute.max(1)).max(1)
+}
+
+// Locale of a document, untranslated pages belong to i18n.default_locale
+pub fn document_locale<'a>(doc: &'a Document, config: &'a Config) -> &'a str {
+	doc.locale.as_deref().unwrap_or(&config.i18n.default_locale)
+}
+
+// Source path shared by a page and its translations, i.e. without the locale directory
+pub fn translation_key(doc: &Document) -> &Path {
+	match &doc.locale {
+		Some(locale) => doc
+			.relative_path
+			.strip_prefix(locale)
+			.unwrap_or(&doc.relative_path),
+		None => &doc.relative_path,
+	}
+}
+
+// File name stem a wiki link to the page points at
+pub fn wiki_link_slug(page_name: &str) -> String {
+	page_name.trim().to_lowercase().replace(' ', "-")
+}
+
+// Source-relative path of an image referenced from a document, None for remote or non-image sources
+pub fn resolve_image(document: &Path, src: &str) -> Option<PathBuf> {
+	if !is_internal_link(src) {
+		return None;
+	}
+	let src = src.split(['#', '?']).next().unwrap_or_default();
+	let extension = Path::new(src).extension()?.to_string_lossy().to_lowercase();
+	if !IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+		return None;
+	}
+
+	let mut resolved = match src.strip_prefix('/') {
+		Some(_) => PathBuf::new(),
+		None => document.parent().map(Path::to_path_buf).unwrap_or_default(),
+	};
+	for component in Path::new(src).components() {
+		match component {
+			std::path::Component::Normal(part) => resolved.push(part),
+			// Nothing above the source directory gets copied
+			std::path::Component::ParentDir if !resolved.pop() => return None,
+			_ => {}
+		}
+	}
+	Some(resolved)
+}
+
+// Anything with a scheme (https:, mailto:) or host points off-site, and a bare #anchor stays on the page
+pub fn is_internal_link(link: &str) -> bool {
+	if link.starts_with('#') || link.starts_with("//") {
+		return false;
+	}
+	let scheme = link.split_once(':').map(|(scheme, _)| scheme);
+	!scheme.is_some_and(|s| {
+		s.starts_with(|c: char| c.is_ascii_alphabetic())
+			&& s.chars()
+				.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+	})
+}
+
+// Absolute http(s) targets of Markdown links, in order of appearance
+pub fn external_links(content: &str) -> Vec<String> {
+	MD_LINK_REGEX
+		.captures_iter(content)
+		.filter_map(|cap| cap.get(2))
+		.map(|link| link.as_str())
+		.filter(|link| link.starts_with("http://") || link.starts_with("https://"))
+		.map(str::to_string)
+		.collect()
+}
+
 impl Default for ContentProcessor {
 	fn default() -> Self {
 		Self::new()
@@ -220,8 +1595,605 @@ description: Example
 		let (fm, md) = ContentProcessor::extract_frontmatter(content).unwrap();
 		assert_eq!(fm.title, Some("Test Page".to_string()));
 		assert_eq!(fm.version, Some("0.1".to_string()));
-		assert_eq!(fm.author, Some("night0721".to_string()));
+		assert_eq!(fm.authors, Some(vec!["night0721".to_string()]));
 		assert_eq!(fm.description, Some("Example".to_string()));
 		assert!(md.contains("Content here"));
+
+		let (fm, _) =
+			ContentProcessor::extract_frontmatter("---\nauthors: [Ada, Grace]\n---\n").unwrap();
+		assert_eq!(
+			fm.authors,
+			Some(vec!["Ada".to_string(), "Grace".to_string()])
+		);
+	}
+
+	#[test]
+	fn test_extract_links() {
+		let content = ContentProcessor::process_content(
+			"See [[Getting Started]], [setup](../guide/setup.md#install), [home](https://example.com), [mail](mailto:me@example.com), [cdn](//cdn.example.com/x.js) and [top](#top).",
+			None,
+		);
+		assert_eq!(
+			ContentProcessor::extract_links(&content),
+			vec!["getting-started.html", "../guide/setup.md#install"]
+		);
+		assert_eq!(external_links(&content), vec!["https://example.com"]);
+	}
+
+	#[test]
+	fn test_wiki_links() {
+		let process = |content: &str| ContentProcessor::process_content(content, None);
+
+		assert_eq!(process("[[Setup]]"), "[Setup](setup.html)");
+		assert_eq!(
+			process("[[Setup|the setup guide]]"),
+			"[the setup guide](setup.html)"
+		);
+		assert_eq!(
+			process("[[Getting Started | Start here ]]"),
+			"[Start here](getting-started.html)"
+		);
+		assert_eq!(
+			process("[[Getting Started#First Steps]]"),
+			"[Getting Started#First Steps](getting-started.html#first-steps)"
+		);
+		assert_eq!(
+			process("[[Getting Started#First Steps|first steps]]"),
+			"[first steps](getting-started.html#first-steps)"
+		);
+		assert_eq!(
+			process("[[Tips & Tricks|<b>Bold</b> & more]]"),
+			"[&lt;b&gt;Bold&lt;/b&gt; &amp; more](tips-&-tricks.html)"
+		);
+
+		let processor = ContentProcessor::new();
+		let (html, _, _) = processor.markdown_to_html(
+			&process("[[Tips & Tricks|<b>Bold</b>]]"),
+			&Config::default(),
+			0,
+			false,
+		);
+		assert!(html.contains("&lt;b&gt;Bold&lt;/b&gt;</a>"));
+		assert!(!html.contains("<b>"));
+
+		// Only the page is recorded, so backlinks resolve to it
+		let links =
+			ContentProcessor::extract_links("[[Setup|the setup guide]] and [[Usage#Flags]]");
+		assert_eq!(links, vec!["Setup", "Usage"]);
+		assert_eq!(
+			ContentProcessor::extract_links(&process("[[Setup|the setup guide]]")),
+			vec!["setup.html"]
+		);
+	}
+
+	#[test]
+	fn test_wiki_link_fragments() {
+		let process = |content: &str| ContentProcessor::process_content(content, None);
+		let fragments = ContentProcessor::extract_wiki_fragments;
+
+		// Bare
+		assert_eq!(
+			process("[[Configuration]]"),
+			"[Configuration](configuration.html)"
+		);
+		assert!(fragments("[[Configuration]]").is_empty());
+
+		// Fragment only
+		assert_eq!(
+			process("[[Configuration#Database Settings]]"),
+			"[Configuration#Database Settings](configuration.html#database-settings)"
+		);
+		assert_eq!(
+			fragments("[[Configuration#Database Settings]]"),
+			vec![("Configuration".to_string(), "Database Settings".to_string())]
+		);
+
+		// Alias only
+		assert_eq!(
+			process("[[Configuration|the config]]"),
+			"[the config](configuration.html)"
+		);
+		assert!(fragments("[[Configuration|the config]]").is_empty());
+
+		// Fragment and alias
+		assert_eq!(
+			process("[[Configuration#database-settings|databases]]"),
+			"[databases](configuration.html#database-settings)"
+		);
+		assert_eq!(
+			fragments("[[Configuration#database-settings|databases]]"),
+			vec![("Configuration".to_string(), "database-settings".to_string())]
+		);
+		assert_eq!(
+			ContentProcessor::extract_links("[[Configuration#database-settings|databases]]"),
+			vec!["Configuration"]
+		);
+
+		// Without a target page there is nothing to link to, it is left as written
+		assert_eq!(process("[[#Database Settings]]"), "[[#Database Settings]]");
+		assert_eq!(process("[[#Intro|intro]]"), "[[#Intro|intro]]");
+		assert!(fragments("[[#Database Settings]]").is_empty());
+		assert!(ContentProcessor::extract_links("[[#Database Settings]]").is_empty());
+	}
+
+	#[test]
+	fn test_includes() {
+		let dir = std::env::temp_dir().join(format!("rum-include-{}", std::process::id()));
+		fs::create_dir_all(dir.join("partials/nested")).unwrap();
+		fs::write(
+			dir.join("partials/note.md"),
+			"---\ntitle: Partial\n---\nShared *note*.\n\n{{include: nested/deep.md}}\n",
+		)
+		.unwrap();
+		fs::write(dir.join("partials/nested/deep.md"), "Deep [[Setup]].\n").unwrap();
+		fs::write(
+			dir.join("page.md"),
+			"# Page\n\n{{ include: partials/note.md }}\n\nAfter.\n",
+		)
+		.unwrap();
+
+		let processor = ContentProcessor::new();
+		let config = Config::default();
+		let mut stack = Vec::new();
+		let doc = processor
+			.parse_document(&dir.join("page.md"), &dir, &config, &mut stack)
+			.unwrap();
+		assert!(stack.is_empty());
+		assert!(doc.html_content.contains("<p>Shared <em>note</em>.</p>"));
+		assert!(doc
+			.html_content
+			.contains("<p>Deep <a href=\"setup.html\">Setup</a>.</p>"));
+		assert!(doc.html_content.contains("<p>After.</p>"));
+		assert!(!doc.content.contains("title: Partial"));
+		assert_eq!(doc.links, vec!["setup.html"]);
+
+		fs::write(dir.join("a.md"), "A\n{{include: b.md}}\n").unwrap();
+		fs::write(dir.join("b.md"), "B\n{{include: a.md}}\n").unwrap();
+		let error = processor
+			.parse_document(&dir.join("a.md"), &dir, &config, &mut Vec::new())
+			.unwrap_err();
+		assert!(format!("{:#}", error).contains("Circular include"));
+
+		fs::write(dir.join("broken.md"), "{{include: missing.md}}\n").unwrap();
+		let error = processor
+			.parse_document(&dir.join("broken.md"), &dir, &config, &mut Vec::new())
+			.unwrap_err();
+		assert!(format!("{:#}", error).contains("Failed to include"));
+
+		// Twice on one page, directly and through another include, is pasted once
+		fs::write(
+			dir.join("twice.md"),
+			"{{include: partials/note.md}}\n\n{{include: wrapper.md}}\n",
+		)
+		.unwrap();
+		fs::write(
+			dir.join("wrapper.md"),
+			"Wrapped.\n\n{{include: partials/note.md}}\n",
+		)
+		.unwrap();
+		let doc = processor
+			.parse_document(&dir.join("twice.md"), &dir, &config, &mut Vec::new())
+			.unwrap();
+		let html = &doc.html_content;
+		assert_eq!(html.matches("<p>Shared <em>note</em>.</p>").count(), 1);
+		assert_eq!(html.matches("<p>Deep ").count(), 1);
+		assert!(html.contains("<p>Wrapped.</p>"));
+		let codes: Vec<_> = doc.warnings.iter().map(|w| w.code.as_str()).collect();
+		assert_eq!(codes, ["W010"]);
+
+		// The guard starts over for the next page
+		let doc = processor
+			.parse_document(&dir.join("page.md"), &dir, &config, &mut Vec::new())
+			.unwrap();
+		assert!(doc.html_content.contains("<p>Shared <em>note</em>.</p>"));
+		assert!(doc.warnings.is_empty());
+
+		fs::remove_dir_all(&dir).ok();
+	}
+
+	#[test]
+	fn test_reading_time() {
+		assert_eq!(reading_time_minutes(0, 200), 1);
+		assert_eq!(reading_time_minutes(200, 200), 1);
+		assert_eq!(reading_time_minutes(201, 200), 2);
+		assert_eq!(reading_time_minutes(1000, 200), 5);
+		assert_eq!(reading_time_minutes(10, 0), 10);
+
+		let dir = std::env::temp_dir().join(format!("rum-words-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		fs::write(
+			dir.join("page.md"),
+			"---\ntitle: Not counted\n---\n# Seven words\n\nin  this\tshort\nfixture.\n",
+		)
+		.unwrap();
+		fs::write(dir.join("empty.md"), "---\ntitle: Empty\n---\n").unwrap();
+		fs::write(dir.join("long.md"), "word ".repeat(200)).unwrap();
+
+		let processor = ContentProcessor::new();
+		let mut config = Config::default();
+		let parse = |name: &str, config: &Config| {
+			processor
+				.parse_document(&dir.join(name), &dir, config, &mut Vec::new())
+				.unwrap()
+		};
+		let doc = parse("page.md", &config);
+		assert_eq!((doc.word_count, doc.reading_time_minutes), (7, 1));
+		let doc = parse("empty.md", &config);
+		assert_eq!((doc.word_count, doc.reading_time_minutes), (0, 1));
+		let doc = parse("long.md", &config);
+		assert_eq!((doc.word_count, doc.reading_time_minutes), (200, 1));
+
+		config.display.words_per_minute = 100;
+		let doc = parse("long.md", &config);
+		assert_eq!(doc.reading_time_minutes, 2);
+
+		fs::remove_dir_all(&dir).ok();
+	}
+
+	#[cfg(feature = "wasm-plugins")]
+	#[test]
+	fn test_plugin_shortcodes() {
+		let dir = std::env::temp_dir().join(format!("rum-plugins-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		fs::write(
+			dir.join("page.md"),
+			"Status: {{badge color=\"red\" label=\"foo\"}}\n\n{{badge color=\"green\"}}Stable{{/badge}} {{unknown}}\n",
+		)
+		.unwrap();
+
+		let plugins = Plugins::load(&[
+			Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/plugins/badge.wasm")
+		])
+		.unwrap();
+		let processor = ContentProcessor::new().with_plugins(plugins);
+		let doc = processor
+			.parse_document(
+				&dir.join("page.md"),
+				&dir,
+				&Config::default(),
+				&mut Vec::new(),
+			)
+			.unwrap();
+		assert!(doc
+			.html_content
+			.contains("<p>Status: <span class=\"badge badge-red\">foo</span></p>"));
+		assert!(doc
+			.html_content
+			.contains("<span class=\"badge badge-green\">Stable</span> {{unknown}}"));
+
+		fs::remove_dir_all(&dir).ok();
+	}
+
+	#[test]
+	fn test_restructuredtext() {
+		let dir = std::env::temp_dir().join(format!("rum-rst-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		fs::write(
+			dir.join("guide.rst"),
+			include_str!("../tests/fixtures/guide.rst"),
+		)
+		.unwrap();
+
+		let processor = ContentProcessor::new();
+		let mut config = Config::default();
+		config.build.rst2html_path = Some(PathBuf::from("/nonexistent/rst2html"));
+		let parse = |config: &Config| {
+			processor
+				.parse_document(&dir.join("guide.rst"), &dir, config, &mut Vec::new())
+				.unwrap()
+		};
+
+		// Without docutils the source is kept readable instead of failing the build
+		let doc = parse(&config);
+		assert_eq!(doc.frontmatter.title.as_deref(), Some("Guide"));
+		assert!(doc
+			.html_content
+			.starts_with("<pre>Installation\n============"));
+		assert!(!doc.html_content.contains("title: Guide"));
+		let missing = rst_to_html("Text", Path::new("/nonexistent/rst2html"));
+		assert!(missing.unwrap_err().to_string().contains("not found"));
+
+		// Older docutils releases only install the script with its .py suffix
+		let Some(rst2html) = ["rst2html", "rst2html.py"]
+			.into_iter()
+			.find(|name| Command::new(name).arg("--version").output().is_ok())
+		else {
+			fs::remove_dir_all(&dir).ok();
+			return;
+		};
+		config.build.rst2html_path = Some(PathBuf::from(rst2html));
+		let doc = parse(&config);
+		assert!(!doc.html_content.contains("<html"));
+		assert!(!doc.html_content.contains("title: Guide"));
+		assert!(doc.html_content.contains("id=\"installation\""));
+		assert!(doc.html_content.contains(">Installation</h1>"));
+		assert!(doc.html_content.contains(">Configuration</h2>"));
+		assert!(doc.html_content.contains("<li>Download a release</li>"));
+		assert!(doc
+			.html_content
+			.contains("<pre class=\"literal-block\">\n$ rum --version\nrum 0.1.0\n</pre>"));
+
+		fs::remove_dir_all(&dir).ok();
+	}
+
+	#[test]
+	fn test_notebook_to_markdown() {
+		let notebook = r##"{
+			"metadata": {"kernelspec": {"display_name": "Python 3", "language": "python"}},
+			"cells": [
+				{"cell_type": "markdown", "source": ["# Analysis\n", "Some text"]},
+				{"cell_type": "code", "source": "print(1)", "outputs": [{"output_type": "stream", "text": "1\n"}]}
+			]
+		}"##;
+		let (fm, md) =
+			ContentProcessor::notebook_to_markdown(notebook, &Config::default()).unwrap();
+		assert_eq!(fm.title, Some("Analysis".to_string()));
+		assert!(md.contains("```python\nprint(1)\n```"));
+		assert!(!md.contains("notebook-output"));
+	}
+
+	#[test]
+	fn test_titled_code_block_language() {
+		let html = ContentProcessor::new()
+			.markdown_to_html(
+				"```title=\"Cargo.toml\"\n[package]\n```",
+				&Config::default(),
+				0,
+				false,
+			)
+			.0;
+		assert!(html.contains("<div class=\"code-title\">Cargo.toml</div>"));
+		assert!(html.contains("class=\"language-toml\""));
+		assert_eq!(detect_language("src/main.rs"), Some("rust".to_string()));
+		assert_eq!(detect_language("notes"), None);
+	}
+
+	#[test]
+	fn test_mermaid_fallback() {
+		let mut config = Config::default();
+		config.mermaid.renderer = "cli".to_string();
+		config.mermaid.cli_path = Some(PathBuf::from("/nonexistent/mmdc"));
+		let html = ContentProcessor::new()
+			.markdown_to_html(
+				"Before\n\n```mermaid\ngraph TD\n  A --> B\n```\n\n```rust\nfn main() {}\n```\n",
+				&config,
+				0,
+				false,
+			)
+			.0;
+		assert!(html.contains("<pre data-mermaid>graph TD\n  A --&gt; B\n</pre>"));
+		assert!(!html.contains("language-mermaid"));
+		assert!(html.contains("<pre class=\"code\"><code>"));
+	}
+
+	#[test]
+	fn test_processor_is_thread_safe() {
+		// Documents are parsed from rayon worker threads sharing one processor
+		fn assert_send_sync<T: Send + Sync>() {}
+		assert_send_sync::<ContentProcessor>();
+	}
+
+	#[test]
+	fn test_syntax_highlighting() {
+		let markdown = "```rust\nfn main() {}\n```\n\n```unknownlang\nplain\n```\n";
+		let processor = ContentProcessor::new();
+		let html = processor
+			.markdown_to_html(markdown, &Config::default(), 0, false)
+			.0;
+		assert!(html.contains("<pre class=\"code\"><code>"));
+		assert!(html.contains(">fn</span>"));
+		assert!(!html.contains("style="));
+		assert!(!html.contains("language-rust"));
+		assert!(html.contains("class=\"language-unknownlang\""));
+		assert!(processor.syntax_css().contains(".code {"));
+
+		let mut config = Config::default();
+		config.syntax.enabled = false;
+		let processor = ContentProcessor::new_with_config(&config);
+		let html = processor.markdown_to_html(markdown, &config, 0, false).0;
+		assert!(html.contains("class=\"language-rust\""));
+		assert_eq!(processor.syntax_css(), "");
+	}
+
+	#[test]
+	fn test_table_of_contents() {
+		let markdown = "# Page\n\n## Install\n\n### From source\n\n#### Linux\n\n#### macOS\n\n### From `cargo`\n\n## Usage\n";
+		let (html, toc, _) =
+			ContentProcessor::new().markdown_to_html(markdown, &Config::default(), 0, true);
+		assert_eq!(
+			toc,
+			concat!(
+				"<nav class=\"toc\"><ol>",
+				"<li><a href=\"#install\">Install</a><ol>",
+				"<li><a href=\"#from-source\">From source</a><ol>",
+				"<li><a href=\"#linux\">Linux</a></li>",
+				"<li><a href=\"#macos\">macOS</a></li>",
+				"</ol></li>",
+				"<li><a href=\"#from-cargo\">From cargo</a></li>",
+				"</ol></li>",
+				"<li><a href=\"#usage\">Usage</a></li>",
+				"</ol></nav>"
+			)
+		);
+		assert!(!html.contains("class=\"toc\""));
+
+		let (_, toc, _) =
+			ContentProcessor::new().markdown_to_html(markdown, &Config::default(), 0, false);
+		assert!(toc.is_empty());
+	}
+
+	#[test]
+	fn test_crlf_frontmatter() {
+		let content = "\u{FEFF}+++\r\ntitle = \"Toml Page\"\r\n+++\r\n# Heading\r\nBody\r\n";
+		let (fm, md) = ContentProcessor::extract_frontmatter(content).unwrap();
+		assert_eq!(fm.title, Some("Toml Page".to_string()));
+		assert_eq!(md, "# Heading\nBody\n");
+
+		let content = "---\r\ntitle: Yaml Page\r\n---\r\nBody\r\n";
+		let (fm, md) = ContentProcessor::extract_frontmatter(content).unwrap();
+		assert_eq!(fm.title, Some("Yaml Page".to_string()));
+		assert_eq!(md, "Body\n");
+	}
+
+	#[test]
+	fn test_extra_fields_keep_order() {
+		let content = "---\ntitle: Page\nzeta: 1\nalpha: 2\nmid: 3\n---\n";
+		let (first, _) = ContentProcessor::extract_frontmatter(content).unwrap();
+		let (second, _) = ContentProcessor::extract_frontmatter(content).unwrap();
+
+		let keys: Vec<_> = first.extra.keys().map(|k| k.as_str()).collect();
+		assert_eq!(keys, vec!["zeta", "alpha", "mid"]);
+		assert_eq!(
+			serde_json::to_string(&first).unwrap(),
+			serde_json::to_string(&second).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_fractional_order() {
+		let (fm, _) = ContentProcessor::extract_frontmatter("---\norder: -1.5\n---\n").unwrap();
+		assert_eq!(fm.order, Some(-1.5));
+
+		let (fm, _) = ContentProcessor::extract_frontmatter("---\norder: 2\n---\n").unwrap();
+		assert_eq!(fm.order, Some(2.0));
+		assert_eq!(
+			serde_yaml::to_string(&fm)
+				.unwrap()
+				.lines()
+				.find(|l| l.starts_with("order")),
+			Some("order: 2")
+		);
+	}
+
+	#[test]
+	fn test_heading_anchor_links() {
+		let html = ContentProcessor::new()
+			.markdown_to_html(
+				"# Setup\n## Usage\n## Usage\n## Usage\n## ???\n## !!!",
+				&Config::default(),
+				0,
+				false,
+			)
+			.0;
+		assert!(html.contains("<h1 id=\"setup\">Setup<a class=\"anchor-link\" href=\"#setup\" aria-label=\"Link to section\">¶</a></h1>"));
+		assert!(html.contains("<h2 id=\"usage\">Usage<a class=\"anchor-link\" href=\"#usage\""));
+		assert!(html.contains("<h2 id=\"usage-2\">Usage<a class=\"anchor-link\" href=\"#usage-2\""));
+		assert!(html.contains("<h2 id=\"usage-3\">Usage<a class=\"anchor-link\" href=\"#usage-3\""));
+		assert!(html.contains("<h2 id=\"section\">???<a class=\"anchor-link\""));
+		assert!(html.contains("<h2 id=\"section-2\">!!!<a class=\"anchor-link\""));
+	}
+
+	#[test]
+	fn test_sequential_heading_ids() {
+		let mut config = Config::default();
+		config.content.anchor_style = "sequential".to_string();
+
+		let html = ContentProcessor::new()
+			.markdown_to_html(
+				"## One\n### Sub\n### Sub\n## Two\n### Sub",
+				&config,
+				0,
+				false,
+			)
+			.0;
+		assert!(html.contains("<h2 id=\"section-1\">One<a class=\"anchor-link\""));
+		assert!(html.contains("<h3 id=\"section-1-2\">Sub<a class=\"anchor-link\""));
+		assert!(html.contains("<h3 id=\"section-2-1\">Sub<a class=\"anchor-link\""));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn test_run_preprocessor() {
+		let output = run_preprocessor(
+			"tr a-z A-Z",
+			"hello\n",
+			Path::new("a.md"),
+			Duration::from_secs(5),
+		)
+		.unwrap();
+		assert_eq!(output, "HELLO\n");
+
+		let err = run_preprocessor(
+			"echo broken >&2; exit 3",
+			"",
+			Path::new("a.md"),
+			Duration::from_secs(5),
+		)
+		.unwrap_err();
+		assert!(err.to_string().contains("broken"));
+	}
+
+	#[test]
+	fn test_heading_offset() {
+		let html = ContentProcessor::new()
+			.markdown_to_html("# Title\n###### Deep", &Config::default(), 1, false)
+			.0;
+		assert!(html.contains("<h2 id=\"title\">Title<a class=\"anchor-link\""));
+		assert!(html.contains("<h6 id=\"deep\">Deep<a class=\"anchor-link\""));
+
+		let html = ContentProcessor::new()
+			.markdown_to_html("## Up", &Config::default(), -3, false)
+			.0;
+		assert!(html.contains("<h1 id=\"up\">Up<a class=\"anchor-link\""));
+	}
+
+	#[test]
+	fn test_tagged_code_spans() {
+		let config = Config::default();
+
+		let html = ContentProcessor::new()
+			.markdown_to_html("Edit `{path}/etc/hosts` or `{nope}x`", &config, 0, false)
+			.0;
+		assert!(html.contains("<code class=\"code-path\">/etc/hosts</code>"));
+		assert!(html.contains("<code>{nope}x</code>"));
+	}
+
+	#[test]
+	fn test_block_shortcodes() {
+		for (kind, title) in [
+			("note", "Note"),
+			("warning", "Warning"),
+			("tip", "Tip"),
+			("danger", "Danger"),
+		] {
+			let markdown = format!("{{{{{0}}}}}\nRun **cargo** `build`.\n{{{{/{0}}}}}", kind);
+			let processed = ContentProcessor::process_content(&markdown, None);
+			let html = ContentProcessor::new()
+				.markdown_to_html(&processed, &Config::default(), 0, false)
+				.0;
+			assert!(html.contains(&format!(
+				"<div class=\"admonition {}\">\n<p class=\"admonition-title\">{}</p>",
+				kind, title
+			)));
+			assert!(html.contains("<p>Run <strong>cargo</strong> <code>build</code>.</p>"));
+			assert!(html.trim_end().ends_with("</div>"));
+		}
+
+		let nested = "{{note}}\nOuter\n{{note}}\nInner\n{{/note}}\n{{/note}}";
+		let processed = ContentProcessor::process_content(nested, None);
+		assert_eq!(
+			processed.matches("<div class=\"admonition note\">").count(),
+			2
+		);
+		assert!(!processed.contains("{{"));
+		let html = ContentProcessor::new()
+			.markdown_to_html(&processed, &Config::default(), 0, false)
+			.0;
+		assert!(html.contains("<p>Outer</p>") && html.contains("<p>Inner</p>"));
+	}
+
+	#[test]
+	fn test_inline_shortcodes() {
+		let processed = ContentProcessor::process_content(
+			"Docs for {{version}}.\n\n{{youtube id=\"dQw4w9WgXcQ\"}}\n\n{{unknown}}",
+			Some("v2"),
+		);
+		assert!(processed.starts_with("Docs for v2."));
+		assert!(processed.contains("src=\"https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ\""));
+		assert!(processed.contains("{{unknown}}"));
+
+		let processed =
+			ContentProcessor::process_content("{{youtube id=\"x\" onload=\"alert(1)\"}}", None);
+		assert!(!processed.contains("iframe"));
 	}
 }
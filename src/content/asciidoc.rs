@@ -0,0 +1,187 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use super::{parse_date, Frontmatter};
+
+// Reads the `:name: value` attribute entries of an AsciiDoc header, which ends at the first empty line
+pub fn extract_attributes(content: &str) -> (Frontmatter, String) {
+	let content = content
+		.strip_prefix('\u{FEFF}')
+		.unwrap_or(content)
+		.replace("\r\n", "\n");
+
+	let mut frontmatter = Frontmatter::default();
+	// A header opens with the document title or an attribute entry, anything else is body
+	if !content.starts_with("= ") && !content.starts_with(':') {
+		return (frontmatter, content);
+	}
+
+	let mut header_end = 0;
+	for line in content.split_inclusive('\n') {
+		let entry = line.trim_end();
+		if entry.is_empty() {
+			break;
+		}
+		header_end += line.len();
+
+		if let Some(title) = entry.strip_prefix("= ") {
+			frontmatter
+				.title
+				.get_or_insert_with(|| title.trim().to_string());
+			continue;
+		}
+		// Author and revision lines or comments may sit in the header too, only attributes are read
+		let Some((name, value)) = entry
+			.strip_prefix(':')
+			.and_then(|rest| rest.split_once(':'))
+		else {
+			continue;
+		};
+		if name.is_empty() || name.ends_with('!') {
+			continue;
+		}
+		apply_attribute(&mut frontmatter, name, value.trim());
+	}
+
+	let body = content[header_end..].trim_start_matches('\n').to_string();
+	(frontmatter, body)
+}
+
+// Renders the body with asciidoctor, embedded output only since the page template supplies the rest
+pub fn to_html(source: &str, asciidoctor: Option<&Path>) -> Result<String> {
+	let program = asciidoctor.unwrap_or(Path::new("asciidoctor"));
+	let mut child = match Command::new(program)
+		.args([
+			"--backend",
+			"html5",
+			"--no-header-footer",
+			"--out-file",
+			"-",
+			"-",
+		])
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+	{
+		Ok(child) => child,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => bail!(
+			"{} not found, install asciidoctor or set build.asciidoctor_path in rum.toml",
+			program.display()
+		),
+		Err(e) => return Err(e).with_context(|| format!("Failed to run {}", program.display())),
+	};
+
+	// Written from its own thread so a large document cannot fill both pipes at once
+	let mut stdin = child
+		.stdin
+		.take()
+		.context("asciidoctor stdin unavailable")?;
+	let input = source.to_string();
+	let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+	let output = child.wait_with_output()?;
+	writer
+		.join()
+		.map_err(|_| anyhow!("asciidoctor input writer panicked"))??;
+
+	if !output.status.success() {
+		bail!(
+			"asciidoctor exited with {}: {}",
+			output.status,
+			String::from_utf8_lossy(&output.stderr).trim()
+		);
+	}
+	Ok(String::from_utf8(output.stdout)?)
+}
+
+fn apply_attribute(frontmatter: &mut Frontmatter, name: &str, value: &str) {
+	match name {
+		"doctitle" => frontmatter.title = Some(value.to_string()),
+		// `:authors:` lists several, separated by semicolons
+		"author" | "authors" => {
+			frontmatter.authors = Some(
+				value
+					.split(';')
+					.map(|a| a.trim().to_string())
+					.filter(|a| !a.is_empty())
+					.collect(),
+			)
+		}
+		"description" => frontmatter.description = Some(value.to_string()),
+		"keywords" => {
+			frontmatter.tags = Some(
+				value
+					.split(',')
+					.map(|t| t.trim().to_string())
+					.filter(|t| !t.is_empty())
+					.collect(),
+			)
+		}
+		"revdate" => frontmatter.date = parse_date(value),
+		_ => {
+			frontmatter
+				.extra
+				.insert(name.to_string(), serde_yaml::Value::from(value));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_extract_attributes() {
+		let content = "= Getting Started\n:author: Jane Doe\n:revdate: 2024-03-01\n:keywords: setup, install,\n:toc: left\n:sectnums!:\n\nBody text\n\n:not-an-attribute: here\n";
+		let (frontmatter, body) = extract_attributes(content);
+
+		assert_eq!(frontmatter.title.as_deref(), Some("Getting Started"));
+		assert_eq!(frontmatter.authors, Some(vec!["Jane Doe".to_string()]));
+		assert_eq!(
+			frontmatter.tags,
+			Some(vec!["setup".to_string(), "install".to_string()])
+		);
+		assert_eq!(frontmatter.date, parse_date("2024-03-01"));
+		assert!(frontmatter.date.is_some());
+		assert_eq!(frontmatter.extra["toc"].as_str(), Some("left"));
+		assert!(!frontmatter.extra.contains_key("sectnums!"));
+		assert!(!frontmatter.extra.contains_key("not-an-attribute"));
+		assert_eq!(body, "Body text\n\n:not-an-attribute: here\n");
+
+		let (frontmatter, _) = extract_attributes(":doctitle: Override\n= Ignored\n\nBody");
+		assert_eq!(frontmatter.title.as_deref(), Some("Override"));
+
+		let (frontmatter, body) = extract_attributes("Just a paragraph\n:with: colons\n");
+		assert!(frontmatter.extra.is_empty());
+		assert_eq!(body, "Just a paragraph\n:with: colons\n");
+	}
+
+	#[test]
+	fn test_to_html() {
+		// Needs asciidoctor on the PATH
+		if Command::new("asciidoctor")
+			.arg("--version")
+			.output()
+			.is_err()
+		{
+			return;
+		}
+
+		let (frontmatter, body) =
+			extract_attributes(include_str!("../../tests/fixtures/guide.adoc"));
+		assert_eq!(frontmatter.title.as_deref(), Some("Guide"));
+
+		let html = to_html(&body, None).unwrap();
+		assert!(!html.contains("<html"));
+		assert!(html.contains("<h2 id=\"_installation\">Installation</h2>"));
+		assert!(html.contains("class=\"listingblock\""));
+		assert!(html.contains("language-rust"));
+		assert!(html.contains("admonitionblock note"));
+
+		let missing = to_html(&body, Some(Path::new("/nonexistent/asciidoctor")));
+		assert!(missing.unwrap_err().to_string().contains("not found"));
+	}
+}
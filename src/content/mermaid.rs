@@ -0,0 +1,155 @@
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::URL_SAFE, Engine};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::config::MermaidConfig;
+
+const MERMAID_INK: &str = "https://mermaid.ink/svg/";
+
+static DIAGRAM_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub fn render(code: &str, config: &MermaidConfig) -> Result<String> {
+	let svg = match config.renderer.as_str() {
+		"cli" => render_cli(code, config.cli_path.as_deref())?,
+		_ => render_api(code, MERMAID_INK)?,
+	};
+	// Inlined into the page, so the XML prolog has to go
+	let svg = svg.trim();
+	let svg = match svg.strip_prefix("<?xml") {
+		Some(rest) => rest.split_once("?>").map_or(rest, |(_, svg)| svg).trim(),
+		None => svg,
+	};
+	if !svg.starts_with("<svg") {
+		bail!("Renderer returned something other than an SVG");
+	}
+	Ok(svg.to_string())
+}
+
+fn render_api(code: &str, endpoint: &str) -> Result<String> {
+	let url = format!("{}{}", endpoint, URL_SAFE.encode(code));
+	// The blocking client cannot run on a runtime thread, and pages may be parsed on one
+	std::thread::scope(|scope| {
+		scope
+			.spawn(|| {
+				let response = reqwest::blocking::Client::new()
+					.get(&url)
+					.timeout(Duration::from_secs(30))
+					.send()
+					.and_then(|response| response.error_for_status())
+					.with_context(|| format!("Request to {} failed", endpoint))?;
+				Ok(response.text()?)
+			})
+			.join()
+			.unwrap_or_else(|_| bail!("Request to {} panicked", endpoint))
+	})
+}
+
+fn render_cli(code: &str, mmdc: Option<&Path>) -> Result<String> {
+	let program = mmdc.unwrap_or(Path::new("mmdc"));
+
+	// Pages render in parallel, so every diagram gets its own scratch files
+	let id = format!(
+		"rum-mermaid-{}-{}",
+		std::process::id(),
+		DIAGRAM_COUNTER.fetch_add(1, Ordering::Relaxed)
+	);
+	let input = std::env::temp_dir().join(format!("{}.mmd", id));
+	let output = std::env::temp_dir().join(format!("{}.svg", id));
+	fs::write(&input, code)?;
+
+	let result = Command::new(program)
+		.arg("--input")
+		.arg(&input)
+		.arg("--output")
+		.arg(&output)
+		.arg("--quiet")
+		.output();
+	let svg = match result {
+		Ok(result) if result.status.success() => fs::read_to_string(&output)
+			.with_context(|| format!("{} wrote no SVG", program.display())),
+		Ok(result) => Err(anyhow::anyhow!(
+			"{} exited with {}: {}",
+			program.display(),
+			result.status,
+			String::from_utf8_lossy(&result.stderr).trim()
+		)),
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Err(anyhow::anyhow!(
+			"{} not found, install @mermaid-js/mermaid-cli or set mermaid.cli_path in rum.toml",
+			program.display()
+		)),
+		Err(e) => Err(e).with_context(|| format!("Failed to run {}", program.display())),
+	};
+
+	fs::remove_file(&input).ok();
+	fs::remove_file(&output).ok();
+	svg
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::{BufRead, BufReader, Write};
+	use std::net::TcpListener;
+	use std::thread;
+
+	// Answers a single request with the given status and body, handing back the request line
+	fn mock_server(
+		status: &'static str,
+		body: &'static str,
+	) -> (String, thread::JoinHandle<String>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let endpoint = format!("http://{}/svg/", listener.local_addr().unwrap());
+		let handle = thread::spawn(move || {
+			let (mut stream, _) = listener.accept().unwrap();
+			let mut request_line = String::new();
+			let mut reader = BufReader::new(stream.try_clone().unwrap());
+			reader.read_line(&mut request_line).unwrap();
+			loop {
+				let mut header = String::new();
+				reader.read_line(&mut header).unwrap();
+				if header.trim().is_empty() {
+					break;
+				}
+			}
+			write!(
+				stream,
+				"HTTP/1.1 {}\r\nContent-Type: image/svg+xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+				status,
+				body.len(),
+				body
+			)
+			.unwrap();
+			request_line
+		});
+		(endpoint, handle)
+	}
+
+	#[test]
+	fn test_render_api() {
+		let code = "graph TD\n  A --> B";
+		let (endpoint, server) = mock_server("200 OK", "<svg id=\"m\"><g/></svg>");
+		let svg = render_api(code, &endpoint).unwrap();
+		assert_eq!(svg, "<svg id=\"m\"><g/></svg>");
+		let request = server.join().unwrap();
+		assert!(request.starts_with(&format!("GET /svg/{} ", URL_SAFE.encode(code))));
+
+		let (endpoint, server) = mock_server("400 Bad Request", "invalid encoded code");
+		assert!(render_api("graph ???", &endpoint).is_err());
+		server.join().unwrap();
+	}
+
+	#[test]
+	fn test_render_cli_missing() {
+		let config = MermaidConfig {
+			renderer: "cli".to_string(),
+			cli_path: Some("/nonexistent/mmdc".into()),
+		};
+		let error = render("graph TD\n  A --> B", &config).unwrap_err();
+		assert!(error.to_string().contains("not found"));
+	}
+}
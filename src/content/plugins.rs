@@ -0,0 +1,249 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+#[cfg(not(feature = "wasm-plugins"))]
+use anyhow::bail;
+
+#[cfg(feature = "wasm-plugins")]
+use wasm::WasmPlugin;
+
+// Shortcode modules from `[plugins] wasm`, see PLUGIN_ABI.md for the guest side
+#[derive(Debug, Clone, Default)]
+pub struct Plugins {
+	#[cfg(feature = "wasm-plugins")]
+	modules: Vec<WasmPlugin>,
+}
+
+impl Plugins {
+	#[cfg(feature = "wasm-plugins")]
+	pub fn load(paths: &[PathBuf]) -> Result<Self> {
+		let engine = wasm::engine()?;
+		let modules = paths
+			.iter()
+			.map(|path| WasmPlugin::load(&engine, path))
+			.collect::<Result<_>>()?;
+		Ok(Self { modules })
+	}
+
+	#[cfg(not(feature = "wasm-plugins"))]
+	pub fn load(paths: &[PathBuf]) -> Result<Self> {
+		if !paths.is_empty() {
+			bail!("plugins.wasm is set but rum was built without the wasm-plugins feature");
+		}
+		Ok(Self::default())
+	}
+
+	#[cfg(feature = "wasm-plugins")]
+	pub fn is_empty(&self) -> bool {
+		self.modules.is_empty()
+	}
+
+	#[cfg(not(feature = "wasm-plugins"))]
+	pub fn is_empty(&self) -> bool {
+		true
+	}
+
+	// The first module that handles the name wins, None when none of them does
+	#[cfg_attr(not(feature = "wasm-plugins"), allow(unused_variables))]
+	pub fn process_shortcode(&self, name: &str, args: &str, body: &str) -> Result<Option<String>> {
+		#[cfg(feature = "wasm-plugins")]
+		for module in &self.modules {
+			if let Some(html) = module.process_shortcode(name, args, body)? {
+				return Ok(Some(html));
+			}
+		}
+		Ok(None)
+	}
+}
+
+#[cfg(feature = "wasm-plugins")]
+mod wasm {
+	use anyhow::{bail, Context, Result};
+	use std::fmt;
+	use std::path::{Path, PathBuf};
+	use wasmtime::{Config, Engine, Instance, InstancePre, Linker, Module, Store};
+
+	// A guest stuck in a loop runs out of fuel and fails the shortcode instead of hanging the build
+	const FUEL: u64 = 100_000_000;
+
+	pub fn engine() -> Result<Engine> {
+		let mut config = Config::new();
+		config.consume_fuel(true);
+		Engine::new(&config)
+	}
+
+	#[derive(Clone)]
+	pub struct WasmPlugin {
+		path: PathBuf,
+		engine: Engine,
+		instance: InstancePre<()>,
+	}
+
+	impl fmt::Debug for WasmPlugin {
+		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+			f.debug_struct("WasmPlugin")
+				.field("path", &self.path)
+				.finish_non_exhaustive()
+		}
+	}
+
+	impl WasmPlugin {
+		// Modules import nothing, so a module missing an export fails here rather than mid-build
+		pub fn load(engine: &Engine, path: &Path) -> Result<Self> {
+			let module = Module::from_file(engine, path)
+				.with_context(|| format!("Failed to load plugin {}", path.display()))?;
+			let instance = Linker::new(engine)
+				.instantiate_pre(&module)
+				.with_context(|| format!("Plugin {} cannot be instantiated", path.display()))?;
+			let plugin = Self {
+				path: path.to_path_buf(),
+				engine: engine.clone(),
+				instance,
+			};
+			let (mut store, instance) = plugin.instantiate()?;
+			Guest::new(&mut store, &instance)
+				.with_context(|| format!("Plugin {} does not follow the ABI", path.display()))?;
+			Ok(plugin)
+		}
+
+		// Every call gets a fresh instance, so nothing one page does can leak into the next
+		pub fn process_shortcode(
+			&self,
+			name: &str,
+			args: &str,
+			body: &str,
+		) -> Result<Option<String>> {
+			let (mut store, instance) = self.instantiate()?;
+			Guest::new(&mut store, &instance)?
+				.process_shortcode(&mut store, name, args, body)
+				.with_context(|| format!("Plugin {} failed", self.path.display()))
+		}
+
+		pub(super) fn instantiate(&self) -> Result<(Store<()>, Instance)> {
+			let mut store = Store::new(&self.engine, ());
+			store.set_fuel(FUEL)?;
+			let instance = self.instance.instantiate(&mut store)?;
+			Ok((store, instance))
+		}
+	}
+
+	// The exports PLUGIN_ABI.md asks for
+	pub(super) struct Guest {
+		memory: wasmtime::Memory,
+		alloc: wasmtime::TypedFunc<i32, i32>,
+		dealloc: wasmtime::TypedFunc<(i32, i32), ()>,
+		process: wasmtime::TypedFunc<(i32, i32, i32, i32, i32, i32), i64>,
+	}
+
+	impl Guest {
+		pub(super) fn new(store: &mut Store<()>, instance: &Instance) -> Result<Self> {
+			Ok(Self {
+				memory: instance
+					.get_memory(&mut *store, "memory")
+					.context("Missing exported memory")?,
+				alloc: instance.get_typed_func(&mut *store, "alloc")?,
+				dealloc: instance.get_typed_func(&mut *store, "dealloc")?,
+				process: instance.get_typed_func(&mut *store, "process_shortcode")?,
+			})
+		}
+
+		pub(super) fn process_shortcode(
+			&self,
+			store: &mut Store<()>,
+			name: &str,
+			args: &str,
+			body: &str,
+		) -> Result<Option<String>> {
+			let name = self.write(store, name.as_bytes())?;
+			let args = self.write(store, args.as_bytes())?;
+			let body = self.write(store, body.as_bytes())?;
+			let result = self.process.call(
+				&mut *store,
+				(name.0, name.1, args.0, args.1, body.0, body.1),
+			)?;
+			for (ptr, len) in [name, args, body] {
+				self.dealloc.call(&mut *store, (ptr, len))?;
+			}
+			if result == 0 {
+				return Ok(None);
+			}
+
+			// Pointer in the high half, length in the low half
+			let (ptr, len) = ((result >> 32) as u32, result as u32);
+			let mut html = vec![0; len as usize];
+			self.memory
+				.read(&*store, ptr as usize, &mut html)
+				.context("Returned output lies outside guest memory")?;
+			self.dealloc.call(&mut *store, (ptr as i32, len as i32))?;
+			match String::from_utf8(html) {
+				Ok(html) => Ok(Some(html)),
+				Err(_) => bail!("Returned output is not UTF-8"),
+			}
+		}
+
+		fn write(&self, store: &mut Store<()>, bytes: &[u8]) -> Result<(i32, i32)> {
+			let len = i32::try_from(bytes.len()).context("Shortcode too large for the guest")?;
+			let ptr = self.alloc.call(&mut *store, len)?;
+			self.memory
+				.write(&mut *store, ptr as u32 as usize, bytes)
+				.context("alloc returned memory outside the guest's")?;
+			Ok((ptr, len))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[cfg(feature = "wasm-plugins")]
+	fn badge() -> PathBuf {
+		PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/plugins/badge.wasm")
+	}
+
+	#[cfg(feature = "wasm-plugins")]
+	#[test]
+	fn test_badge_plugin() {
+		let plugins = Plugins::load(&[badge()]).unwrap();
+		assert_eq!(
+			plugins
+				.process_shortcode("badge", "color=\"red\" label=\"foo\"", "")
+				.unwrap()
+				.as_deref(),
+			Some("<span class=\"badge badge-red\">foo</span>")
+		);
+		assert_eq!(
+			plugins
+				.process_shortcode("badge", "", "<b>beta</b>")
+				.unwrap()
+				.as_deref(),
+			Some("<span class=\"badge badge-gray\">&lt;b&gt;beta&lt;/b&gt;</span>")
+		);
+		assert_eq!(plugins.process_shortcode("other", "", "").unwrap(), None);
+
+		// Inputs and the returned string are all handed back to the guest
+		let plugin = WasmPlugin::load(&wasm::engine().unwrap(), &badge()).unwrap();
+		let (mut store, instance) = plugin.instantiate().unwrap();
+		let guest = wasm::Guest::new(&mut store, &instance).unwrap();
+		for _ in 0..3 {
+			guest
+				.process_shortcode(&mut store, "badge", "label=\"x\"", "")
+				.unwrap();
+		}
+		let live = instance
+			.get_typed_func::<(), i32>(&mut store, "live_allocations")
+			.unwrap();
+		assert_eq!(live.call(&mut store, ()).unwrap(), 0);
+
+		let missing = Plugins::load(&[PathBuf::from("/nonexistent/plugin.wasm")]);
+		assert!(missing.is_err());
+	}
+
+	#[cfg(not(feature = "wasm-plugins"))]
+	#[test]
+	fn test_plugins_need_feature() {
+		assert!(Plugins::load(&[]).unwrap().is_empty());
+		let error = Plugins::load(&[PathBuf::from("badge.wasm")]).unwrap_err();
+		assert!(error.to_string().contains("wasm-plugins feature"));
+	}
+}
@@ -0,0 +1,203 @@
+use ammonia::Builder;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::LazyLock;
+
+use crate::config::SecurityConfig;
+
+static TAG_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r"<([a-zA-Z][a-zA-Z0-9-]*)([^>]*)>").unwrap());
+static ATTRIBUTE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+	Regex::new(r#"([^\s"'=/>]+)(?:\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+))?"#).unwrap()
+});
+
+// Random per process so a page cannot spell out a placeholder and get a fragment pasted elsewhere
+static PLACEHOLDER_PREFIX: LazyLock<String> = LazyLock::new(|| {
+	format!(
+		"rum-trusted-{:016x}",
+		RandomState::new().build_hasher().finish()
+	)
+});
+
+// Only schemes in these attributes are checked when letting data: URLs through for images
+const URL_ATTRIBUTES: [&str; 8] = [
+	"href",
+	"src",
+	"cite",
+	"action",
+	"formaction",
+	"poster",
+	"longdesc",
+	"xlink:href",
+];
+
+// HTML rum generates itself, such as inline mermaid SVGs with their <style>, which the
+// sanitizer would otherwise mangle. Each fragment is swapped for a placeholder until restore.
+#[derive(Default)]
+pub struct Trusted {
+	fragments: Vec<String>,
+}
+
+impl Trusted {
+	pub fn set_aside(&mut self, html: String) -> String {
+		self.fragments.push(html);
+		format!("{}-{}-", *PLACEHOLDER_PREFIX, self.fragments.len() - 1)
+	}
+
+	pub fn restore(&self, html: &str) -> String {
+		let mut html = html.to_string();
+		for (idx, fragment) in self.fragments.iter().enumerate() {
+			html = html.replacen(&format!("{}-{}-", *PLACEHOLDER_PREFIX, idx), fragment, 1);
+		}
+		html
+	}
+}
+
+// Returns the cleaned HTML and what was taken out, e.g. "<script>" or "onclick on <button>"
+pub fn sanitize(html: &str, config: &SecurityConfig) -> (String, Vec<String>) {
+	let mut builder = Builder::default();
+	builder
+		// Markdown task lists and table alignment, plus the ids and classes rum adds itself
+		.add_tags(["input"])
+		.add_tag_attributes("input", ["type", "checked", "disabled"])
+		.add_tag_attributes("th", ["style"])
+		.add_tag_attributes("td", ["style"])
+		.add_generic_attributes(["id", "class", "aria-label"])
+		// Notebook outputs embed their plots as data: images, nothing else may use data:
+		.add_url_schemes(["data"])
+		.attribute_filter(|element, attribute, value| {
+			let is_data = value
+				.trim_start()
+				.get(..5)
+				.is_some_and(|scheme| scheme.eq_ignore_ascii_case("data:"));
+			let is_image = element == "img"
+				&& attribute == "src"
+				&& value.trim_start().starts_with("data:image/");
+			if is_data && URL_ATTRIBUTES.contains(&attribute) && !is_image {
+				None
+			} else {
+				Some(Cow::Borrowed(value))
+			}
+		})
+		.link_rel(None);
+	builder.add_tags(config.allowed_tags.iter().map(String::as_str));
+	for (tag, attributes) in &config.allowed_attributes {
+		let attributes = attributes.iter().map(String::as_str);
+		if tag == "*" {
+			builder.add_generic_attributes(attributes);
+		} else {
+			builder.add_tag_attributes(tag.as_str(), attributes);
+		}
+	}
+
+	let cleaned = builder.clean(html).to_string();
+	let before = elements(html);
+	let after = elements(&cleaned);
+	let removed = before
+		.into_iter()
+		.filter(|(name, count)| after.get(name).copied().unwrap_or(0) < *count)
+		.map(|(name, _)| name)
+		.collect();
+	(cleaned, removed)
+}
+
+// Counts of each tag and each attribute on a tag, compared before and after cleaning
+fn elements(html: &str) -> BTreeMap<String, usize> {
+	let mut counts = BTreeMap::new();
+	for tag in TAG_REGEX.captures_iter(html) {
+		let name = tag[1].to_ascii_lowercase();
+		for attribute in ATTRIBUTE_REGEX.captures_iter(&tag[2]) {
+			let key = format!("{} on <{}>", attribute[1].to_ascii_lowercase(), name);
+			*counts.entry(key).or_default() += 1;
+		}
+		*counts.entry(format!("<{}>", name)).or_default() += 1;
+	}
+	counts
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::Config;
+	use crate::content::ContentProcessor;
+
+	fn render(config: &Config) -> (String, Vec<String>) {
+		let processed =
+			ContentProcessor::process_content(include_str!("../../tests/fixtures/unsafe.md"), None);
+		let (html, _, removed) =
+			ContentProcessor::new().markdown_to_html(&processed, config, 0, false);
+		(html, removed)
+	}
+
+	#[test]
+	fn test_sanitize_fixture() {
+		let (html, removed) = render(&Config::default());
+		assert!(!html.contains("<script"));
+		assert!(!html.contains("stolen"));
+		assert!(!html.contains("onclick"));
+		assert!(html.contains("<div>Click me</div>"));
+		assert!(!html.contains("evil.example.com"));
+		assert!(!html.contains("javascript:"));
+		assert!(!html.contains("data:text/html"));
+		assert_eq!(
+			removed,
+			[
+				"<iframe>",
+				"<script>",
+				"href on <a>",
+				"onclick on <div>",
+				"src on <iframe>",
+				"src on <img>"
+			]
+		);
+
+		// Markdown's own output and rum's generated HTML come through untouched
+		assert!(html.contains("<h1 id=\"community-page\">"));
+		assert!(html.contains("<kbd>Ctrl</kbd>+<kbd>C</kbd>"));
+		assert!(html.contains("<input disabled=\"\" type=\"checkbox\" checked=\"\""));
+		assert!(html.contains("<th style=\"text-align: left\">"));
+		assert!(html.contains("src=\"https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ\""));
+		assert!(html.contains("<pre class=\"code\"><code>"));
+		assert!(html.contains("<span class=\"source "));
+		assert!(!html.contains(PLACEHOLDER_PREFIX.as_str()));
+
+		let mut config = Config::default();
+		config.security.sanitize_html = false;
+		let (html, removed) = render(&config);
+		assert!(html.contains("<script>alert(\"stolen\")</script>"));
+		assert!(html.contains("onclick=\"steal()\""));
+		assert!(removed.is_empty());
+	}
+
+	#[test]
+	fn test_allowed_tags_and_attributes() {
+		let mut config = Config::default();
+		config.security.allowed_tags = vec!["iframe".to_string()];
+		config
+			.security
+			.allowed_attributes
+			.insert("iframe".to_string(), vec!["src".to_string()]);
+		config
+			.security
+			.allowed_attributes
+			.insert("*".to_string(), vec!["data-note".to_string()]);
+		let (html, _) = sanitize(
+			"<iframe src=\"https://example.com/\"></iframe><p data-note=\"x\" onclick=\"y()\">Hi</p>",
+			&config.security,
+		);
+		assert_eq!(
+			html,
+			"<iframe src=\"https://example.com/\"></iframe><p data-note=\"x\">Hi</p>"
+		);
+
+		let (html, removed) = sanitize(
+			"<img src=\"data:image/png;base64,AAAA\" alt=\"\">",
+			&config.security,
+		);
+		assert_eq!(html, "<img src=\"data:image/png;base64,AAAA\" alt=\"\">");
+		assert!(removed.is_empty());
+	}
+}
@@ -0,0 +1,161 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticLevel {
+	Error,
+	Warning,
+	Info,
+}
+
+impl DiagnosticLevel {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			DiagnosticLevel::Error => "error",
+			DiagnosticLevel::Warning => "warning",
+			DiagnosticLevel::Info => "info",
+		}
+	}
+
+	fn color(self) -> Color {
+		match self {
+			DiagnosticLevel::Error => Color::Red,
+			DiagnosticLevel::Warning => Color::Yellow,
+			DiagnosticLevel::Info => Color::Cyan,
+		}
+	}
+}
+
+// Codes stay the same between releases, so CI can match on them instead of the message
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+	pub level: DiagnosticLevel,
+	pub path: Option<PathBuf>,
+	pub code: Option<String>,
+	pub message: String,
+}
+
+// Shared by the rayon workers of a parallel build, so reports can arrive from any thread
+pub trait DiagnosticSink: Send + Sync {
+	fn report(&self, diag: Diagnostic);
+}
+
+// `--output-format`, "text" for people and "json" for tooling
+pub fn diagnostic_sink(format: &str) -> Result<Box<dyn DiagnosticSink>> {
+	match format {
+		"text" => Ok(Box::new(PrettyDiagnosticSink::new())),
+		"json" => Ok(Box::new(JsonDiagnosticSink)),
+		other => bail!(
+			"Unknown output format '{}', expected \"text\" or \"json\"",
+			other
+		),
+	}
+}
+
+// `warning[W002]: guide.md: message` on stderr, coloured when it is a terminal
+pub struct PrettyDiagnosticSink {
+	color: ColorChoice,
+}
+
+impl PrettyDiagnosticSink {
+	pub fn new() -> Self {
+		let color = if io::stderr().is_terminal() {
+			ColorChoice::Auto
+		} else {
+			ColorChoice::Never
+		};
+		Self { color }
+	}
+}
+
+impl Default for PrettyDiagnosticSink {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl DiagnosticSink for PrettyDiagnosticSink {
+	fn report(&self, diag: Diagnostic) {
+		let stream = StandardStream::stderr(self.color);
+		let mut stream = stream.lock();
+		// Nowhere left to report a failing stderr
+		let _ = write_pretty(&mut stream, &diag);
+	}
+}
+
+fn write_pretty(out: &mut impl WriteColor, diag: &Diagnostic) -> io::Result<()> {
+	out.set_color(
+		ColorSpec::new()
+			.set_fg(Some(diag.level.color()))
+			.set_bold(true),
+	)?;
+	write!(out, "{}", diag.level.as_str())?;
+	if let Some(code) = &diag.code {
+		write!(out, "[{}]", code)?;
+	}
+	out.reset()?;
+	write!(out, ": ")?;
+	if let Some(path) = &diag.path {
+		out.set_color(ColorSpec::new().set_bold(true))?;
+		write!(out, "{}", path.display())?;
+		out.reset()?;
+		write!(out, ": ")?;
+	}
+	writeln!(out, "{}", diag.message)
+}
+
+// One object per line on stdout, e.g. {"level":"warning","path":"guide.md","code":"W002",...}
+pub struct JsonDiagnosticSink;
+
+impl DiagnosticSink for JsonDiagnosticSink {
+	fn report(&self, diag: Diagnostic) {
+		let Ok(line) = serde_json::to_string(&diag) else {
+			return;
+		};
+		let _ = writeln!(io::stdout().lock(), "{}", line);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use termcolor::NoColor;
+
+	#[test]
+	fn test_diagnostic_formats() {
+		let diag = Diagnostic {
+			level: DiagnosticLevel::Warning,
+			path: Some(PathBuf::from("guide/setup.md")),
+			code: Some("W002".to_string()),
+			message: "Broken link to missing.html".to_string(),
+		};
+		let mut out = NoColor::new(Vec::new());
+		write_pretty(&mut out, &diag).unwrap();
+		assert_eq!(
+			String::from_utf8(out.into_inner()).unwrap(),
+			"warning[W002]: guide/setup.md: Broken link to missing.html\n"
+		);
+		assert_eq!(
+			serde_json::to_string(&diag).unwrap(),
+			r#"{"level":"warning","path":"guide/setup.md","code":"W002","message":"Broken link to missing.html"}"#
+		);
+
+		let bare = Diagnostic {
+			level: DiagnosticLevel::Error,
+			path: None,
+			code: None,
+			message: "Build failed".to_string(),
+		};
+		let mut out = NoColor::new(Vec::new());
+		write_pretty(&mut out, &bare).unwrap();
+		assert_eq!(
+			String::from_utf8(out.into_inner()).unwrap(),
+			"error: Build failed\n"
+		);
+		assert!(diagnostic_sink("xml").is_err());
+	}
+}
@@ -0,0 +1,202 @@
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+#[derive(Debug, Default)]
+pub struct BuildDiff {
+	pub added: Vec<PathBuf>,
+	pub removed: Vec<PathBuf>,
+	pub changed: Vec<(PathBuf, String, String)>,
+}
+
+// Hashes the written HTML rather than the sources, a page also changes with its sidebar,
+// backlinks and neighbours, and generated pages have no source at all
+pub fn diff_builds(old: &Path, new: &Path) -> Result<BuildDiff> {
+	let old_pages = page_hashes(old)?;
+	let new_pages = page_hashes(new)?;
+
+	let mut diff = BuildDiff::default();
+	for (path, old_hash) in &old_pages {
+		match new_pages.get(path) {
+			None => diff.removed.push(path.clone()),
+			Some(new_hash) if new_hash != old_hash => {
+				diff.changed
+					.push((path.clone(), old_hash.clone(), new_hash.clone()))
+			}
+			Some(_) => {}
+		}
+	}
+	for path in new_pages.keys() {
+		if !old_pages.contains_key(path) {
+			diff.added.push(path.clone());
+		}
+	}
+
+	Ok(diff)
+}
+
+pub fn print_summary(diff: &BuildDiff) {
+	for path in &diff.added {
+		println!("+ {}", path.display());
+	}
+	for path in &diff.removed {
+		println!("- {}", path.display());
+	}
+	for (path, old_hash, new_hash) in &diff.changed {
+		println!(
+			"~ {} ({} -> {})",
+			path.display(),
+			&old_hash[..12],
+			&new_hash[..12]
+		);
+	}
+	println!(
+		"{} added, {} removed, {} changed",
+		diff.added.len(),
+		diff.removed.len(),
+		diff.changed.len()
+	);
+}
+
+pub fn print_unified(diff: &BuildDiff, old: &Path, new: &Path) -> Result<()> {
+	for (path, _, _) in &diff.changed {
+		let output = Command::new("diff")
+			.arg("-u")
+			.arg(old.join(path))
+			.arg(new.join(path))
+			.output();
+
+		let output = match output {
+			Ok(output) => output,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {
+				bail!("The diff utility is required for --format unified-diff")
+			}
+			Err(e) => return Err(e).context("Failed to run diff"),
+		};
+		// diff exits with 1 when the files differ, only 2 means trouble
+		if output.status.code() == Some(2) {
+			bail!(
+				"diff failed for {}: {}",
+				path.display(),
+				String::from_utf8_lossy(&output.stderr).trim()
+			);
+		}
+		print!("{}", String::from_utf8_lossy(&output.stdout));
+	}
+	Ok(())
+}
+
+fn page_hashes(output_dir: &Path) -> Result<BTreeMap<PathBuf, String>> {
+	if !output_dir.is_dir() {
+		bail!("{} is not a build output directory", output_dir.display());
+	}
+
+	let mut pages = BTreeMap::new();
+	for entry in WalkDir::new(output_dir)
+		.into_iter()
+		.filter_map(|e| e.ok())
+		.filter(|e| e.file_type().is_file())
+	{
+		let path = entry.path();
+		if path.extension().and_then(|e| e.to_str()) != Some("html") {
+			continue;
+		}
+
+		let content =
+			fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+		let relative = path.strip_prefix(output_dir).unwrap_or(path).to_path_buf();
+		pages.insert(relative, format!("{:x}", Sha256::digest(&content)));
+	}
+
+	Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::Config;
+	use crate::generator::Generator;
+
+	#[test]
+	fn test_diff_output_directories() {
+		let root = std::env::temp_dir().join(format!("rum-diff-html-{}", std::process::id()));
+		let (old, new) = (root.join("old"), root.join("new"));
+		for (dir, pages) in [
+			(&old, [("index.html", "home"), ("gone.html", "gone")]),
+			(
+				&new,
+				[("index.html", "home, edited"), ("fresh.html", "fresh")],
+			),
+		] {
+			fs::create_dir_all(dir.join("assets")).unwrap();
+			fs::write(
+				dir.join("assets/style.css"),
+				dir.to_string_lossy().as_bytes(),
+			)
+			.unwrap();
+			for (page, content) in pages {
+				fs::write(dir.join(page), content).unwrap();
+			}
+		}
+
+		let diff = diff_builds(&old, &new).unwrap();
+		assert_eq!(diff.added, [PathBuf::from("fresh.html")]);
+		assert_eq!(diff.removed, [PathBuf::from("gone.html")]);
+		assert_eq!(diff.changed.len(), 1);
+		let (path, old_hash, new_hash) = &diff.changed[0];
+		assert_eq!(path, Path::new("index.html"));
+		assert_eq!(*old_hash, format!("{:x}", Sha256::digest("home")));
+		assert_eq!(*new_hash, format!("{:x}", Sha256::digest("home, edited")));
+
+		assert!(diff_builds(&old, &root.join("missing")).is_err());
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_diff_builds() {
+		let root = std::env::temp_dir().join(format!("rum-diff-manifest-{}", std::process::id()));
+		let source = root.join("docs");
+		fs::create_dir_all(source.join("guide")).unwrap();
+		fs::write(source.join("index.md"), "---\ntitle: Home\n---\nHome\n").unwrap();
+		fs::write(
+			source.join("guide/setup.md"),
+			"---\ntitle: Setup\n---\nSetup\n",
+		)
+		.unwrap();
+		fs::write(source.join("guide/old.md"), "---\ntitle: Old\n---\nOld\n").unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let build = |output: PathBuf| {
+			let generator = Generator::new(source.clone(), output, Some(config.clone()))
+				.unwrap()
+				.with_incremental(true);
+			async move { generator.build("html").await.unwrap() }
+		};
+		let (old, new) = (root.join("old"), root.join("new"));
+		build(old.clone()).await;
+		fs::write(
+			source.join("guide/setup.md"),
+			"---\ntitle: Setup\n---\nSet up\n",
+		)
+		.unwrap();
+		fs::remove_file(source.join("guide/old.md")).unwrap();
+		fs::write(source.join("guide/new.md"), "---\ntitle: New\n---\nNew\n").unwrap();
+		build(new.clone()).await;
+
+		// The sidebar changed on every page, so the untouched home page counts as changed too
+		let diff = diff_builds(&old, &new).unwrap();
+		assert_eq!(diff.added, [PathBuf::from("guide/new.html")]);
+		assert_eq!(diff.removed, [PathBuf::from("guide/old.html")]);
+		let changed: Vec<_> = diff.changed.iter().map(|(path, _, _)| path).collect();
+		assert!(changed.contains(&&PathBuf::from("guide/setup.html")));
+		assert!(changed.contains(&&PathBuf::from("index.html")));
+
+		fs::remove_dir_all(&root).ok();
+	}
+}
@@ -1,29 +1,1244 @@
-use anyhow::Result;
-use std::path::Path;
+use anyhow::{anyhow, bail, Context, Result};
+use axum::Router;
+use epub_builder::{EpubBuilder, EpubContent, EpubVersion, ReferenceType, ZipLibrary};
+use headless_chrome::browser::default_executable;
+use headless_chrome::types::PrintToPdfOptions;
+use headless_chrome::{Browser, LaunchOptions};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tower_http::services::ServeDir;
+use tracing::{info, warn};
 
 use crate::config::Config;
-use crate::content::Document;
+use crate::content::{ContentProcessor, Document};
+use crate::generator::output_path;
+use crate::utils::escape_html;
 
+static IMG_SRC_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r#"<img[^>]*\ssrc="([^"]+)""#).unwrap());
+
+#[derive(Serialize)]
+struct ApiDocument<'a> {
+	title: String,
+	content: String,
+	html_content: &'a str,
+	tags: &'a [String],
+	date: Option<chrono::NaiveDate>,
+	version: Option<&'a str>,
+	locale: Option<&'a str>,
+	backlinks: &'a [String],
+	links: &'a [String],
+	relative_path: String,
+	word_count: usize,
+	reading_time_minutes: usize,
+}
+
+// Entries of api/index.json, everything needed to list pages without fetching each one
+#[derive(Serialize)]
+struct ApiSummary<'a> {
+	title: String,
+	tags: &'a [String],
+	date: Option<chrono::NaiveDate>,
+	version: Option<&'a str>,
+	url: String,
+}
+
+#[derive(Debug, Clone)]
 pub struct Exporter {
 	output_dir: std::path::PathBuf,
+	chromium_path: Option<PathBuf>,
 }
 
 impl Exporter {
 	pub fn new(output_dir: &Path) -> Self {
 		Self {
 			output_dir: output_dir.to_path_buf(),
+			chromium_path: None,
+		}
+	}
+
+	pub fn with_chromium_path(mut self, chromium_path: Option<PathBuf>) -> Self {
+		self.chromium_path = chromium_path;
+		self
+	}
+
+	// Prints the rendered HTML pages, so it has to run after the HTML build
+	pub async fn export_pdfs(&self, documents: &[Document], config: &Config) -> Result<()> {
+		let chromium = self.chromium()?;
+		let (paper_width, paper_height) = paper_size(&config.pdf.paper_size)?;
+		let margin = config.pdf.margin_mm / 25.4;
+		let print_background = config.pdf.print_background;
+		// PrintToPdfOptions is not Clone, every page gets its own
+		let options = move || PrintToPdfOptions {
+			print_background: Some(print_background),
+			paper_width: Some(paper_width),
+			paper_height: Some(paper_height),
+			margin_top: Some(margin),
+			margin_bottom: Some(margin),
+			margin_left: Some(margin),
+			margin_right: Some(margin),
+			..Default::default()
+		};
+
+		// Pages link their assets by absolute path, which only resolves when served over HTTP
+		let listener = TcpListener::bind("127.0.0.1:0").await?;
+		let base_url = format!("http://{}", listener.local_addr()?);
+		let app = Router::new().fallback_service(ServeDir::new(&self.output_dir));
+		let (shutdown, stopped) = oneshot::channel::<()>();
+		let server = tokio::spawn(async move {
+			axum::serve(listener, app)
+				.with_graceful_shutdown(async {
+					stopped.await.ok();
+				})
+				.await
+		});
+
+		let pdf_dir = self.output_dir.join("pdf");
+		let pages: Vec<(String, PathBuf)> = documents
+			.iter()
+			.map(|doc| {
+				let page = output_path(doc, config);
+				(
+					format!("{}/{}", base_url, page.to_string_lossy().replace('\\', "/")),
+					pdf_dir.join(doc.relative_path.with_extension("pdf")),
+				)
+			})
+			.collect();
+
+		let printed =
+			tokio::task::spawn_blocking(move || print_pdfs(&chromium, &pages, options)).await;
+		let _ = shutdown.send(());
+		let _ = server.await;
+		printed?
+	}
+
+	fn chromium(&self) -> Result<PathBuf> {
+		match &self.chromium_path {
+			Some(path) if path.is_file() => Ok(path.clone()),
+			Some(path) => bail!(
+				"Chromium not found at {} (set by pdf.chromium_path)",
+				path.display()
+			),
+			None => default_executable().map_err(|e| {
+				anyhow!(
+					"PDF export needs Chrome or Chromium, install one or set pdf.chromium_path in rum.toml ({})",
+					e
+				)
+			}),
+		}
+	}
+
+	pub fn export_man_pages(&self, documents: &[Document], config: &Config) -> Result<()> {
+		for doc in documents {
+			let section = doc.frontmatter.man_section.unwrap_or(config.man.section);
+
+			let section_dir = self.output_dir.join("man").join(format!("man{}", section));
+			fs::create_dir_all(&section_dir)?;
+			fs::write(
+				section_dir.join(format!("{}.{}", document_slug(doc), section)),
+				ManPageWriter::render(doc, section, &config.site.title),
+			)?;
+		}
+		Ok(())
+	}
+
+	pub fn export_epub(&self, documents: &[Document], config: &Config) -> Result<()> {
+		let mut builder = epub(EpubBuilder::new(epub(ZipLibrary::new())?))?;
+		builder.epub_version(EpubVersion::V30);
+		epub(builder.metadata("title", &config.site.title))?;
+		epub(builder.metadata("description", &config.site.description))?;
+		epub(builder.metadata("lang", &config.site.language))?;
+		if let Some(author) = &config.site.author {
+			epub(builder.metadata("author", author))?;
+		}
+		epub(builder.stylesheet(include_str!("../templates/assets/style.css").as_bytes()))?;
+
+		// Documents arrive sorted by order, the grouping keeps that order within each version
+		let mut versions: Vec<(Option<&str>, Vec<&Document>)> = Vec::new();
+		for doc in documents {
+			let version = doc.version.as_deref();
+			match versions.iter_mut().find(|(v, _)| *v == version) {
+				Some((_, docs)) => docs.push(doc),
+				None => versions.push((version, vec![doc])),
+			}
+		}
+		versions.sort_by(|a, b| a.0.cmp(&b.0));
+		let parts = versions.len() > 1;
+
+		let mut images = 0;
+		let mut chapters = 0;
+		for (part, (version, docs)) in versions.iter().enumerate() {
+			if parts {
+				let title = version.unwrap_or("General");
+				let page = epub_page(title, &format!("<h1>{}</h1>", escape_html(title)));
+				epub(
+					builder.add_content(
+						EpubContent::new(format!("part_{}.xhtml", part + 1), page.as_bytes())
+							.title(title)
+							.level(1),
+					),
+				)?;
+			}
+
+			for doc in docs {
+				let mut html = doc.html_content.clone();
+				for src in image_sources(&doc.html_content) {
+					let Some(source) = doc.path.parent().map(|dir| dir.join(&src)) else {
+						continue;
+					};
+					let Ok(file) = fs::File::open(&source) else {
+						warn!("Image {} not found for the EPUB", source.display());
+						continue;
+					};
+					images += 1;
+					let name = format!(
+						"images/{}-{}",
+						images,
+						source.file_name().unwrap_or_default().to_string_lossy()
+					);
+					epub(builder.add_resource(&name, file, image_mime(&source)))?;
+					html = html.replace(&format!("src=\"{}\"", src), &format!("src=\"{}\"", name));
+				}
+
+				// Directories nest the same way they do in the sidebar
+				let depth =
+					doc.relative_path.components().count() - 1 - usize::from(doc.version.is_some());
+				let title = doc_title(doc);
+				chapters += 1;
+				let page = epub_page(
+					&title,
+					&format!("<h1>{}</h1>\n{}", escape_html(&title), html),
+				);
+				epub(
+					builder.add_content(
+						EpubContent::new(format!("chapter_{}.xhtml", chapters), page.as_bytes())
+							.title(title)
+							.reftype(ReferenceType::Text)
+							.level((depth + 1 + usize::from(parts)) as i32),
+					),
+				)?;
+			}
+		}
+
+		let name: String = config
+			.site
+			.title
+			.chars()
+			.map(|c| if "/\\:*?\"<>|".contains(c) { '-' } else { c })
+			.collect();
+		fs::create_dir_all(&self.output_dir)?;
+		let mut file = fs::File::create(self.output_dir.join(format!("{}.epub", name.trim())))?;
+		epub(builder.generate(&mut file))?;
+		Ok(())
+	}
+
+	pub fn export_json(&self, documents: &[Document], config: &Config) -> Result<()> {
+		let api_dir = self.output_dir.join("api");
+		fs::create_dir_all(&api_dir)?;
+
+		let mut index = Vec::new();
+		for doc in documents {
+			let api_doc = ApiDocument {
+				title: doc_title(doc),
+				content: markdown_to_text(&doc.content),
+				html_content: &doc.html_content,
+				tags: doc.frontmatter.tags.as_deref().unwrap_or_default(),
+				date: doc.frontmatter.date,
+				version: doc.version.as_deref(),
+				locale: doc.locale.as_deref(),
+				backlinks: &doc.backlinks,
+				links: &doc.links,
+				relative_path: doc.relative_path.to_string_lossy().replace('\\', "/"),
+				word_count: doc.word_count,
+				reading_time_minutes: doc.reading_time_minutes,
+			};
+			fs::write(
+				api_dir.join(format!("{}.json", document_slug(doc))),
+				serde_json::to_string_pretty(&api_doc)?,
+			)?;
+
+			index.push(ApiSummary {
+				title: api_doc.title,
+				tags: api_doc.tags,
+				date: api_doc.date,
+				version: api_doc.version,
+				url: format!(
+					"/{}",
+					output_path(doc, config)
+						.to_string_lossy()
+						.replace('\\', "/")
+				),
+			});
 		}
+
+		fs::write(
+			api_dir.join("index.json"),
+			serde_json::to_string_pretty(&index)?,
+		)?;
+		Ok(())
 	}
 
-	pub async fn export_pdfs(&self, _documents: &[Document], _config: &Config) -> Result<()> {
-		// PDF export placeholder
-		println!("PDF export not yet fully implemented");
+	pub fn export_latex(&self, documents: &[Document], config: &Config) -> Result<()> {
+		let latex_dir = self.output_dir.join("latex");
+		fs::create_dir_all(&latex_dir)?;
+
+		// One chapter file per document, pulled together by main.tex
+		let mut chapters = Vec::new();
+		for doc in documents {
+			let slug = document_slug(doc);
+			fs::write(
+				latex_dir.join(format!("{}.tex", slug)),
+				LatexWriter::render(doc),
+			)?;
+			chapters.push(slug);
+		}
+
+		let mut main = String::from("\\documentclass{report}\n");
+		main.push_str("\\usepackage[utf8]{inputenc}\n");
+		main.push_str("\\usepackage{graphicx}\n");
+		main.push_str("\\usepackage{alltt}\n");
+		main.push_str("\\usepackage[normalem]{ulem}\n");
+		main.push_str("\\usepackage{hyperref}\n\n");
+		main.push_str(&format!(
+			"\\title{{{}}}\n",
+			escape_latex(&config.site.title)
+		));
+		main.push_str(&format!(
+			"\\author{{{}}}\n",
+			escape_latex(config.site.author.as_deref().unwrap_or_default())
+		));
+		main.push_str("\\date{\\today}\n\n");
+		main.push_str("\\begin{document}\n\\maketitle\n\\tableofcontents\n\n");
+		for chapter in &chapters {
+			main.push_str(&format!("\\input{{{}}}\n", chapter));
+		}
+		main.push_str("\n\\end{document}\n");
+
+		fs::write(latex_dir.join("main.tex"), main)?;
 		Ok(())
 	}
 
-	pub async fn export_man_pages(&self, _documents: &[Document], _config: &Config) -> Result<()> {
-		// Man page(roff) export placeholder
-		println!("Man page export not yet fully implemented");
+	pub fn install_man_pages(&self, prefix: &Path, dry_run: bool) -> Result<()> {
+		let man_dir = self.output_dir.join("man");
+		if !man_dir.is_dir() {
+			bail!(
+				"No man pages found in {}, run `rum build --format man` first",
+				man_dir.display()
+			);
+		}
+
+		let install_dir = prefix.join("share").join("man");
+		let pages = Self::collect_man_pages(&man_dir, &install_dir)?;
+
+		if dry_run {
+			for (source, target) in &pages {
+				println!("{} -> {}", source.display(), target.display());
+			}
+			return Ok(());
+		}
+
+		Self::check_writable(&install_dir)?;
+
+		for (source, target) in &pages {
+			if let Some(parent) = target.parent() {
+				fs::create_dir_all(parent)?;
+			}
+			fs::copy(source, target)
+				.with_context(|| format!("Failed to install {}", target.display()))?;
+			info!("Installed {}", target.display());
+		}
+
+		// Refresh the man database if mandb is available
+		match Command::new("mandb").arg("--quiet").status() {
+			Ok(status) if !status.success() => warn!("mandb exited with {}", status),
+			Err(e) if e.kind() != io::ErrorKind::NotFound => {
+				warn!("Failed to run mandb: {}", e)
+			}
+			_ => {}
+		}
+
 		Ok(())
 	}
+
+	fn collect_man_pages(man_dir: &Path, install_dir: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+		let mut pages = Vec::new();
+
+		// Pages live in man{section}/ subdirectories
+		for entry in fs::read_dir(man_dir)? {
+			let section_dir = entry?.path();
+			let section = match section_dir.file_name().and_then(|s| s.to_str()) {
+				Some(name) if name.starts_with("man") && section_dir.is_dir() => name.to_string(),
+				_ => continue,
+			};
+
+			for page in fs::read_dir(&section_dir)? {
+				let page = page?.path();
+				if let (true, Some(name)) = (page.is_file(), page.file_name()) {
+					let target = install_dir.join(&section).join(name);
+					pages.push((page, target));
+				}
+			}
+		}
+
+		pages.sort();
+		Ok(pages)
+	}
+
+	fn check_writable(dir: &Path) -> Result<()> {
+		let probe = dir.join(".rum-install-probe");
+		let result = fs::create_dir_all(dir)
+			.and_then(|_| fs::write(&probe, b""))
+			.and_then(|_| fs::remove_file(&probe));
+
+		match result {
+			Ok(()) => Ok(()),
+			Err(e) if e.kind() == io::ErrorKind::PermissionDenied => bail!(
+				"Permission denied writing to {}, installing there requires root (try sudo)",
+				dir.display()
+			),
+			Err(e) => Err(e).with_context(|| format!("Cannot write to {}", dir.display())),
+		}
+	}
+}
+
+fn print_pdfs(
+	chromium: &Path,
+	pages: &[(String, PathBuf)],
+	options: impl Fn() -> PrintToPdfOptions,
+) -> Result<()> {
+	let launch = LaunchOptions::default_builder()
+		.path(Some(chromium.to_path_buf()))
+		.headless(true)
+		.build()
+		.map_err(|e| anyhow!("Invalid Chromium launch options: {}", e))?;
+	let browser =
+		Browser::new(launch).with_context(|| format!("Failed to launch {}", chromium.display()))?;
+	let tab = browser.new_tab()?;
+
+	for (url, target) in pages {
+		tab.navigate_to(url)?.wait_until_navigated()?;
+		let pdf = tab
+			.print_to_pdf(Some(options()))
+			.with_context(|| format!("Failed to print {}", url))?;
+		if let Some(parent) = target.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		fs::write(target, pdf)?;
+		info!("Wrote {}", target.display());
+	}
+	Ok(())
+}
+
+// Paper dimensions in inches, as Chromium expects them
+fn paper_size(name: &str) -> Result<(f64, f64)> {
+	Ok(match name.to_ascii_uppercase().as_str() {
+		"A3" => (11.69, 16.54),
+		"A4" => (8.27, 11.69),
+		"A5" => (5.83, 8.27),
+		"LETTER" => (8.5, 11.0),
+		"LEGAL" => (8.5, 14.0),
+		_ => bail!(
+			"Unknown paper size '{}', expected A3, A4, A5, Letter or Legal",
+			name
+		),
+	})
+}
+
+// epub-builder brings its own error type
+fn epub<T, E: std::fmt::Display>(result: std::result::Result<T, E>) -> Result<T> {
+	result.map_err(|e| anyhow!("EPUB export failed: {}", e))
+}
+
+fn epub_page(title: &str, body: &str) -> String {
+	format!(
+		"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n\
+		<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">\n\
+		<head>\n<title>{}</title>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"stylesheet.css\"/>\n</head>\n\
+		<body>\n{}\n</body>\n</html>\n",
+		escape_html(title),
+		body
+	)
+}
+
+// Local images only, remote ones stay as links
+fn image_sources(html: &str) -> Vec<String> {
+	let mut sources: Vec<String> = IMG_SRC_REGEX
+		.captures_iter(html)
+		.map(|caps| caps[1].to_string())
+		.filter(|src| !src.contains("://") && !src.starts_with("data:") && !src.starts_with('/'))
+		.collect();
+	sources.dedup();
+	sources
+}
+
+fn image_mime(path: &Path) -> &'static str {
+	match path
+		.extension()
+		.and_then(|e| e.to_str())
+		.map(|e| e.to_ascii_lowercase())
+		.as_deref()
+	{
+		Some("png") => "image/png",
+		Some("jpg" | "jpeg") => "image/jpeg",
+		Some("gif") => "image/gif",
+		Some("svg") => "image/svg+xml",
+		Some("webp") => "image/webp",
+		_ => "application/octet-stream",
+	}
+}
+
+fn doc_title(doc: &Document) -> String {
+	doc.frontmatter
+		.title
+		.clone()
+		.or_else(|| {
+			doc.relative_path
+				.file_stem()
+				.map(|s| s.to_string_lossy().to_string())
+		})
+		.unwrap_or_else(|| "Untitled".to_string())
+}
+
+// Text content only, block ends become line breaks
+fn markdown_to_text(markdown: &str) -> String {
+	let mut text = String::new();
+	for event in Parser::new(markdown) {
+		match event {
+			Event::Text(t) | Event::Code(t) => text.push_str(&t),
+			Event::SoftBreak | Event::HardBreak => text.push(' '),
+			Event::End(
+				TagEnd::Paragraph
+				| TagEnd::Heading(_)
+				| TagEnd::Item
+				| TagEnd::CodeBlock
+				| TagEnd::TableRow,
+			) if !text.ends_with('\n') => text.push('\n'),
+			_ => {}
+		}
+	}
+	text.trim_end().to_string()
+}
+
+// Path components joined with '-', e.g. guide/setup.md is guide-setup
+pub fn document_slug(doc: &Document) -> String {
+	doc.relative_path
+		.with_extension("")
+		.components()
+		.map(|c| c.as_os_str().to_string_lossy().to_string())
+		.collect::<Vec<_>>()
+		.join("-")
+}
+
+fn escape_latex(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for ch in text.chars() {
+		match ch {
+			'\\' => escaped.push_str("\\textbackslash{}"),
+			'~' => escaped.push_str("\\textasciitilde{}"),
+			'^' => escaped.push_str("\\textasciicircum{}"),
+			'&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+				escaped.push('\\');
+				escaped.push(ch);
+			}
+			_ => escaped.push(ch),
+		}
+	}
+	escaped
+}
+
+// hyperref reads the URL with _, &, ~ and $ made plain characters, but braces, backslashes and
+// spaces still need percent-encoding and % and # a backslash
+fn escape_latex_url(url: &str) -> String {
+	let mut escaped = String::with_capacity(url.len());
+	for ch in url.chars() {
+		match ch {
+			'%' | '#' => {
+				escaped.push('\\');
+				escaped.push(ch);
+			}
+			'\\' | '{' | '}' | ' ' | '^' => escaped.push_str(&format!("\\%{:02X}", ch as u32)),
+			_ => escaped.push(ch),
+		}
+	}
+	escaped
+}
+
+// Image paths name files, so %20 and friends are decoded first. \detokenize copes with
+// _ & $ ~ ^ and spaces, but not with a % # { or \ in the name, those images are left out.
+fn latex_graphics_path(url: &str) -> Option<String> {
+	let bytes = url.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+	while i < bytes.len() {
+		let hex = bytes
+			.get(i + 1..i + 3)
+			.and_then(|h| std::str::from_utf8(h).ok())
+			.and_then(|h| u8::from_str_radix(h, 16).ok());
+		match (bytes[i], hex) {
+			(b'%', Some(byte)) => {
+				decoded.push(byte);
+				i += 3;
+			}
+			(byte, _) => {
+				decoded.push(byte);
+				i += 1;
+			}
+		}
+	}
+	let path = String::from_utf8(decoded).ok()?;
+	if path.contains(['%', '#', '{', '}', '\\']) {
+		return None;
+	}
+	Some(format!("\\detokenize{{{}}}", path))
+}
+
+struct LatexWriter {
+	output: String,
+	lists: Vec<&'static str>,
+	code: Option<String>,
+	in_image: bool,
+	table_cell: usize,
+}
+
+impl LatexWriter {
+	fn render(doc: &Document) -> String {
+		let mut writer = Self {
+			output: String::new(),
+			lists: Vec::new(),
+			code: None,
+			in_image: false,
+			table_cell: 0,
+		};
+
+		let title = doc_title(doc);
+		writer
+			.output
+			.push_str(&format!("\\chapter{{{}}}\n", escape_latex(&title)));
+
+		// Author and date from the frontmatter form a byline under the chapter title
+		let authors = doc.frontmatter.authors.as_ref().map(|a| a.join(", "));
+		let date = doc.frontmatter.date.map(|d| d.to_string());
+		let byline: Vec<String> = [authors.as_deref(), date.as_deref()]
+			.into_iter()
+			.flatten()
+			.filter(|part| !part.is_empty())
+			.map(escape_latex)
+			.collect();
+		if !byline.is_empty() {
+			writer.output.push_str(&format!(
+				"\\begin{{flushright}}\\textit{{{}}}\\end{{flushright}}\n",
+				byline.join(" \\\\ ")
+			));
+		}
+
+		let parser = Parser::new_ext(&doc.content, ContentProcessor::markdown_options());
+		for event in parser {
+			writer.event(event);
+		}
+
+		writer.output
+	}
+
+	fn event(&mut self, event: Event) {
+		match event {
+			Event::Start(tag) => self.start(tag),
+			Event::End(tag) => self.end(tag),
+			Event::Text(text) => {
+				if let Some(code) = &mut self.code {
+					code.push_str(&text);
+				} else if !self.in_image {
+					self.output.push_str(&escape_latex(&text));
+				}
+			}
+			Event::Code(code) => {
+				self.output
+					.push_str(&format!("\\texttt{{{}}}", escape_latex(&code)));
+			}
+			Event::SoftBreak => self.output.push('\n'),
+			Event::HardBreak => self.output.push_str("\\\\\n"),
+			Event::Rule => self
+				.output
+				.push_str("\n\\noindent\\rule{\\linewidth}{0.4pt}\n"),
+			Event::TaskListMarker(checked) => {
+				self.output.push_str(if checked { "[x] " } else { "[ ] " })
+			}
+			_ => {}
+		}
+	}
+
+	fn start(&mut self, tag: Tag) {
+		match tag {
+			Tag::Heading { level, .. } => {
+				let command = match level as usize {
+					1 => "section",
+					2 => "subsection",
+					3 => "subsubsection",
+					_ => "paragraph",
+				};
+				self.output.push_str(&format!("\n\\{}{{", command));
+			}
+			Tag::Paragraph => self.output.push('\n'),
+			Tag::Emphasis => self.output.push_str("\\emph{"),
+			Tag::Strong => self.output.push_str("\\textbf{"),
+			Tag::Strikethrough => self.output.push_str("\\sout{"),
+			Tag::BlockQuote { .. } => self.output.push_str("\n\\begin{quote}\n"),
+			Tag::CodeBlock(_) => self.code = Some(String::new()),
+			Tag::List(start) => {
+				let env = if start.is_some() {
+					"enumerate"
+				} else {
+					"itemize"
+				};
+				self.lists.push(env);
+				self.output.push_str(&format!("\n\\begin{{{}}}\n", env));
+			}
+			Tag::Item => self.output.push_str("\\item "),
+			Tag::Link { dest_url, .. } => {
+				self.output
+					.push_str(&format!("\\href{{{}}}{{", escape_latex_url(&dest_url)));
+			}
+			Tag::Image { dest_url, .. } => {
+				self.in_image = true;
+				match latex_graphics_path(&dest_url) {
+					Some(path) => self
+						.output
+						.push_str(&format!("\\includegraphics[width=\\linewidth]{{{}}}", path)),
+					None => warn!("Leaving image {} out of the LaTeX export", dest_url),
+				}
+			}
+			Tag::Table(alignments) => {
+				let columns: String = alignments
+					.iter()
+					.map(|a| match a {
+						pulldown_cmark::Alignment::Center => 'c',
+						pulldown_cmark::Alignment::Right => 'r',
+						_ => 'l',
+					})
+					.collect();
+				self.output
+					.push_str(&format!("\n\\begin{{tabular}}{{{}}}\n\\hline\n", columns));
+			}
+			Tag::TableHead | Tag::TableRow => self.table_cell = 0,
+			Tag::TableCell => {
+				if self.table_cell > 0 {
+					self.output.push_str(" & ");
+				}
+				self.table_cell += 1;
+			}
+			_ => {}
+		}
+	}
+
+	fn end(&mut self, tag: TagEnd) {
+		match tag {
+			TagEnd::Heading(_) => self.output.push_str("}\n"),
+			TagEnd::Paragraph => self.output.push('\n'),
+			TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link => {
+				self.output.push('}')
+			}
+			TagEnd::BlockQuote { .. } => self.output.push_str("\\end{quote}\n"),
+			// verbatim ends at the first \end{verbatim}, code containing one goes through alltt
+			TagEnd::CodeBlock => match self.code.take() {
+				Some(code) if code.contains("\\end{verbatim}") => {
+					let code: String = code
+						.chars()
+						.map(|ch| match ch {
+							'\\' => "\\textbackslash{}".to_string(),
+							'{' | '}' => format!("\\{}", ch),
+							_ => ch.to_string(),
+						})
+						.collect();
+					self.output
+						.push_str(&format!("\n\\begin{{alltt}}\n{}\\end{{alltt}}\n", code));
+				}
+				code => self.output.push_str(&format!(
+					"\n\\begin{{verbatim}}\n{}\\end{{verbatim}}\n",
+					code.unwrap_or_default()
+				)),
+			},
+			TagEnd::List(_) => {
+				let env = self.lists.pop().unwrap_or("itemize");
+				self.output.push_str(&format!("\\end{{{}}}\n", env));
+			}
+			TagEnd::Item => self.output.push('\n'),
+			TagEnd::Image => self.in_image = false,
+			TagEnd::TableHead => self.output.push_str(" \\\\\n\\hline\n"),
+			TagEnd::TableRow => self.output.push_str(" \\\\\n"),
+			TagEnd::Table => self.output.push_str("\\hline\n\\end{tabular}\n"),
+			_ => {}
+		}
+	}
+}
+
+// Escapes text for roff, a leading . or ' would otherwise be read as a request
+fn escape_roff(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for ch in text.chars() {
+		match ch {
+			'\\' => escaped.push_str("\\e"),
+			'.' | '\'' => {
+				escaped.push_str("\\&");
+				escaped.push(ch);
+			}
+			_ => escaped.push(ch),
+		}
+	}
+	escaped
+}
+
+// Macro arguments are double quoted, so quotes inside them need the named glyph
+fn roff_argument(text: &str) -> String {
+	format!("\"{}\"", text.trim().replace('"', "\\(dq"))
+}
+
+struct ManPageWriter {
+	output: String,
+	heading: Option<String>,
+	th: Option<String>,
+	lists: Vec<Option<u64>>,
+	item_paragraphs: usize,
+	links: Vec<String>,
+	in_code_block: bool,
+	in_image: bool,
+	table_cell: usize,
+}
+
+impl ManPageWriter {
+	fn render(doc: &Document, section: u8, volume: &str) -> String {
+		let mut writer = Self {
+			output: String::new(),
+			heading: None,
+			th: None,
+			lists: Vec::new(),
+			item_paragraphs: 0,
+			links: Vec::new(),
+			in_code_block: false,
+			in_image: false,
+			table_cell: 0,
+		};
+
+		let parser = Parser::new_ext(&doc.content, ContentProcessor::markdown_options());
+		for event in parser {
+			writer.event(event);
+		}
+		if !writer.output.is_empty() && !writer.output.ends_with('\n') {
+			writer.output.push('\n');
+		}
+
+		// The first H1 names the page, documents without one fall back to their title
+		let title = writer
+			.th
+			.take()
+			.unwrap_or_else(|| escape_roff(&doc_title(doc)));
+		// Never the current time, the same sources should give the same page
+		let date = doc
+			.frontmatter
+			.date
+			.or(doc.frontmatter.last_modified.map(|t| t.date_naive()))
+			.or_else(|| {
+				let epoch = std::env::var("SOURCE_DATE_EPOCH").ok()?.parse().ok()?;
+				chrono::DateTime::from_timestamp(epoch, 0).map(|t| t.date_naive())
+			})
+			.map(|d| d.to_string())
+			.unwrap_or_default();
+
+		let mut page = format!(
+			".TH {} {} {} \"\" {}\n",
+			roff_argument(&title),
+			roff_argument(&section.to_string()),
+			roff_argument(&escape_roff(&date)),
+			roff_argument(&escape_roff(volume))
+		);
+		page.push_str(&writer.output);
+		page
+	}
+
+	fn out(&mut self) -> &mut String {
+		match self.heading.as_mut() {
+			Some(heading) => heading,
+			None => &mut self.output,
+		}
+	}
+
+	// Requests have to start a line of their own
+	fn request(&mut self, request: &str) {
+		if !self.output.is_empty() && !self.output.ends_with('\n') {
+			self.output.push('\n');
+		}
+		self.output.push_str(request);
+		self.output.push('\n');
+	}
+
+	fn event(&mut self, event: Event) {
+		match event {
+			Event::Start(tag) => self.start(tag),
+			Event::End(tag) => self.end(tag),
+			Event::Text(text) => {
+				if self.in_code_block {
+					// Blank lines are not allowed in the output, even inside no-fill blocks
+					for line in text.lines() {
+						if line.is_empty() {
+							self.output.push_str("\\&\n");
+						} else {
+							self.output.push_str(&escape_roff(line));
+							self.output.push('\n');
+						}
+					}
+				} else if !self.in_image {
+					let text = escape_roff(&text);
+					self.out().push_str(&text);
+				}
+			}
+			Event::Code(code) => {
+				let code = format!("\\f[CW]{}\\fR", escape_roff(&code));
+				self.out().push_str(&code);
+			}
+			Event::SoftBreak => self.out().push('\n'),
+			Event::HardBreak => self.request(".br"),
+			Event::Rule => self.request(".PP"),
+			Event::TaskListMarker(checked) => {
+				self.out().push_str(if checked { "[x] " } else { "[ ] " })
+			}
+			_ => {}
+		}
+	}
+
+	fn start(&mut self, tag: Tag) {
+		match tag {
+			Tag::Heading { .. } => self.heading = Some(String::new()),
+			Tag::Paragraph => {
+				if self.lists.is_empty() {
+					self.request(".PP");
+				} else {
+					// The first paragraph sits on the item's tag line, later ones keep its indent
+					if self.item_paragraphs > 0 {
+						self.request(".IP");
+					}
+					self.item_paragraphs += 1;
+				}
+			}
+			Tag::Emphasis => self.out().push_str("\\fI"),
+			Tag::Strong => self.out().push_str("\\fB"),
+			Tag::BlockQuote { .. } => self.request(".RS"),
+			Tag::CodeBlock(_) => {
+				self.in_code_block = true;
+				self.request(".PP");
+				self.request(".nf");
+			}
+			Tag::List(start) => {
+				if !self.lists.is_empty() {
+					self.request(".RS");
+				}
+				self.lists.push(start);
+			}
+			Tag::Item => {
+				self.item_paragraphs = 0;
+				match self.lists.last_mut() {
+					Some(Some(number)) => {
+						let request = format!(".IP {}. 4", number);
+						*number += 1;
+						self.request(&request);
+					}
+					_ => self.request(".IP \\(bu 2"),
+				}
+			}
+			Tag::Link { dest_url, .. } => self.links.push(dest_url.to_string()),
+			Tag::Image { .. } => self.in_image = true,
+			Tag::Table(_) => self.request(".PP"),
+			Tag::TableHead | Tag::TableRow => self.table_cell = 0,
+			Tag::TableCell => {
+				if self.table_cell > 0 {
+					self.output.push_str(" | ");
+				}
+				self.table_cell += 1;
+			}
+			_ => {}
+		}
+	}
+
+	fn end(&mut self, tag: TagEnd) {
+		match tag {
+			TagEnd::Heading(level) => {
+				let text = self.heading.take().unwrap_or_default();
+				match level as usize {
+					1 if self.th.is_none() => self.th = Some(text),
+					1 | 2 => self.request(&format!(".SH {}", roff_argument(&text))),
+					_ => self.request(&format!(".SS {}", roff_argument(&text))),
+				}
+			}
+			TagEnd::Emphasis | TagEnd::Strong => self.out().push_str("\\fR"),
+			TagEnd::BlockQuote { .. } => self.request(".RE"),
+			TagEnd::CodeBlock => {
+				self.in_code_block = false;
+				self.request(".fi");
+			}
+			TagEnd::List(_) => {
+				self.lists.pop();
+				if !self.lists.is_empty() {
+					self.request(".RE");
+				}
+			}
+			TagEnd::Link => {
+				let url = self.links.pop().unwrap_or_default();
+				if !url.is_empty() && !url.starts_with('#') {
+					let url = format!(" <{}>", escape_roff(&url));
+					self.out().push_str(&url);
+				}
+			}
+			TagEnd::Image => self.in_image = false,
+			TagEnd::TableHead | TagEnd::TableRow => self.request(".br"),
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::content::Frontmatter;
+
+	#[tokio::test]
+	async fn test_export_pdfs() {
+		// Needs a Chromium install, so only runs where CI_PDF is set
+		if std::env::var_os("CI_PDF").is_none() {
+			return;
+		}
+
+		let output = std::env::temp_dir().join(format!("rum-pdf-test-{}", std::process::id()));
+		fs::create_dir_all(output.join("guide")).unwrap();
+		fs::write(
+			output.join("guide/setup.html"),
+			"<html><body><h1>Setup</h1></body></html>",
+		)
+		.unwrap();
+		let doc = Document {
+			path: PathBuf::from("guide/setup.md"),
+			relative_path: PathBuf::from("guide/setup.md"),
+			..Default::default()
+		};
+
+		Exporter::new(&output)
+			.export_pdfs(&[doc], &Config::default())
+			.await
+			.unwrap();
+		let pdf = fs::read(output.join("pdf/guide/setup.pdf")).unwrap();
+		fs::remove_dir_all(&output).ok();
+		assert!(pdf.starts_with(b"%PDF"));
+	}
+
+	#[tokio::test]
+	async fn test_export_epub() {
+		let root = std::env::temp_dir().join(format!("rum-epub-test-{}", std::process::id()));
+		fs::create_dir_all(root.join("docs/latest")).unwrap();
+		fs::write(root.join("docs/latest/pic.png"), b"\x89PNG").unwrap();
+
+		let doc = |path: &str, version: Option<&str>, html: &str| Document {
+			html_content: html.to_string(),
+			path: root.join("docs").join(path),
+			relative_path: PathBuf::from(path),
+			version: version.map(str::to_string),
+			..Default::default()
+		};
+		let documents = vec![
+			doc("index.md", None, "<p>Home</p>"),
+			doc(
+				"latest/guide.md",
+				Some("latest"),
+				"<p><img src=\"pic.png\" alt=\"\" /></p>",
+			),
+			doc("latest/more/deep.md", Some("latest"), "<p>Deep</p>"),
+		];
+
+		let output = root.join("dist");
+		Exporter::new(&output)
+			.export_epub(&documents, &Config::default())
+			.unwrap();
+
+		let file = fs::File::open(output.join(format!("{}.epub", Config::default().site.title)));
+		let mut archive = zip::ZipArchive::new(file.unwrap()).unwrap();
+		let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+		let chapters = names
+			.iter()
+			.filter(|n| n.contains("chapter_") && n.ends_with(".xhtml"))
+			.count();
+		assert_eq!(chapters, documents.len());
+		assert!(names.iter().any(|n| n.ends_with("images/1-pic.png")));
+
+		let mut chapter = String::new();
+		let entry = names
+			.iter()
+			.find(|n| n.ends_with("chapter_2.xhtml"))
+			.unwrap();
+		std::io::Read::read_to_string(&mut archive.by_name(entry).unwrap(), &mut chapter).unwrap();
+		assert!(chapter.contains("src=\"images/1-pic.png\""));
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_export_json() {
+		let output = std::env::temp_dir().join(format!("rum-json-test-{}", std::process::id()));
+		let doc = Document {
+			frontmatter: Frontmatter {
+				title: Some("Setup".to_string()),
+				tags: Some(vec!["guide".to_string()]),
+				..Default::default()
+			},
+			content: "# Install\n\nRun `cargo build` with **care**.\n\n- [Docs](index.html)\n"
+				.to_string(),
+			html_content: "<h1>Install</h1>".to_string(),
+			path: PathBuf::from("docs/v1/guide/setup.md"),
+			relative_path: PathBuf::from("v1/guide/setup.md"),
+			version: Some("v1".to_string()),
+			backlinks: vec!["index.html".to_string()],
+			links: vec!["index.html".to_string()],
+			word_count: 7,
+			reading_time_minutes: 1,
+			..Default::default()
+		};
+
+		Exporter::new(&output)
+			.export_json(&[doc], &Config::default())
+			.unwrap();
+		let page: serde_json::Value = serde_json::from_str(
+			&fs::read_to_string(output.join("api/v1-guide-setup.json")).unwrap(),
+		)
+		.unwrap();
+		let index: serde_json::Value =
+			serde_json::from_str(&fs::read_to_string(output.join("api/index.json")).unwrap())
+				.unwrap();
+		fs::remove_dir_all(&output).ok();
+
+		assert_eq!(page["title"], "Setup");
+		assert_eq!(page["content"], "Install\nRun cargo build with care.\nDocs");
+		assert_eq!(page["html_content"], "<h1>Install</h1>");
+		assert_eq!(page["tags"], serde_json::json!(["guide"]));
+		assert_eq!(page["version"], "v1");
+		assert_eq!(page["relative_path"], "v1/guide/setup.md");
+		assert_eq!(page["word_count"], 7);
+		assert_eq!(
+			index,
+			serde_json::json!([{
+				"title": "Setup",
+				"tags": ["guide"],
+				"date": null,
+				"version": "v1",
+				"url": "/v1/guide/setup.html",
+			}])
+		);
+	}
+
+	#[test]
+	fn test_latex_writer() {
+		let doc = Document {
+			frontmatter: Frontmatter {
+				title: Some("Tips & Tricks".to_string()),
+				..Default::default()
+			},
+			content: "# Setup_guide\n\n## Install\n\n- one\n- two\n\n1. first\n\n\
+				| Name | Cost |\n|:-----|-----:|\n| a_b | 5% |\n\n\
+				![Diagram](img/my%20chart_v1&2.png) ![Odd](img/100%25.png)\n\n\
+				[Docs](https://example.com/a_b?x=1&y={2}#top%20here)\n\n\
+				```\nlet x = a_b & {c};\n```\n\n```latex\n\\end{verbatim}\n```\n"
+				.to_string(),
+			path: PathBuf::from("tips.md"),
+			relative_path: PathBuf::from("tips.md"),
+			..Default::default()
+		};
+		let tex = LatexWriter::render(&doc);
+		assert!(tex.starts_with("\\chapter{Tips \\& Tricks}\n"));
+		assert!(tex.contains("\\section{Setup\\_guide}\n"));
+		assert!(tex.contains("\\subsection{Install}\n"));
+		assert!(tex.contains("\\begin{itemize}\n\\item one\n\\item two\n\\end{itemize}\n"));
+		assert!(tex.contains("\\begin{enumerate}\n\\item first\n\\end{enumerate}\n"));
+		assert!(tex.contains(
+			"\\begin{tabular}{lr}\n\\hline\nName & Cost \\\\\n\\hline\na\\_b & 5\\% \\\\\n\\hline\n\\end{tabular}\n"
+		));
+		assert!(tex
+			.contains("\\includegraphics[width=\\linewidth]{\\detokenize{img/my chart_v1&2.png}}"));
+		assert!(!tex.contains("100"));
+		assert!(
+			tex.contains("\\href{https://example.com/a_b?x=1&y=\\%7B2\\%7D\\#top\\%20here}{Docs}")
+		);
+		assert!(tex.contains("\\begin{verbatim}\nlet x = a_b & {c};\n\\end{verbatim}\n"));
+		assert!(tex.contains("\\begin{alltt}\n\\textbackslash{}end\\{verbatim\\}\n\\end{alltt}\n"));
+		assert_eq!(tex.matches("\\end{verbatim}").count(), 1);
+	}
+
+	#[test]
+	fn test_man_page_writer() {
+		let doc = Document {
+			content: include_str!("../tests/fixtures/man.md").to_string(),
+			path: PathBuf::from("rum.md"),
+			relative_path: PathBuf::from("rum.md"),
+			..Default::default()
+		};
+		let page = ManPageWriter::render(&doc, 1, "Rum \"Docs\"");
+		assert!(page.starts_with(".TH \"rum\" \"1\" \""));
+		let dated = Document {
+			frontmatter: Frontmatter {
+				last_modified: "2024-03-09T22:15:00Z".parse().ok(),
+				..Default::default()
+			},
+			..doc.clone()
+		};
+		assert!(ManPageWriter::render(&dated, 1, "Rum")
+			.starts_with(".TH \"rum\" \"1\" \"2024-03-09\" \"\" \"Rum\"\n"));
+		assert!(page.contains("\"\" \"Rum \\(dqDocs\\(dq\"\n"));
+		assert!(page
+			.contains("\\&.hidden back\\eslash \\fBbold\\fR \\fIit\\fR \\f[CW]\\&'code\\&'\\fR"));
+		assert!(page.contains(".nf\n\\&.TH fake\n\\&\nrum build\n.fi\n"));
+		assert!(
+			page.contains(".IP \\(bu 2\none\n.IP \\(bu 2\ntwo\n.RS\n.IP 1. 4\nnested\n.IP 2. 4\n")
+		);
+
+		// Only checked where groff is installed
+		let path = std::env::temp_dir().join(format!("rum-man-test-{}.1", std::process::id()));
+		fs::write(&path, &page).unwrap();
+		let output = Command::new("groff")
+			.args(["-man", "-ww", "-e", "-t", "-T", "utf8"])
+			.arg(&path)
+			.output();
+		if let Ok(output) = output {
+			let warnings = String::from_utf8_lossy(&output.stderr);
+			assert!(
+				output.status.success() && warnings.is_empty(),
+				"{}",
+				warnings
+			);
+		}
+
+		// And through man itself where available, the escapes must come back out as plain text
+		let output = Command::new("man")
+			.env("MANWIDTH", "80")
+			.arg("--local-file")
+			.arg(&path)
+			.output();
+		fs::remove_file(&path).ok();
+		if let Some(output) = output.ok().filter(|o| o.status.success()) {
+			let text = String::from_utf8_lossy(&output.stdout);
+			for expected in [
+				"Synopsis",
+				".hidden back\\slash",
+				"code",
+				"1. nested",
+				".TH fake",
+				"rum build",
+			] {
+				assert!(
+					text.contains(expected),
+					"missing {:?} in {}",
+					expected,
+					text
+				);
+			}
+		}
+	}
 }
@@ -1,13 +1,49 @@
-use anyhow::Result;
-use std::collections::HashMap;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Instant;
+use tracing::{debug, instrument};
 use walkdir::WalkDir;
 
-use crate::config::Config;
-use crate::content::{ContentProcessor, Document};
-use crate::export::Exporter;
-use crate::templates::TemplateEngine;
+use crate::buildlog::BuildLog;
+use crate::cache::BuildCache;
+use crate::check::{self, LinkError, LintIssue};
+use crate::collections::Collections;
+use crate::config::{parse_version, Config, SectionConfig};
+use crate::content::{
+	document_locale, external_links, is_internal_link, resolve_image, syntax_theme_css,
+	translation_key, wiki_link_slug, ContentProcessor, Document, Frontmatter, Plugins,
+};
+use crate::export::{document_slug, Exporter};
+use crate::graph::DocumentGraph;
+use crate::ignore::{read_ignore_file, IgnoreRules};
+use crate::minify;
+use crate::order::{resolve_nav_order, NavOrder};
+use crate::schema::{FrontmatterError, FrontmatterSchema};
+use crate::summary::{BuildStats, BuildSummary};
+use crate::templates::{
+	extra_asset_name, fingerprinted_name, AssetManifest, Listing, PageContext, TemplateEngine,
+};
+use crate::urls::UrlResolver;
+use crate::utils::{absolute_url, escape_html, is_valid_lang, slugify, write_atomic};
+
+const FORMATS: &[&str] = &["html", "pdf", "man", "latex", "epub", "json"];
+
+static IMG_SRC_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r#"(<img[^>]*\ssrc=")([^"]+)(")"#).unwrap());
 
 pub struct Generator {
 	source_dir: PathBuf,
@@ -15,6 +51,24 @@ pub struct Generator {
 	config: Config,
 	processor: ContentProcessor,
 	template_engine: TemplateEngine,
+	template_dir: Option<PathBuf>,
+	ignore: IgnoreRules,
+	nav_order: NavOrder,
+	collections: Collections,
+	schema: Option<FrontmatterSchema>,
+	log: BuildLog,
+	parallel: bool,
+	incremental: bool,
+	strict: bool,
+	include_drafts: bool,
+	production: bool,
+	no_html: bool,
+	print: bool,
+	search_entries: Option<Arc<RwLock<Vec<SearchEntry>>>>,
+	documents: Option<Arc<RwLock<Vec<Document>>>>,
+	navigation: Option<Arc<RwLock<NavigationTree>>>,
+	// .rum-cache next to rum.toml, outside the output directory a full build wipes
+	cache_dir: PathBuf,
 }
 
 impl Generator {
@@ -24,8 +78,102 @@ impl Generator {
 		config_path: Option<PathBuf>,
 	) -> Result<Self> {
 		let config = Config::load(config_path.as_deref())?;
-		let processor = ContentProcessor::new();
-		let template_engine = TemplateEngine::new()?;
+		let project_root = match config_path.as_deref().and_then(Path::parent) {
+			Some(root) => root.to_path_buf(),
+			None => std::env::current_dir()?,
+		};
+		for theme in [&config.syntax.theme_light, &config.syntax.theme_dark] {
+			syntax_theme_css(theme)?;
+		}
+		if config.navigation.max_depth == Some(0) {
+			bail!("navigation.max_depth must be at least 1, leave it out to show every level");
+		}
+		if !matches!(config.mermaid.renderer.as_str(), "api" | "cli") {
+			bail!(
+				"Unknown mermaid renderer '{}', expected \"api\" or \"cli\"",
+				config.mermaid.renderer
+			);
+		}
+		if config.feed.enabled && !matches!(config.feed.format.as_str(), "rss2" | "atom") {
+			bail!(
+				"Unknown feed format '{}', expected \"rss2\" or \"atom\"",
+				config.feed.format
+			);
+		}
+		if config.sitemap.enabled && config.sitemap.base_url.is_none() {
+			bail!("sitemap.base_url is required when the sitemap is enabled");
+		}
+		if !matches!(
+			config.build.sort_by.as_str(),
+			"order" | "date" | "title" | "filename"
+		) {
+			bail!(
+				"Unknown build.sort_by '{}', expected \"order\", \"date\", \"title\" or \"filename\"",
+				config.build.sort_by
+			);
+		}
+		for (alias, target) in &config.site.version_aliases {
+			if parse_version(target).is_none() {
+				bail!(
+					"Version alias '{}' points to '{}', which is not a semantic version",
+					alias,
+					target
+				);
+			}
+		}
+		// ammonia always drops these along with their contents and refuses to allow them
+		for tag in &config.security.allowed_tags {
+			if matches!(tag.to_ascii_lowercase().as_str(), "script" | "style") {
+				bail!("security.allowed_tags cannot include <{}>", tag);
+			}
+		}
+		if config.pagination.page_size == 0 {
+			bail!("pagination.page_size must be at least 1");
+		}
+		if config.display.words_per_minute == 0 {
+			bail!("display.words_per_minute must be at least 1");
+		}
+		if !matches!(
+			config.repository.kind.as_str(),
+			"github" | "gitlab" | "gitea"
+		) {
+			bail!(
+				"Unknown repository type '{}', expected \"github\", \"gitlab\" or \"gitea\"",
+				config.repository.kind
+			);
+		}
+		if !matches!(config.output.structure.as_str(), "mirror" | "flat") {
+			bail!(
+				"Unknown output structure '{}', expected \"mirror\" or \"flat\"",
+				config.output.structure
+			);
+		}
+		if config.output.structure == "flat" && !config.urls.pattern.is_empty() {
+			bail!("output.structure = \"flat\" cannot be combined with urls.pattern, the pattern decides every output path");
+		}
+		let mut ignore = config.build.ignore.clone();
+		ignore.extend(read_ignore_file(&project_root.join(".rumignore"))?);
+		let ignore = IgnoreRules::new(&ignore)?;
+		let nav_order = NavOrder::new(&config.navigation.order)?;
+		let collections = Collections::new(&config.collections)?;
+		let plugins: Vec<PathBuf> = config
+			.plugins
+			.wasm
+			.iter()
+			.map(|path| project_root.join(path))
+			.collect();
+		let processor =
+			ContentProcessor::new_with_config(&config).with_plugins(Plugins::load(&plugins)?);
+		let schema = match &config.validation.schema {
+			Some(path) => Some(FrontmatterSchema::load(&project_root.join(path))?),
+			None => None,
+		};
+		let template_dir = config
+			.theme
+			.template_dir
+			.as_ref()
+			.map(|dir| project_root.join(dir));
+		let template_engine = TemplateEngine::from_dir(template_dir.as_deref())?;
 
 		Ok(Self {
 			source_dir,
@@ -33,51 +181,625 @@ impl Generator {
 			config,
 			processor,
 			template_engine,
+			template_dir,
+			ignore,
+			nav_order,
+			collections,
+			schema,
+			log: BuildLog::default(),
+			parallel: false,
+			incremental: false,
+			strict: false,
+			include_drafts: false,
+			production: false,
+			no_html: false,
+			print: false,
+			search_entries: None,
+			documents: None,
+			navigation: None,
+			cache_dir: project_root.join(".rum-cache"),
 		})
 	}
 
-	pub async fn build(&self, formats: &str) -> Result<()> {
+	pub fn with_log(mut self, log: BuildLog) -> Self {
+		self.log = log;
+		self
+	}
+
+	pub fn log(&self) -> &BuildLog {
+		&self.log
+	}
+
+	pub fn config(&self) -> &Config {
+		&self.config
+	}
+
+	pub fn with_parallel(mut self, parallel: bool) -> Self {
+		self.parallel = parallel;
+		self
+	}
+
+	// Takes over from `theme.template_dir`, None keeps whatever the config set
+	pub fn with_template_dir(mut self, template_dir: Option<&Path>) -> Result<Self> {
+		if let Some(dir) = template_dir {
+			self.template_engine = TemplateEngine::from_dir(Some(dir))?;
+			self.template_dir = Some(dir.to_path_buf());
+		}
+		Ok(self)
+	}
+
+	// None for the built-in templates
+	pub fn template_dir(&self) -> Option<&Path> {
+		self.template_dir.as_deref()
+	}
+
+	pub fn with_incremental(mut self, incremental: bool) -> Self {
+		self.incremental = incremental;
+		self
+	}
+
+	pub fn with_strict(mut self, strict: bool) -> Self {
+		self.strict = strict;
+		self
+	}
+
+	pub fn with_include_drafts(mut self, include_drafts: bool) -> Self {
+		self.include_drafts = include_drafts;
+		self
+	}
+
+	// `rum build`, applying `[build.environments.production]`
+	pub fn with_production(mut self, production: bool) -> Self {
+		self.production = production;
+		self
+	}
+
+	// Writes the documents and navigation as JSON instead of rendering any pages
+	pub fn with_no_html(mut self, no_html: bool) -> Self {
+		self.no_html = no_html;
+		self
+	}
+
+	// Also writes print/{slug}.html for every page, without the sidebar
+	pub fn with_print(mut self, print: bool) -> Self {
+		self.print = print;
+		self
+	}
+
+	// Kept up to date on every build, for the dev server's search API
+	pub fn with_search_entries(mut self, search_entries: Arc<RwLock<Vec<SearchEntry>>>) -> Self {
+		self.search_entries = Some(search_entries);
+		self
+	}
+
+	// The documents of the last build, kept so watch mode can re-parse only what changed
+	pub fn with_documents(mut self, documents: Arc<RwLock<Vec<Document>>>) -> Self {
+		self.documents = Some(documents);
+		self
+	}
+
+	// Kept up to date on every build, for the dev server's navigation API
+	pub fn with_navigation(mut self, navigation: Arc<RwLock<NavigationTree>>) -> Self {
+		self.navigation = Some(navigation);
+		self
+	}
+
+	// Small sites render faster without the thread pool overhead
+	fn use_parallel(&self, count: usize) -> bool {
+		self.parallel || count >= self.config.build.parallel_threshold
+	}
+
+	pub async fn build(&self, formats: &str) -> Result<BuildSummary> {
 		// Clean output directory
 		if self.output_dir.exists() {
-			fs::remove_dir_all(&self.output_dir)?;
+			if self.incremental {
+				BuildCache::clean(&self.output_dir)?;
+			} else {
+				fs::remove_dir_all(&self.output_dir)?;
+			}
+		}
+		self.generate(formats).await
+	}
+
+	// Renders into the output directory as-is, leaving existing files in place
+	#[instrument(skip(self))]
+	pub async fn generate(&self, formats: &str) -> Result<BuildSummary> {
+		let started = Instant::now();
+		let formats = &parse_formats(formats)?;
+		if self.no_html && formats.contains(&"pdf") {
+			bail!("PDFs are printed from the HTML pages, so the pdf format cannot be used with --no-html");
+		}
+		if self.no_html && self.print {
+			bail!("--print renders pages, so it cannot be used with --no-html");
 		}
+		let warnings_before = self.log.warning_count();
+		let errors_before = self.log.error_count();
+		let mut stats = BuildStats::default();
 		fs::create_dir_all(&self.output_dir)?;
+		for lang in std::iter::once(&self.config.site.language).chain(&self.config.i18n.locales) {
+			if !is_valid_lang(lang) {
+				self.log.warn(
+					"W013",
+					None,
+					&format!(
+						"Ignoring invalid language '{}' for the html lang attribute",
+						lang
+					),
+				);
+			}
+		}
 
-		// Collect all documents
-		let documents = self.collect_documents()?;
+		// Collect all documents, reusing unchanged ones from the last build when incremental
+		let cache = if self.incremental {
+			Some(BuildCache::load(
+				&self.output_dir,
+				&self.config,
+				self.template_engine.base_template(),
+			)?)
+		} else {
+			None
+		};
+		let phase = Instant::now();
+		let documents = self.collect_documents(cache.as_ref())?;
+		stats.record("collect", phase);
+		stats.documents_collected = documents.len();
+		stats.documents_from_cache = cache.as_ref().map_or(0, BuildCache::reused);
 
 		// Process backlinks
+		let phase = Instant::now();
 		let documents = self.process_backlinks(documents);
+		stats.record("backlinks", phase);
+		stats.backlinks_processed = documents.iter().map(|d| d.backlinks.len()).sum();
+
+		let link_errors = self.check_links(&documents);
+		for error in &link_errors {
+			self.log.warn(
+				"W002",
+				Some(&error.source_path),
+				&format!("Broken link to {} in: {}", error.target, error.context),
+			);
+		}
+		if self.strict && !link_errors.is_empty() {
+			bail!(
+				"Found {} broken internal link(s) and --strict is set",
+				link_errors.len()
+			);
+		}
+
+		for doc in &documents {
+			let unknown = self.unknown_frontmatter_fields(doc);
+			if !unknown.is_empty() {
+				self.log.warn(
+					"W008",
+					Some(&doc.relative_path),
+					&format!(
+						"Unknown frontmatter field(s) {}, list custom ones under validation.extra_fields",
+						unknown.join(", ")
+					),
+				);
+			}
+		}
+
+		let frontmatter_errors = self.validate_frontmatter(&documents);
+		for error in &frontmatter_errors {
+			self.log.warn(
+				"W003",
+				Some(&error.document_path),
+				&format!("Frontmatter field '{}': {}", error.field, error.message),
+			);
+		}
+		if self.strict && !frontmatter_errors.is_empty() {
+			bail!(
+				"Found {} frontmatter error(s) and --strict is set",
+				frontmatter_errors.len()
+			);
+		}
 
 		// Build navigation structure
+		let phase = Instant::now();
 		let navigation = self.build_navigation(&documents);
+		stats.record("navigation", phase);
 
 		// Generate search index
-		let search_index = self.generate_search_index(&documents);
+		let phase = Instant::now();
+		let search_index = self.generate_search_index(&documents)?;
+		self.publish_search_entries(&documents);
+		self.publish_navigation(&navigation);
+		stats.record("search_index", phase);
+		if let Some(store) = &self.documents {
+			*store.write().unwrap() = documents.clone();
+		}
+
+		// Each requested format renders concurrently from the same shared corpus
+		let documents = Arc::new(documents);
+		let exporter = Exporter::new(&self.output_dir)
+			.with_chromium_path(self.config.pdf.chromium_path.clone());
+
+		// PDFs are printed from the rendered pages, so the pdf format implies html
+		let html = {
+			let (documents, stats) = (Arc::clone(&documents), &mut stats);
+			async move {
+				if self.no_html {
+					if formats.contains(&"html") {
+						self.generate_content(&documents, &navigation, &search_index)?;
+					}
+				} else if formats.contains(&"html") || formats.contains(&"pdf") {
+					self.generate_html(&documents, &navigation, &search_index, stats)
+						.await?;
+				}
+				Ok::<_, anyhow::Error>(())
+			}
+		};
+
+		let pdf = {
+			let (documents, exporter) = (Arc::clone(&documents), exporter.clone());
+			async move {
+				if formats.contains(&"pdf") {
+					exporter.export_pdfs(&documents, &self.config).await?;
+				}
+				Ok::<_, anyhow::Error>(())
+			}
+		};
+
+		// The other exporters only do blocking work, each gets its own thread while this task
+		// renders the pages
+		let export = |format: &'static str,
+		              run: fn(&Exporter, &[Document], &Config) -> Result<()>| {
+			let (documents, exporter, config) = (
+				Arc::clone(&documents),
+				exporter.clone(),
+				self.config.clone(),
+			);
+			let requested = formats.contains(&format);
+			tokio::task::spawn_blocking(move || {
+				if requested {
+					run(&exporter, &documents, &config)?;
+				}
+				Ok::<_, anyhow::Error>(())
+			})
+		};
+		let man = export("man", Exporter::export_man_pages);
+		let latex = export("latex", Exporter::export_latex);
+		let epub = export("epub", Exporter::export_epub);
+		let json = export("json", Exporter::export_json);
 
-		// Generate HTML
-		if formats.contains("html") {
-			self.generate_html(&documents, &navigation, &search_index)
-				.await?;
+		let html = html.await;
+		let pdf = if html.is_ok() { pdf.await } else { Ok(()) };
+		let joined = |result: Result<Result<()>, tokio::task::JoinError>| {
+			result.unwrap_or_else(|e| Err(anyhow!("exporter panicked: {}", e)))
+		};
+		let (man, latex, epub, json) = tokio::join!(man, latex, epub, json);
+		let (man, latex, epub, json) = (joined(man), joined(latex), joined(epub), joined(json));
+
+		// Report every failed format rather than just the first one
+		let failures: Vec<String> = [
+			("html", html),
+			("pdf", pdf),
+			("man", man),
+			("latex", latex),
+			("epub", epub),
+			("json", json),
+		]
+		.into_iter()
+		.filter_map(|(format, result)| result.err().map(|e| format!("{}: {:#}", format, e)))
+		.collect();
+		if !failures.is_empty() {
+			bail!("Build failed for {}", failures.join("; "));
 		}
 
-		// Generate PDFs
-		if formats.contains("pdf") {
-			let exporter = Exporter::new(&self.output_dir);
-			exporter.export_pdfs(&documents, &self.config).await?;
+		// Checked against lint.max_warnings and warnings_as_errors once the caller printed the summary
+		let warnings = self.log.warning_count() - warnings_before;
+		let lint = &self.config.lint;
+		let mut summary = BuildSummary::collect(
+			&self.output_dir,
+			&documents,
+			&self.config,
+			started.elapsed(),
+		);
+		summary.warnings = warnings;
+		summary.max_warnings = lint.max_warnings;
+		summary.warnings_as_errors = lint.warnings_as_errors;
+		stats.warnings = warnings;
+		stats.errors = self.log.error_count() - errors_before;
+		summary.stats = stats;
+
+		if let Some(cache) = &cache {
+			cache.save()?;
 		}
+		Ok(summary)
+	}
+
+	pub fn check(&self) -> Result<Vec<LintIssue>> {
+		let documents = self.collect_documents(None)?;
+		let documents = self.process_backlinks(documents);
+		let navigation = self.build_navigation(&documents);
 
-		// Generate man pages
-		if formats.contains("man") {
-			let exporter = Exporter::new(&self.output_dir);
-			exporter.export_man_pages(&documents, &self.config).await?;
+		let mut issues = Vec::new();
+		if self.config.lint.warn_orphans {
+			let graph = DocumentGraph::new(&documents);
+			issues.extend(check::find_orphans(&graph, &navigation.flatten()));
 		}
 
-		Ok(())
+		Ok(issues)
 	}
 
-	fn collect_documents(&self) -> Result<Vec<Document>> {
-		let mut documents = Vec::new();
+	// The link graph as GraphViz DOT or JSON, for `rum graph`
+	pub fn export_graph(&self, format: &str) -> Result<String> {
+		let documents = self.process_backlinks(self.collect_documents(None)?);
+		let graph = DocumentGraph::new(&documents);
+		match format {
+			"dot" => Ok(graph.to_dot()),
+			"json" => Ok(format!(
+				"{}\n",
+				serde_json::to_string_pretty(&graph.to_json())?
+			)),
+			other => bail!(
+				"Unknown graph format '{}', expected \"dot\" or \"json\"",
+				other
+			),
+		}
+	}
+
+	// Content checks only, nothing is rendered or written
+	pub fn lint(&self) -> Result<Vec<LintIssue>> {
+		let documents = self.process_backlinks(self.collect_documents(None)?);
+
+		let mut issues = check::lint_links(&self.check_links(&documents));
+		issues.extend(check::lint_draft_links(&documents));
+		issues.extend(check::lint_tags(&documents));
+		issues.extend(check::lint_duplicate_titles(&documents));
+		issues.extend(check::lint_required_fields(
+			&documents,
+			&self.config.lint.required_fields,
+		));
+		issues.extend(check::lint_frontmatter_errors(
+			&self.validate_frontmatter(&documents),
+		));
+		issues.sort_by(|a, b| a.path.cmp(&b.path).then(b.severity.cmp(&a.severity)));
+		Ok(issues)
+	}
+
+	// Against `validation.schema`, nothing to report when none is set
+	pub fn validate_frontmatter(&self, documents: &[Document]) -> Vec<FrontmatterError> {
+		let Some(schema) = &self.schema else {
+			return Vec::new();
+		};
+		documents
+			.iter()
+			.flat_map(|doc| schema.validate(&doc.relative_path, &doc.frontmatter))
+			.collect()
+	}
+
+	// Keys that ended up in `extra` without being declared anywhere, most likely typos. AsciiDoc
+	// attributes and notebook metadata land there too, those are not the author's frontmatter.
+	fn unknown_frontmatter_fields<'a>(&self, doc: &'a Document) -> Vec<&'a str> {
+		if matches!(
+			doc.path.extension().and_then(|e| e.to_str()),
+			Some("adoc" | "ipynb")
+		) {
+			return Vec::new();
+		}
+		let declared = |key: &str| {
+			self.config.validation.extra_fields.iter().any(|f| f == key)
+				|| self.config.lint.required_fields.iter().any(|f| f == key)
+				|| self
+					.config
+					.search
+					.index_fields
+					.iter()
+					.any(|f| f.strip_prefix("extra.") == Some(key))
+		};
+		doc.frontmatter
+			.extra
+			.keys()
+			.map(String::as_str)
+			.filter(|key| !declared(key))
+			.collect()
+	}
+
+	// External URLs and the documents linking to them
+	pub fn external_links(&self) -> Result<BTreeMap<String, Vec<PathBuf>>> {
+		let mut links: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+		for doc in self.collect_documents(None)? {
+			for link in external_links(&doc.content) {
+				let sources = links.entry(link).or_default();
+				if !sources.contains(&doc.relative_path) {
+					sources.push(doc.relative_path.clone());
+				}
+			}
+		}
+		Ok(links)
+	}
+
+	pub fn untranslated(&self) -> Result<Vec<(String, Vec<PathBuf>)>> {
+		if self.config.i18n.locales.is_empty() {
+			bail!("No locales to check, set i18n.locales in the config file");
+		}
+		let documents = self.collect_documents(None)?;
+		Ok(check::untranslated_pages(&documents, &self.config))
+	}
+
+	// Links are resolved the way a browser would, relative to the page's own output location
+	pub fn check_links(&self, documents: &[Document]) -> Vec<LinkError> {
+		let mut pages: HashSet<PathBuf> = documents
+			.iter()
+			.map(|d| output_path(d, &self.config))
+			.collect();
+		pages.insert(PathBuf::from("tags/index.html"));
+		for slug in group_by_tag(documents).keys() {
+			pages.insert(Path::new("tags").join(slug).join("index.html"));
+		}
+		pages.insert(PathBuf::from("authors/index.html"));
+		for slug in group_by_author(documents).keys() {
+			pages.insert(Path::new("authors").join(slug).join("index.html"));
+		}
+		for dir in listing_directories(documents).keys() {
+			pages.insert(dir.join("index.html"));
+		}
+		for collection in &self.config.collections {
+			pages.insert(Path::new(&collection.name).join("index.html"));
+		}
+
+		let mut errors = Vec::new();
+		for doc in documents {
+			let page = output_path(doc, &self.config);
+			for link in &doc.links {
+				let Some(target) = resolve_link_path(&page, link) else {
+					continue;
+				};
+				let found = if target.extension().is_some() {
+					pages.contains(&target)
+				} else {
+					pages.contains(&target.with_extension("html"))
+						|| pages.contains(&target.join("index.html"))
+				};
+				if !found {
+					errors.push(LinkError {
+						source_path: doc.relative_path.clone(),
+						target: link.clone(),
+						context: link_context(&doc.content, link),
+					});
+				}
+			}
+		}
+		errors
+	}
+
+	pub async fn build_incremental(&self, changed_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+		// Removed sources leave stale pages behind, which only a full build cleans up
+		let store = self
+			.documents
+			.as_ref()
+			.filter(|store| !store.read().unwrap().is_empty())
+			.filter(|_| !self.no_html)
+			.filter(|_| self.output_dir.exists() && changed_paths.iter().all(|p| p.exists()));
+		let Some(store) = store else {
+			self.build("html").await?;
+			return Ok(Vec::new());
+		};
+
+		let affected: HashSet<PathBuf> = self
+			.rebuild_documents(changed_paths)?
+			.into_iter()
+			.map(|doc| doc.relative_path)
+			.collect();
+		if affected.is_empty() {
+			return Ok(Vec::new());
+		}
+		let documents = store.read().unwrap();
+
+		// Navigation is cheap, rebuild it regardless
+		let navigation = self.build_navigation(&documents);
+
+		self.update_search_index(&documents, &affected)?;
+		self.publish_search_entries(&documents);
+		self.publish_navigation(&navigation);
+		self.write_api(&documents, &navigation, Some(&affected))?;
+
+		let assets = AssetManifest::load(&self.output_dir);
+		let written = self.render_pages(&documents, &navigation, &assets, Some(&affected))?;
+		self.write_root_redirect()?;
+		Ok(written)
+	}
+
+	// Re-parses only `changed_paths` into the documents of the last build and returns every document
+	// whose page has to be rendered again. Backlinks are redone just for the pages the changed ones
+	// link to, before or after the change. A page appearing, leaving or moving in the sidebar
+	// re-collects everything instead.
+	pub fn rebuild_documents(&self, changed_paths: &[PathBuf]) -> Result<Vec<Document>> {
+		let Some(store) = &self.documents else {
+			bail!("No documents from an earlier build to update");
+		};
+		let mut documents = store.write().unwrap();
+
+		let changed: HashSet<PathBuf> = changed_paths
+			.iter()
+			.filter(|path| is_document_source(path))
+			.filter_map(|path| fs::canonicalize(path).ok())
+			.collect();
+		if changed.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let mut reparsed = Vec::new();
+		for (idx, doc) in documents.iter().enumerate() {
+			if fs::canonicalize(&doc.path).is_ok_and(|path| changed.contains(&path)) {
+				reparsed.push((idx, self.load_document(&doc.path)?));
+			}
+		}
+		if reparsed.len() < changed.len()
+			|| reparsed
+				.iter()
+				.any(|(idx, doc)| !same_outline(&documents[*idx], doc))
+		{
+			*documents = self.process_backlinks(self.collect_documents(None)?);
+			return Ok(documents.clone());
+		}
+
+		// Pages the old links pointed at lose a backlink, the ones the new links point at gain one
+		let mut targets: HashSet<usize> = HashSet::new();
+		let mut affected: HashSet<usize> = HashSet::new();
+		for (idx, mut doc) in reparsed {
+			targets.extend(link_targets(&documents[idx], &documents));
+			doc.backlinks = std::mem::take(&mut documents[idx].backlinks);
+			documents[idx] = doc;
+			affected.insert(idx);
+		}
+		for &idx in &affected {
+			targets.extend(link_targets(&documents[idx], &documents));
+		}
+		affected.extend(refresh_backlinks(&mut documents, &targets));
+
+		let mut affected: Vec<usize> = affected.into_iter().collect();
+		affected.sort_unstable();
+		Ok(affected
+			.into_iter()
+			.map(|idx| documents[idx].clone())
+			.collect())
+	}
+
+	// One source file parsed the way collect_documents does it
+	fn load_document(&self, path: &Path) -> Result<Document> {
+		let mut document = self.parse_source(path, None)?;
+		let dir = path
+			.strip_prefix(&self.source_dir)
+			.unwrap_or(path)
+			.parent()
+			.unwrap_or(Path::new(""));
+		document.effective_config = self.directory_config(dir, &mut HashMap::new())?;
+		document.collections = self.collections.matching(&document.relative_path);
+		Ok(document)
+	}
+
+	fn parse_source(&self, path: &Path, cache: Option<&BuildCache>) -> Result<Document> {
+		let parse = || {
+			self.processor
+				.parse_document(path, &self.source_dir, &self.config, &mut Vec::new())
+		};
+		let mut document = match cache {
+			Some(cache) => {
+				let relative_path = path.strip_prefix(&self.source_dir).unwrap_or(path);
+				cache.document(path, relative_path, parse)
+			}
+			None => parse(),
+		}?;
+		for warning in std::mem::take(&mut document.warnings) {
+			self.log.warn(
+				&warning.code,
+				Some(&document.relative_path),
+				&warning.message,
+			);
+		}
+		// Looked up on every build, a commit changes the date without touching the content
+		if self.config.build.git && document.frontmatter.last_modified.is_none() {
+			document.frontmatter.last_modified = git_last_modified(path);
+		}
+		Ok(document)
+	}
+
+	#[instrument(skip_all)]
+	fn collect_documents(&self, cache: Option<&BuildCache>) -> Result<Vec<Document>> {
+		let mut paths = Vec::new();
 
 		for entry in WalkDir::new(&self.source_dir)
 			.follow_links(true)
@@ -87,38 +809,97 @@ impl Generator {
 			let path = entry.path();
 
 			if path.is_file() {
-				let ext = path.extension().and_then(|s| s.to_str());
-				if matches!(ext, Some("md" | "rst" | "txt" | "adoc")) {
-					match ContentProcessor::parse_document(path, &self.source_dir) {
-						Ok(doc) => documents.push(doc),
-						Err(e) => eprintln!("Warning: Failed to parse {}: {}", path.display(), e),
-					}
+				let relative_path = path.strip_prefix(&self.source_dir).unwrap_or(path);
+				if self.ignore.is_ignored(relative_path) {
+					debug!("Ignoring {}", relative_path.display());
+					continue;
+				}
+				if is_document_source(path) {
+					paths.push(path.to_path_buf());
 				}
 			}
 		}
 
-		// Sort by order if specified
-		documents.sort_by(|a, b| {
-			let a_order = a.frontmatter.order.unwrap_or(999);
-			let b_order = b.frontmatter.order.unwrap_or(999);
-			a_order.cmp(&b_order)
-		});
+		let parse = |path: &PathBuf| self.parse_source(path, cache);
+		let parsed: Vec<_> = if self.use_parallel(paths.len()) {
+			paths.par_iter().map(parse).collect()
+		} else {
+			paths.iter().map(parse).collect()
+		};
+
+		debug!("Parsing {} source files", paths.len());
+		let mut documents = Vec::new();
+		let mut directory_configs = HashMap::new();
+		for (path, result) in paths.iter().zip(parsed) {
+			match result {
+				Ok(doc) if doc.frontmatter.draft == Some(true) && !self.include_drafts => {
+					debug!("Skipping draft {}", doc.relative_path.display());
+				}
+				Ok(mut doc) => {
+					if doc.frontmatter.draft == Some(true)
+						&& self.production && self
+						.config
+						.build
+						.environments
+						.production
+						.noindex_drafts
+					{
+						doc.frontmatter.noindex = Some(true);
+					}
+					let dir = path
+						.strip_prefix(&self.source_dir)
+						.unwrap_or(path)
+						.parent()
+						.unwrap_or(Path::new(""));
+					doc.effective_config = self.directory_config(dir, &mut directory_configs)?;
+					doc.collections = self.collections.matching(&doc.relative_path);
+					documents.push(doc);
+				}
+				Err(e) => self
+					.log
+					.warn("W001", Some(path), &format!("Failed to parse: {}", e)),
+			}
+		}
 
+		sort_documents(&mut documents, &self.config.build.sort_by, &self.nav_order);
 		Ok(documents)
 	}
 
-	fn process_backlinks(&self, mut documents: Vec<Document>) -> Vec<Document> {
-		// Create a map of document titles/paths to their indices
-		let mut doc_map: HashMap<String, usize> = HashMap::new();
-
-		for (idx, doc) in documents.iter().enumerate() {
-			if let Some(title) = &doc.frontmatter.title {
-				doc_map.insert(title.to_lowercase(), idx);
-			}
-			// Also index by path
-			let path_key = doc.relative_path.to_string_lossy().to_lowercase();
-			doc_map.insert(path_key, idx);
+	// A rum.toml in a source subdirectory overrides the config for every page below it.
+	// None when no directory on the way down has one, the page then uses the root config.
+	fn directory_config(
+		&self,
+		dir: &Path,
+		configs: &mut HashMap<PathBuf, Option<Config>>,
+	) -> Result<Option<Config>> {
+		if dir.as_os_str().is_empty() {
+			return Ok(None);
+		}
+		if let Some(config) = configs.get(dir) {
+			return Ok(config.clone());
 		}
+		let inherited = self.directory_config(dir.parent().unwrap_or(Path::new("")), configs)?;
+
+		let file = self.source_dir.join(dir).join("rum.toml");
+		let config = if file.is_file() {
+			let table = fs::read_to_string(&file)?
+				.parse::<toml::Table>()
+				.with_context(|| format!("Invalid config {}", file.display()))?;
+			let base = inherited.as_ref().unwrap_or(&self.config);
+			let config = Config::merge(base, table)
+				.with_context(|| format!("Invalid config {}", file.display()))?;
+			Some(config)
+		} else {
+			inherited
+		};
+		configs.insert(dir.to_path_buf(), config.clone());
+		Ok(config)
+	}
+
+	#[instrument(skip_all)]
+	fn process_backlinks(&self, mut documents: Vec<Document>) -> Vec<Document> {
+		// Pages in one version tend to link the same few targets, each is looked up once
+		let mut resolved: HashMap<(String, Option<String>), Option<usize>> = HashMap::new();
 
 		// Collect backlink updates
 		let mut backlink_updates: Vec<(usize, String)> = Vec::new();
@@ -126,16 +907,13 @@ impl Generator {
 		// Process backlinks
 		for doc in &documents {
 			for link in &doc.links {
-				let link_lower = link.to_lowercase();
-				if let Some(&target_idx) = doc_map.get(&link_lower) {
-					let doc_title = doc
-						.frontmatter
-						.title
-						.as_ref()
-						.map(|t| t.clone())
-						.unwrap_or_else(|| doc.relative_path.to_string_lossy().to_string());
-
-					backlink_updates.push((target_idx, doc_title));
+				// [[Page#section]] links to Page
+				let link = link.split_once('#').map_or(link.as_str(), |(page, _)| page);
+				let target = *resolved
+					.entry((link.to_lowercase(), doc.version.clone()))
+					.or_insert_with(|| resolve_link(link, doc.version.as_deref(), &documents));
+				if let Some(target_idx) = target {
+					backlink_updates.push((target_idx, backlink_title(doc)));
 				}
 			}
 		}
@@ -148,161 +926,1003 @@ impl Generator {
 		documents
 	}
 
+	#[instrument(skip_all)]
 	fn build_navigation(&self, documents: &[Document]) -> NavigationTree {
 		let mut tree = NavigationTree::new();
 
-		for doc in documents {
+		// Hidden documents are still built but left out of the sidebar, as is the 404 page
+		for doc in documents
+			.iter()
+			.filter(|d| d.frontmatter.hidden != Some(true) && !is_not_found_page(d))
+		{
 			let path = &doc.relative_path;
-			let title = doc
-				.frontmatter
-				.title
-				.as_ref()
-				.map(|t| t.clone())
-				.unwrap_or_else(|| {
-					path.file_stem()
-						.and_then(|s| s.to_str())
-						.unwrap_or("Untitled")
-						.to_string()
-				});
+			let title = doc.frontmatter.title.clone().unwrap_or_else(|| {
+				path.file_stem()
+					.and_then(|s| s.to_str())
+					.unwrap_or("Untitled")
+					.to_string()
+			});
+
+			let href = page_href(doc, &self.config);
+			tree.add_path(path, title, doc.version.clone(), href);
+		}
+
+		// Before sections retitle the directory nodes, they are found by directory name
+		for dir in listing_directories(documents).keys() {
+			tree.set_directory_href(
+				dir,
+				format!("/{}/", dir.to_string_lossy().replace('\\', "/")),
+			);
+		}
+
+		if !self.config.navigation.sections.is_empty() {
+			tree.apply_sections(&self.config.navigation.sections);
+		}
+
+		// Collection, tag and author indexes are only written by the HTML build
+		if self.no_html {
+			return tree;
+		}
 
-			tree.add_path(path, title, doc.version.clone());
+		// A collection named after a source directory is already linked from its node
+		for collection in &self.config.collections {
+			let href = format!("/{}/", collection.name);
+			if tree
+				.items
+				.iter()
+				.any(|item| item.href.as_ref() == Some(&href))
+			{
+				continue;
+			}
+			tree.items.push(NavigationItem {
+				title: collection.name.clone(),
+				path: PathBuf::new(),
+				href: Some(href),
+				icon: None,
+				children: Vec::new(),
+				version: None,
+			});
+		}
+
+		if !group_by_tag(documents).is_empty() {
+			tree.items.push(NavigationItem {
+				title: "Tags".to_string(),
+				path: PathBuf::new(),
+				href: Some("/tags/".to_string()),
+				icon: None,
+				children: Vec::new(),
+				version: None,
+			});
+		}
+
+		if !group_by_author(documents).is_empty() {
+			tree.items.push(NavigationItem {
+				title: "Authors".to_string(),
+				path: PathBuf::new(),
+				href: Some("/authors/".to_string()),
+				icon: None,
+				children: Vec::new(),
+				version: None,
+			});
 		}
 
 		tree
 	}
 
-	fn generate_search_index(&self, documents: &[Document]) -> String {
-		use serde_json::json;
+	fn generate_feed(&self, documents: &[Document]) -> Result<()> {
+		let feed = &self.config.feed;
+		if !feed.enabled {
+			return Ok(());
+		}
 
-		let search_docs: Vec<_> = documents
-            .iter()
-            .map(|doc| {
-                json!({
-                    "title": doc.frontmatter.title.as_ref().unwrap_or(&doc.relative_path.to_string_lossy().to_string()),
-                    "content": doc.content,
-                    "path": doc.relative_path.to_string_lossy(),
-                    "version": doc.version,
-                })
-            })
-            .collect();
+		let entries = feed_entries(documents, feed.max_items);
+		let xml = if feed.format == "atom" {
+			self.atom_feed(&entries)
+		} else {
+			self.rss_feed(&entries)
+		};
 
-		serde_json::to_string(&search_docs).unwrap_or_default()
+		let path = self
+			.output_dir
+			.join(feed.output_path.trim_start_matches('/'));
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		fs::write(path, xml)?;
+		Ok(())
 	}
 
-	async fn generate_html(
+	fn rss_feed(&self, entries: &[(&Document, NaiveDate)]) -> String {
+		let site = &self.config.site;
+		let items: Vec<rss::Item> = entries
+			.iter()
+			.map(|(doc, date)| {
+				let link = self.page_url(doc);
+				rss::ItemBuilder::default()
+					.title(Some(feed_title(doc)))
+					.link(Some(link.clone()))
+					.description(doc.frontmatter.description.clone())
+					.author(doc.frontmatter.authors.as_ref().map(|a| a.join(", ")))
+					.guid(Some(
+						rss::GuidBuilder::default()
+							.value(link)
+							.permalink(true)
+							.build(),
+					))
+					.pub_date(Some(midnight_utc(*date).to_rfc2822()))
+					.build()
+			})
+			.collect();
+
+		rss::ChannelBuilder::default()
+			.title(site.title.clone())
+			.link(absolute_url(site.base_url.as_deref(), "/"))
+			.description(site.description.clone())
+			.language(Some(site.language.clone()))
+			.items(items)
+			.build()
+			.to_string()
+	}
+
+	fn atom_feed(&self, entries: &[(&Document, NaiveDate)]) -> String {
+		let site = &self.config.site;
+		let atom_entries: Vec<atom_syndication::Entry> = entries
+			.iter()
+			.map(|(doc, date)| {
+				let link = self.page_url(doc);
+				let authors: Vec<_> = doc
+					.frontmatter
+					.authors
+					.iter()
+					.flatten()
+					.map(|name| {
+						atom_syndication::PersonBuilder::default()
+							.name(name.clone())
+							.build()
+					})
+					.collect();
+				atom_syndication::EntryBuilder::default()
+					.title(feed_title(doc))
+					.id(link.clone())
+					.updated(midnight_utc(*date))
+					.published(Some(midnight_utc(*date)))
+					.authors(authors)
+					.links(vec![atom_syndication::LinkBuilder::default()
+						.href(link)
+						.build()])
+					.summary(
+						doc.frontmatter
+							.description
+							.clone()
+							.map(atom_syndication::Text::plain),
+					)
+					.build()
+			})
+			.collect();
+
+		// The newest entry dates the feed, so unchanged content yields an unchanged feed
+		let updated = entries
+			.first()
+			.map(|(_, date)| midnight_utc(*date))
+			.unwrap_or_else(|| midnight_utc(NaiveDate::default()));
+		let feed_url = absolute_url(site.base_url.as_deref(), &self.config.feed.output_path);
+
+		atom_syndication::FeedBuilder::default()
+			.title(site.title.clone())
+			.id(absolute_url(site.base_url.as_deref(), "/"))
+			.updated(updated)
+			.links(vec![atom_syndication::LinkBuilder::default()
+				.href(feed_url)
+				.rel("self")
+				.build()])
+			.entries(atom_entries)
+			.build()
+			.to_string()
+	}
+
+	fn page_url(&self, doc: &Document) -> String {
+		absolute_url(
+			self.config.site.base_url.as_deref(),
+			&page_href(doc, &self.config),
+		)
+	}
+
+	fn generate_tag_pages(
 		&self,
 		documents: &[Document],
 		navigation: &NavigationTree,
-		search_index: &str,
+		assets: &AssetManifest,
 	) -> Result<()> {
-		// Create output directories
-		fs::create_dir_all(self.output_dir.join("assets"))?;
-		fs::create_dir_all(self.output_dir.join("assets/css"))?;
-		fs::create_dir_all(self.output_dir.join("assets/js"))?;
-
-		// Copy static assets
-		self.copy_assets()?;
+		let tags = group_by_tag(documents);
+		if tags.is_empty() {
+			return Ok(());
+		}
 
-		// Write search index
+		let tags_dir = self.output_dir.join("tags");
+		fs::create_dir_all(&tags_dir)?;
+		let counts: Vec<_> = tags
+			.iter()
+			.map(|(slug, (name, docs))| (slug.as_str(), name.as_str(), docs.len()))
+			.collect();
 		fs::write(
-			self.output_dir.join("assets/search-index.json"),
-			search_index,
+			tags_dir.join("index.html"),
+			self.template_engine
+				.render_tag_index(&counts, navigation, &self.config, assets)?,
 		)?;
 
-		// Group documents by version
-		let mut docs_by_version: HashMap<Option<String>, Vec<&Document>> = HashMap::new();
-		for doc in documents {
-			docs_by_version
-				.entry(doc.version.clone())
-				.or_insert_with(Vec::new)
-				.push(doc);
+		for (slug, (name, docs)) in &tags {
+			let tag_dir = tags_dir.join(slug);
+			fs::create_dir_all(&tag_dir)?;
+			fs::write(
+				tag_dir.join("index.html"),
+				self.template_engine.render_tag_page(
+					slug,
+					name,
+					docs,
+					navigation,
+					&self.config,
+					assets,
+				)?,
+			)?;
 		}
 
-		// Generate pages for each version
-		for (version, docs) in &docs_by_version {
-			let version_path = if let Some(v) = version {
-				self.output_dir.join(v)
-			} else {
-				self.output_dir.clone()
-			};
-			fs::create_dir_all(&version_path)?;
+		Ok(())
+	}
 
-			/*
-			// Generate index page - use a doc named index.md or first doc
-			let index_doc = docs
-				.iter()
-				.find(|d| d.relative_path.file_stem().and_then(|s| s.to_str()) == Some("index"))
-				.or_else(|| docs.first())
-				.ok_or_else(|| anyhow::anyhow!("No documents found"))?;
+	fn generate_author_pages(
+		&self,
+		documents: &[Document],
+		navigation: &NavigationTree,
+		assets: &AssetManifest,
+	) -> Result<()> {
+		let authors = group_by_author(documents);
+		if authors.is_empty() {
+			return Ok(());
+		}
 
-			self.template_engine.render_page(
-				index_doc,
-				docs,
-				navigation,
-				&self.config,
-				&version_path.join("index.html"),
+		let authors_dir = self.output_dir.join("authors");
+		fs::create_dir_all(&authors_dir)?;
+		let counts: Vec<_> = authors
+			.iter()
+			.map(|(slug, (name, docs))| (slug.as_str(), name.as_str(), docs.len()))
+			.collect();
+		fs::write(
+			authors_dir.join("index.html"),
+			self.template_engine
+				.render_author_index(&counts, navigation, &self.config, assets)?,
+		)?;
+
+		for (slug, (name, mut docs)) in authors {
+			// Newest first, undated documents after all dated ones
+			docs.sort_by_key(|doc| Reverse(doc.frontmatter.date));
+			let author_dir = authors_dir.join(&slug);
+			fs::create_dir_all(&author_dir)?;
+			fs::write(
+				author_dir.join("index.html"),
+				self.template_engine.render_author_page(
+					&slug,
+					&name,
+					&docs,
+					navigation,
+					&self.config,
+					assets,
+				)?,
+			)?;
+		}
+
+		Ok(())
+	}
+
+	fn generate_directory_listings(
+		&self,
+		documents: &[Document],
+		navigation: &NavigationTree,
+		assets: &AssetManifest,
+	) -> Result<()> {
+		let page_size = self.config.pagination.page_size;
+		for (dir, docs) in listing_directories(documents) {
+			// A collection of the same name takes the directory's index
+			if self
+				.config
+				.collections
+				.iter()
+				.any(|c| Path::new(&c.name) == dir)
+			{
+				continue;
+			}
+			let total_pages = docs.len().div_ceil(page_size);
+			for (idx, items) in docs.chunks(page_size).enumerate() {
+				let page = idx + 1;
+				let path = if page == 1 {
+					self.output_dir.join(&dir).join("index.html")
+				} else {
+					self.output_dir
+						.join(&dir)
+						.join("page")
+						.join(page.to_string())
+						.join("index.html")
+				};
+				let listing = Listing {
+					dir: &dir,
+					items,
+					page,
+					total_pages,
+					template: None,
+				};
+				let html = self.template_engine.render_list_page(
+					&listing,
+					navigation,
+					&self.config,
+					assets,
+				)?;
+				if let Some(parent) = path.parent() {
+					fs::create_dir_all(parent)?;
+				}
+				fs::write(path, html)?;
+			}
+		}
+		Ok(())
+	}
+
+	// Each collection lists its documents at /{name}/, in its own sort order
+	fn generate_collection_pages(
+		&self,
+		documents: &[Document],
+		navigation: &NavigationTree,
+		assets: &AssetManifest,
+	) -> Result<()> {
+		for collection in &self.config.collections {
+			let dir = Path::new(&collection.name);
+			let index = dir.join("index.html");
+			if let Some(doc) = documents
+				.iter()
+				.find(|d| output_path(d, &self.config) == index)
+			{
+				self.log.warn(
+					"W007",
+					Some(&doc.path),
+					&format!(
+						"Collection '{}' would replace this page at /{}, skipping its index",
+						collection.name,
+						index.display()
+					),
+				);
+				continue;
+			}
+			if let Some(template) = &collection.template {
+				if !self.template_engine.has_template(template) {
+					bail!(
+						"Collection '{}' uses template '{}', which is not in the template directory",
+						collection.name,
+						template
+					);
+				}
+			}
+
+			let mut items: Vec<&Document> = documents
+				.iter()
+				.filter(|d| d.frontmatter.hidden != Some(true) && !is_not_found_page(d))
+				.filter(|d| d.collections.contains(&collection.name))
+				.collect();
+			sort_documents(&mut items, &collection.sort_by, &self.nav_order);
+			let listing = Listing {
+				dir,
+				items: &items,
+				page: 1,
+				total_pages: 1,
+				template: collection.template.as_deref(),
+			};
+			let html = self.template_engine.render_list_page(
+				&listing,
+				navigation,
+				&self.config,
+				assets,
+			)?;
+			fs::create_dir_all(self.output_dir.join(dir))?;
+			fs::write(self.output_dir.join(index), html)?;
+		}
+		Ok(())
+	}
+
+	#[instrument(skip_all)]
+	fn generate_search_index(&self, documents: &[Document]) -> Result<String> {
+		// Reuse the cached index when no document changed since the last build. The hash is
+		// written after the index, so a matching hash always comes with a complete index.
+		let hash_path = self.cache_dir.join("search-index-hash");
+		let index_path = self.cache_dir.join("search-index.json");
+		let fields = self.index_fields();
+		let corpus_hash = Self::corpus_hash(documents, &fields);
+
+		if fs::read_to_string(&hash_path).is_ok_and(|hash| hash == corpus_hash) {
+			if let Ok(search_index) = fs::read_to_string(&index_path) {
+				return Ok(search_index);
+			}
+		}
+
+		let search_docs: Vec<_> = documents
+			.iter()
+			.map(|doc| Self::search_entry(doc, &fields))
+			.collect();
+		let search_index = serde_json::to_string(&search_docs).unwrap_or_default();
+
+		fs::create_dir_all(&self.cache_dir)?;
+		write_atomic(&index_path, &search_index)?;
+		write_atomic(&hash_path, &corpus_hash)?;
+
+		Ok(search_index)
+	}
+
+	// Configured index fields, minus the ones search entries cannot be built from
+	fn index_fields(&self) -> Vec<String> {
+		let mut fields = Vec::new();
+		for field in &self.config.search.index_fields {
+			let known = matches!(
+				field.as_str(),
+				"title"
+					| "content" | "path"
+					| "version" | "tags"
+					| "author" | "authors"
+					| "description"
+			) || field
+				.strip_prefix("extra.")
+				.is_some_and(|key| !key.is_empty());
+
+			if known {
+				fields.push(field.clone());
+			} else {
+				self.log.warn(
+					"W006",
+					None,
+					&format!("Unknown search index field '{}', skipping", field),
+				);
+			}
+		}
+		fields
+	}
+
+	fn search_entry(doc: &Document, fields: &[String]) -> serde_json::Value {
+		let frontmatter = &doc.frontmatter;
+		let mut entry = serde_json::Map::new();
+
+		for field in fields {
+			let value = match field.as_str() {
+				"title" => serde_json::json!(frontmatter
+					.title
+					.clone()
+					.unwrap_or_else(|| doc.relative_path.to_string_lossy().to_string())),
+				"content" => serde_json::json!(doc.content),
+				"path" => serde_json::json!(doc.relative_path.to_string_lossy()),
+				"version" => serde_json::json!(doc.version),
+				"tags" => serde_json::json!(frontmatter.tags),
+				"author" | "authors" => serde_json::json!(frontmatter.authors),
+				"description" => serde_json::json!(frontmatter.description),
+				extra => {
+					let key = extra.trim_start_matches("extra.");
+					serde_json::to_value(frontmatter.extra.get(key)).unwrap_or_default()
+				}
+			};
+			entry.insert(field.clone(), value);
+		}
+
+		serde_json::Value::Object(entry)
+	}
+
+	fn publish_navigation(&self, navigation: &NavigationTree) {
+		if let Some(shared) = &self.navigation {
+			*shared.write().unwrap() = navigation.clone();
+		}
+	}
+
+	// The dev server's /api/navigation and /api/documents as files, so a client-side frontend works
+	// against the static output too. Only the given documents are rewritten when there is a set.
+	fn write_api(
+		&self,
+		documents: &[Document],
+		navigation: &NavigationTree,
+		only: Option<&HashSet<PathBuf>>,
+	) -> Result<()> {
+		let api_dir = self.output_dir.join("api");
+		fs::create_dir_all(api_dir.join("documents"))?;
+		fs::write(
+			api_dir.join("navigation.json"),
+			serde_json::to_string(navigation)?,
+		)?;
+		let index: Vec<DocumentEntry> = documents.iter().map(DocumentEntry::new).collect();
+		fs::write(
+			api_dir.join("documents.json"),
+			serde_json::to_string(&index)?,
+		)?;
+		for (doc, entry) in documents.iter().zip(&index) {
+			if only.is_some_and(|only| !only.contains(&doc.relative_path)) {
+				continue;
+			}
+			fs::write(
+				api_dir
+					.join("documents")
+					.join(format!("{}.json", entry.slug)),
+				serde_json::to_string(doc)?,
+			)?;
+		}
+		Ok(())
+	}
+
+	fn publish_search_entries(&self, documents: &[Document]) {
+		if let Some(search_entries) = &self.search_entries {
+			*search_entries.write().unwrap() = documents.iter().map(SearchEntry::new).collect();
+		}
+	}
+
+	fn update_search_index(
+		&self,
+		documents: &[Document],
+		affected: &HashSet<PathBuf>,
+	) -> Result<()> {
+		let index_path = self.output_dir.join("assets/search-index.json");
+		let fields = self.index_fields();
+
+		// Entries can only be matched up by path, without it the whole index is rebuilt
+		let entries: Vec<serde_json::Value> = if fields.iter().any(|f| f == "path") {
+			let mut entries: Vec<serde_json::Value> = fs::read_to_string(&index_path)
+				.ok()
+				.and_then(|index| serde_json::from_str(&index).ok())
+				.unwrap_or_default();
+
+			// Replace only the entries of the affected documents
+			entries.retain(|entry| {
+				let path = entry["path"].as_str().unwrap_or_default();
+				!affected.contains(Path::new(path))
+			});
+			entries.extend(
+				documents
+					.iter()
+					.filter(|doc| affected.contains(&doc.relative_path))
+					.map(|doc| Self::search_entry(doc, &fields)),
+			);
+			entries
+		} else {
+			documents
+				.iter()
+				.map(|doc| Self::search_entry(doc, &fields))
+				.collect()
+		};
+
+		fs::write(index_path, serde_json::to_string(&entries)?)?;
+		Ok(())
+	}
+
+	fn corpus_hash(documents: &[Document], fields: &[String]) -> String {
+		// Hash of the sorted (path, entry hash) pairs, independent of document order
+		let mut entries: Vec<String> = documents
+			.iter()
+			.map(|doc| {
+				let mut hasher = Sha256::new();
+				hasher.update(Self::search_entry(doc, fields).to_string());
+				format!(
+					"{}{:x}",
+					doc.relative_path.to_string_lossy(),
+					hasher.finalize()
+				)
+			})
+			.collect();
+		entries.sort();
+
+		let mut hasher = Sha256::new();
+		for entry in &entries {
+			hasher.update(entry);
+		}
+		format!("{:x}", hasher.finalize())
+	}
+
+	// --no-html, the parsed corpus as data for other tools to render
+	fn generate_content(
+		&self,
+		documents: &[Document],
+		navigation: &NavigationTree,
+		search_index: &str,
+	) -> Result<()> {
+		fs::create_dir_all(self.output_dir.join("assets"))?;
+		fs::write(
+			self.output_dir.join("assets/search-index.json"),
+			search_index,
+		)?;
+		fs::write(
+			self.output_dir.join("content.json"),
+			serde_json::to_string(documents)?,
+		)?;
+		fs::write(
+			self.output_dir.join("navigation.json"),
+			serde_json::to_string(navigation)?,
+		)?;
+		Ok(())
+	}
+
+	async fn generate_html(
+		&self,
+		documents: &[Document],
+		navigation: &NavigationTree,
+		search_index: &str,
+		stats: &mut BuildStats,
+	) -> Result<()> {
+		// Create output directories
+		fs::create_dir_all(self.output_dir.join("assets"))?;
+		fs::create_dir_all(self.output_dir.join("assets/css"))?;
+		fs::create_dir_all(self.output_dir.join("assets/js"))?;
+
+		// Copy static assets
+		let phase = Instant::now();
+		let assets = self.copy_assets()?;
+		stats.record("asset_copy", phase);
+		let phase = Instant::now();
+
+		// Write search index
+		fs::write(
+			self.output_dir.join("assets/search-index.json"),
+			search_index,
+		)?;
+
+		// Write navigation for client-side rendering
+		fs::write(
+			self.output_dir.join("assets/navigation.json"),
+			serde_json::to_string(navigation)?,
+		)?;
+		self.write_api(documents, navigation, None)?;
+
+		let mut extra_pages = i18n_fallbacks(documents, &self.config);
+		extra_pages.extend(version_alias_pages(documents, &self.config));
+		let written = if extra_pages.is_empty() {
+			self.render_pages(documents, navigation, &assets, None)?
+		} else {
+			let pages: Vec<Document> = documents.iter().cloned().chain(extra_pages).collect();
+			self.render_pages(&pages, navigation, &assets, None)?
+		};
+		stats.html_pages_written = written.len();
+		self.write_root_redirect()?;
+		if !documents.iter().any(is_not_found_page) {
+			fs::write(
+				self.output_dir.join("404.html"),
+				self.template_engine
+					.render_not_found(navigation, &self.config, &assets)?,
+			)?;
+		}
+		self.generate_tag_pages(documents, navigation, &assets)?;
+		self.generate_author_pages(documents, navigation, &assets)?;
+		self.generate_directory_listings(documents, navigation, &assets)?;
+		self.generate_collection_pages(documents, navigation, &assets)?;
+		self.generate_feed(documents)?;
+		generate_sitemap(documents, &self.config, &self.output_dir)?;
+		stats.record("html_generation", phase);
+
+		Ok(())
+	}
+
+	// A versioned site's index.html only points at the newest version, even over a root index.md
+	fn write_root_redirect(&self) -> Result<()> {
+		let Some(latest) = self.config.site.root_redirect() else {
+			return Ok(());
+		};
+		let target = format!("{}/index.html", latest);
+		fs::write(
+			self.output_dir.join("index.html"),
+			format!(
+				"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta http-equiv=\"refresh\" content=\"0; url={}\">\n</head>\n</html>\n",
+				escape_html(&target)
+			),
+		)?;
+		Ok(())
+	}
+
+	#[instrument(skip_all)]
+	fn render_pages(
+		&self,
+		documents: &[Document],
+		navigation: &NavigationTree,
+		assets: &AssetManifest,
+		only: Option<&HashSet<PathBuf>>,
+	) -> Result<Vec<PathBuf>> {
+		let mut pages = Vec::new();
+
+		// Group documents by version
+		let mut docs_by_version: HashMap<Option<String>, Vec<&Document>> = HashMap::new();
+		for doc in documents {
+			docs_by_version
+				.entry(doc.version.clone())
+				.or_default()
+				.push(doc);
+		}
+
+		// Unversioned first, then by version name, so pages are always written in the same order
+		let mut docs_by_version: Vec<_> = docs_by_version.into_iter().collect();
+		docs_by_version.sort_by(|a, b| a.0.cmp(&b.0));
+
+		// Generate pages for each version
+		for (_, docs) in &docs_by_version {
+			/*
+			// Generate index page - use a doc named index.md or first doc
+			let index_doc = docs
+				.iter()
+				.find(|d| d.relative_path.file_stem().and_then(|s| s.to_str()) == Some("index"))
+				.or_else(|| docs.first())
+				.ok_or_else(|| anyhow::anyhow!("No documents found"))?;
+
+			self.template_engine.render_page(
+				index_doc,
+				docs,
+				navigation,
+				&self.config,
+				&version_path.join("index.html"),
 			)?;*/
 
+			// Previous/next links follow the navigation, so hidden pages and the 404 page are skipped
+			let sequence: Vec<&Document> = docs
+				.iter()
+				.copied()
+				.filter(|d| d.frontmatter.hidden != Some(true) && !is_not_found_page(d))
+				.collect();
+
 			// Generate individual pages
 			for doc in docs {
-				/*
-				// Skip index.md as we already generated it
-				if doc.relative_path.file_stem().and_then(|s| s.to_str()) == Some("index") {
+				if only.is_some_and(|only| !only.contains(&doc.relative_path)) {
 					continue;
 				}
-				*/
+				let (prev, next) = neighbours(&sequence, doc);
+				pages.push((*doc, docs.as_slice(), prev, next));
+			}
+		}
 
-				let stripped_path = if let Some(v) = version {
-					doc.relative_path
-						.strip_prefix(v)
-						.unwrap_or(&doc.relative_path)
-				} else {
-					&doc.relative_path
-				};
+		// Static hosts serve 404.html for missing paths, so the first 404 source is written there
+		let not_found = documents
+			.iter()
+			.find(|d| is_not_found_page(d))
+			.map(|d| &d.relative_path);
 
-				/* 				let html_path = version_path.join(doc.relative_path.with_extension("html")); */
-				let html_path = version_path.join(stripped_path.with_extension("html"));
+		type Page<'a> = (
+			&'a Document,
+			&'a [&'a Document],
+			Option<&'a Document>,
+			Option<&'a Document>,
+		);
+		let render = |&(doc, docs, prev, next): &Page<'_>| -> Result<PathBuf> {
+			let html_path = if not_found == Some(&doc.relative_path) {
+				self.output_dir.join("404.html")
+			} else {
+				self.output_dir.join(output_path(doc, &self.config))
+			};
 
-				// Create parent directories
-				if let Some(parent) = html_path.parent() {
-					fs::create_dir_all(parent)?;
-				}
+			// Create parent directories
+			if let Some(parent) = html_path.parent() {
+				fs::create_dir_all(parent)?;
+			}
 
+			// Images are copied to the same place under the output root, wherever the page lands
+			let rewritten;
+			let is_draft = doc.frontmatter.draft == Some(true);
+			let doc = if doc.images.is_empty() && !is_draft {
+				doc
+			} else {
+				let mut html_content = rewrite_image_sources(doc);
+				if is_draft {
+					html_content.insert_str(0, DRAFT_BANNER);
+				}
+				rewritten = Document {
+					html_content,
+					..doc.clone()
+				};
+				&rewritten
+			};
+			let page = PageContext {
+				doc,
+				prev,
+				next,
+				all_docs: docs,
+				navigation,
+				config: &self.config,
+				assets,
+				print: false,
+			};
+			self.template_engine.render_page(&page, &html_path)?;
+			if self.print && not_found != Some(&doc.relative_path) {
 				self.template_engine.render_page(
-					doc,
-					docs,
-					navigation,
-					&self.config,
-					&html_path,
+					&PageContext {
+						print: true,
+						..page
+					},
+					&self.output_dir.join(print_path(doc)),
 				)?;
 			}
+			self.copy_images(doc)?;
+			Ok(html_path)
+		};
+
+		if self.use_parallel(pages.len()) {
+			pages.par_iter().map(render).collect()
+		} else {
+			pages.iter().map(render).collect()
 		}
+	}
 
+	fn copy_images(&self, doc: &Document) -> Result<()> {
+		for image in &doc.images {
+			let source = self.source_dir.join(image);
+			if !source.is_file() {
+				self.log.warn(
+					"W004",
+					Some(&doc.relative_path),
+					&format!("Image {} not found, skipping", image.display()),
+				);
+				continue;
+			}
+			let target = self.output_dir.join(image);
+			if let Some(parent) = target.parent() {
+				fs::create_dir_all(parent)?;
+			}
+			fs::copy(&source, &target)?;
+		}
 		Ok(())
 	}
 
-	fn copy_assets(&self) -> Result<()> {
+	#[instrument(skip_all)]
+	fn copy_assets(&self) -> Result<AssetManifest> {
+		let fingerprint = |name: &str, bytes: &str| {
+			if self.config.assets.fingerprint {
+				fingerprinted_name(name, bytes.as_bytes())
+			} else {
+				name.to_string()
+			}
+		};
+
 		// Copy CSS
 		let css = include_str!("../templates/assets/style.css");
-		fs::write(self.output_dir.join("assets/css/style.css"), css)?;
+		let css_dir = self.output_dir.join("assets/css");
+		let css_name;
+		if self.config.build.minify {
+			let minified = minify::minify_css(css);
+			let mut output = minified.css.clone();
+			css_name = fingerprint("style.css", &output);
+			if self.config.build.source_maps {
+				let map_name = format!("{}.map", css_name);
+				let source_map = minified.source_map(&css_name, "templates/assets/style.css", css);
+				fs::write(css_dir.join(&map_name), source_map)?;
+				output.push_str(&format!("\n/*# sourceMappingURL={} */", map_name));
+			}
+			fs::write(css_dir.join(&css_name), output)?;
+		} else {
+			css_name = fingerprint("style.css", css);
+			fs::write(css_dir.join(&css_name), css)?;
+		}
+
+		// Syntax themes, picked by media query or the runtime theme toggle
+		for (variant, theme) in [
+			("light", &self.config.syntax.theme_light),
+			("dark", &self.config.syntax.theme_dark),
+		] {
+			fs::write(
+				css_dir.join(format!("syntax-{}.css", variant)),
+				syntax_theme_css(theme)?,
+			)?;
+		}
+
+		// syntax.theme alone, for templates that link a single stylesheet
+		if self.config.syntax.enabled {
+			fs::write(css_dir.join("syntax.css"), self.processor.syntax_css())?;
+		}
+
+		fs::write(
+			css_dir.join("print.css"),
+			include_str!("../templates/assets/print.css"),
+		)?;
 
 		// Copy JS
 		let js = include_str!("../templates/assets/app.js");
-		fs::write(self.output_dir.join("assets/js/app.js"), js)?;
+		let js_name = fingerprint("app.js", js);
+		fs::write(self.output_dir.join("assets/js").join(&js_name), js)?;
 
-		Ok(())
+		// User stylesheets and scripts from [assets], a missing one should not sink the build
+		for (paths, dir) in [
+			(&self.config.assets.extra_css, "assets/css"),
+			(&self.config.assets.extra_js, "assets/js"),
+		] {
+			for path in paths {
+				let name = extra_asset_name(path);
+				let path = Path::new(path);
+				let Some(name) = name.filter(|_| path.is_file()) else {
+					self.log
+						.warn("W005", Some(path), "Extra asset not found, skipping");
+					continue;
+				};
+				fs::copy(path, self.output_dir.join(dir).join(name))?;
+			}
+		}
+
+		let manifest = AssetManifest {
+			css: css_name,
+			js: js_name,
+		};
+		fs::write(
+			self.output_dir.join("assets/manifest.json"),
+			serde_json::to_string_pretty(&manifest)?,
+		)?;
+		Ok(manifest)
+	}
+}
+
+// Entries of /api/documents, enough to list pages and fetch one by slug
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentEntry {
+	pub title: String,
+	pub slug: String,
+	pub path: String,
+	pub version: Option<String>,
+	pub tags: Vec<String>,
+	pub date: Option<NaiveDate>,
+}
+
+impl DocumentEntry {
+	pub fn new(doc: &Document) -> Self {
+		let path = doc.relative_path.to_string_lossy().replace('\\', "/");
+		Self {
+			title: doc
+				.frontmatter
+				.title
+				.clone()
+				.unwrap_or_else(|| path.clone()),
+			slug: document_slug(doc),
+			path,
+			version: doc.version.clone(),
+			tags: doc.frontmatter.tags.clone().unwrap_or_default(),
+			date: doc.frontmatter.date,
+		}
+	}
+}
+
+// Fixed fields of a document for server-side search, search.index_fields only shapes the static index
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchEntry {
+	pub title: String,
+	pub content: String,
+	pub path: String,
+	pub version: Option<String>,
+}
+
+impl SearchEntry {
+	fn new(doc: &Document) -> Self {
+		let path = doc.relative_path.to_string_lossy().replace('\\', "/");
+		Self {
+			title: doc
+				.frontmatter
+				.title
+				.clone()
+				.unwrap_or_else(|| path.clone()),
+			content: doc.content.clone(),
+			path,
+			version: doc.version.clone(),
+		}
+	}
+
+	// Occurrences of each lowercase term, title matches count three times
+	pub fn score(&self, terms: &[String]) -> usize {
+		let title = self.title.to_lowercase();
+		let content = self.content.to_lowercase();
+		terms
+			.iter()
+			.filter(|term| !term.is_empty())
+			.map(|term| {
+				title.matches(term.as_str()).count() * 3 + content.matches(term.as_str()).count()
+			})
+			.sum()
 	}
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NavigationTree {
 	pub items: Vec<NavigationItem>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NavigationItem {
 	pub title: String,
 	pub path: PathBuf,
+	pub href: Option<String>,
+	pub icon: Option<String>,
 	pub children: Vec<NavigationItem>,
 	pub version: Option<String>,
 }
@@ -312,7 +1932,7 @@ impl NavigationTree {
 		Self { items: Vec::new() }
 	}
 
-	pub fn add_path(&mut self, path: &Path, title: String, version: Option<String>) {
+	pub fn add_path(&mut self, path: &Path, title: String, version: Option<String>, href: String) {
 		let components: Vec<_> = path.components().collect();
 		let mut current = &mut self.items;
 
@@ -324,6 +1944,8 @@ impl NavigationTree {
 				current.push(NavigationItem {
 					title: title.clone(),
 					path: path.to_path_buf(),
+					href: Some(href.clone()),
+					icon: None,
 					children: Vec::new(),
 					version: version.clone(),
 				});
@@ -339,6 +1961,8 @@ impl NavigationTree {
 					let new_item = NavigationItem {
 						title: name.clone(),
 						path: PathBuf::new(),
+						href: None,
+						icon: None,
 						children: Vec::new(),
 						version: None,
 					};
@@ -349,10 +1973,2232 @@ impl NavigationTree {
 			}
 		}
 	}
-}
 
-impl Default for NavigationTree {
-	fn default() -> Self {
-		Self::new()
+	// Links the directory node at dir, if there is one, to its generated listing
+	pub fn set_directory_href(&mut self, dir: &Path, href: String) {
+		let mut items = &mut self.items;
+		let mut components = dir.components().peekable();
+		while let Some(component) = components.next() {
+			let name = component.as_os_str().to_string_lossy();
+			let Some(item) = items
+				.iter_mut()
+				.find(|item| item.title == name && item.path.as_os_str().is_empty())
+			else {
+				return;
+			};
+			if components.peek().is_none() {
+				item.href = Some(href);
+				return;
+			}
+			items = &mut item.children;
+		}
+	}
+
+	// Pages in navigation order, depth-first with each directory's pages before the next sibling
+	pub fn flatten(&self) -> Vec<&NavigationItem> {
+		self.flatten_all()
+			.into_iter()
+			.filter(|item| !item.path.as_os_str().is_empty())
+			.collect()
+	}
+
+	// Same traversal, directory nodes included ahead of their children
+	pub fn flatten_all(&self) -> Vec<&NavigationItem> {
+		fn visit<'a>(items: &'a [NavigationItem], out: &mut Vec<&'a NavigationItem>) {
+			for item in items {
+				out.push(item);
+				visit(&item.children, out);
+			}
+		}
+
+		let mut items = Vec::new();
+		visit(&self.items, &mut items);
+		items
+	}
+
+	// Pinned sections come first in their configured order, other directories follow alphabetically
+	pub fn apply_sections(&mut self, sections: &[SectionConfig]) {
+		// Directory nodes are still titled by their directory name at this point
+		let section_for = |item: &NavigationItem| {
+			if item.path.as_os_str().is_empty() {
+				sections.iter().find(|s| s.dir == item.title)
+			} else {
+				None
+			}
+		};
+
+		// Top-level pages keep their place ahead of the directories
+		self.items.sort_by_cached_key(|item| {
+			if !item.path.as_os_str().is_empty() {
+				return (0, 0, String::new());
+			}
+			match section_for(item) {
+				Some(section) => (1, section.order, String::new()),
+				None => (2, 0, item.title.to_lowercase()),
+			}
+		});
+
+		for item in self.items.iter_mut() {
+			if let Some(section) = section_for(item) {
+				item.title = section.title.clone();
+				item.icon = section.icon.clone();
+			}
+		}
+	}
+}
+
+// Source directories with pages of their own but no index page, and those pages in build order
+fn listing_directories(documents: &[Document]) -> BTreeMap<PathBuf, Vec<&Document>> {
+	// Hidden index pages still take the directory's index.html
+	let indexed: HashSet<&Path> = documents
+		.iter()
+		.filter(|d| {
+			d.relative_path
+				.file_stem()
+				.is_some_and(|stem| stem == "index")
+		})
+		.filter_map(|d| d.relative_path.parent())
+		.collect();
+
+	let mut directories: BTreeMap<PathBuf, Vec<&Document>> = BTreeMap::new();
+	for doc in documents
+		.iter()
+		.filter(|d| d.frontmatter.hidden != Some(true) && !is_not_found_page(d))
+	{
+		match doc.relative_path.parent() {
+			Some(dir) if !dir.as_os_str().is_empty() && !indexed.contains(dir) => {
+				directories.entry(dir.to_path_buf()).or_default().push(doc);
+			}
+			_ => {}
+		}
+	}
+	directories
+}
+
+// Lists every rendered page in the output directory except those built from drafts
+pub fn generate_sitemap(documents: &[Document], config: &Config, output_dir: &Path) -> Result<()> {
+	let sitemap = &config.sitemap;
+	if !sitemap.enabled {
+		return Ok(());
+	}
+	let Some(base_url) = sitemap.base_url.as_deref() else {
+		bail!("sitemap.base_url is required when the sitemap is enabled");
+	};
+
+	// Pages kept out of search engines, whether drafts or marked noindex, and the print copies
+	// that duplicate every page
+	let excluded: HashSet<PathBuf> = documents
+		.iter()
+		.filter(|d| d.frontmatter.draft == Some(true) || d.frontmatter.noindex == Some(true))
+		.map(|d| output_path(d, config))
+		.chain(documents.iter().map(print_path))
+		.collect();
+
+	let mut xml = String::from(
+		"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+	);
+	for entry in WalkDir::new(output_dir)
+		.sort_by_file_name()
+		.into_iter()
+		.filter_map(|e| e.ok())
+		.filter(|e| e.file_type().is_file())
+	{
+		let path = entry.path();
+		if path.extension().and_then(|e| e.to_str()) != Some("html") {
+			continue;
+		}
+		let relative = path.strip_prefix(output_dir).unwrap_or(path);
+		if excluded.contains(relative)
+			|| BuildCache::is_cache_path(relative)
+			|| relative == Path::new("404.html")
+		{
+			continue;
+		}
+
+		let href = relative.to_string_lossy().replace('\\', "/");
+		xml.push_str(&format!(
+			"  <url>\n    <loc>{}</loc>\n    <changefreq>{}</changefreq>\n    <priority>{:.1}</priority>\n  </url>\n",
+			escape_html(&absolute_url(Some(base_url), &href)),
+			escape_html(&sitemap.changefreq),
+			sitemap.priority
+		));
+	}
+	xml.push_str("</urlset>\n");
+
+	if sitemap.compress {
+		let mut encoder = GzEncoder::new(
+			fs::File::create(output_dir.join("sitemap.xml.gz"))?,
+			Compression::default(),
+		);
+		encoder.write_all(xml.as_bytes())?;
+		encoder.finish()?;
+	} else {
+		fs::write(output_dir.join("sitemap.xml"), xml)?;
+	}
+	Ok(())
+}
+
+fn is_not_found_page(doc: &Document) -> bool {
+	let path = &doc.relative_path;
+	path.file_stem().is_some_and(|stem| stem == "404")
+		&& matches!(
+			path.extension().and_then(|e| e.to_str()),
+			Some("md" | "adoc" | "rst")
+		)
+}
+
+fn git_last_modified(path: &Path) -> Option<DateTime<Utc>> {
+	let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+		return None;
+	};
+	let output = Command::new("git")
+		.args(["log", "-1", "--format=%cI", "--"])
+		.arg(name)
+		.current_dir(dir)
+		.output();
+	let date = parse_git_log(output);
+	if date.is_none() {
+		debug!("No git history for {}", path.display());
+	}
+	date
+}
+
+// Empty output means the file is untracked or the repository has no commits yet
+fn parse_git_log(output: io::Result<Output>) -> Option<DateTime<Utc>> {
+	let output = match output {
+		Ok(output) if output.status.success() => output,
+		Ok(output) => {
+			debug!(
+				"git log failed: {}",
+				String::from_utf8_lossy(&output.stderr).trim()
+			);
+			return None;
+		}
+		Err(e) => {
+			debug!("Failed to run git: {}", e);
+			return None;
+		}
+	};
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	DateTime::parse_from_rfc3339(stdout.trim())
+		.ok()
+		.map(|date| date.with_timezone(&Utc))
+}
+
+fn neighbours<'a>(
+	sequence: &[&'a Document],
+	doc: &Document,
+) -> (Option<&'a Document>, Option<&'a Document>) {
+	match sequence.iter().position(|d| std::ptr::eq(*d, doc)) {
+		Some(idx) => (
+			idx.checked_sub(1).map(|prev| sequence[prev]),
+			sequence.get(idx + 1).copied(),
+		),
+		None => (None, None),
+	}
+}
+
+// Drafts only reach the output with --drafts, and then say so at the top of the page
+const DRAFT_BANNER: &str = "<div class=\"draft-banner\">⚠ Draft – not for publication</div>\n";
+
+// Pages missing from a locale are rendered there from the default locale, under a banner saying so
+fn i18n_fallbacks(documents: &[Document], config: &Config) -> Vec<Document> {
+	let default_locale = &config.i18n.default_locale;
+	let mut fallbacks = Vec::new();
+	for locale in config.i18n.locales.iter().filter(|l| *l != default_locale) {
+		let translated: HashSet<&Path> = documents
+			.iter()
+			.filter(|d| document_locale(d, config) == locale)
+			.map(translation_key)
+			.collect();
+		for doc in documents {
+			let key = translation_key(doc);
+			if document_locale(doc, config) != default_locale
+				|| translated.contains(key)
+				|| is_not_found_page(doc)
+			{
+				continue;
+			}
+			let banner = format!(
+				"<div class=\"i18n-fallback-banner\">This page is not available in {} yet, showing the {} version.</div>\n",
+				escape_html(locale),
+				escape_html(default_locale)
+			);
+			fallbacks.push(Document {
+				relative_path: Path::new(locale).join(key),
+				locale: Some(locale.clone()),
+				// Image sources are made absolute while they still resolve against the original path
+				html_content: banner + &rewrite_image_sources(doc),
+				..doc.clone()
+			});
+		}
+	}
+	fallbacks
+}
+
+// Pages under an alias directory such as latest/ are also written under the version it points to
+fn version_alias_pages(documents: &[Document], config: &Config) -> Vec<Document> {
+	let aliases = &config.site.version_aliases;
+	if aliases.is_empty() {
+		return Vec::new();
+	}
+	let existing: HashSet<&Path> = documents
+		.iter()
+		.map(|d| d.relative_path.as_path())
+		.collect();
+	let mut pages = Vec::new();
+	for doc in documents {
+		let Some((alias, target)) = doc
+			.version
+			.as_ref()
+			.and_then(|version| aliases.get_key_value(version))
+		else {
+			continue;
+		};
+		// The version directory sits below the locale one, if any
+		let mut replaced = false;
+		let relative_path: PathBuf = doc
+			.relative_path
+			.components()
+			.map(|c| {
+				if !replaced && c.as_os_str() == alias.as_str() {
+					replaced = true;
+					Path::new(target).as_os_str()
+				} else {
+					c.as_os_str()
+				}
+			})
+			.collect();
+		if existing.contains(relative_path.as_path()) {
+			continue;
+		}
+		pages.push(Document {
+			relative_path,
+			version: Some(target.clone()),
+			html_content: rewrite_image_sources(doc),
+			..doc.clone()
+		});
+	}
+	pages
+}
+
+fn rewrite_image_sources(doc: &Document) -> String {
+	IMG_SRC_REGEX
+		.replace_all(
+			&doc.html_content,
+			|caps: &regex::Captures| match resolve_image(&doc.relative_path, &caps[2]) {
+				Some(image) if doc.images.contains(&image) => format!(
+					"{}/{}{}",
+					&caps[1],
+					image.to_string_lossy().replace('\\', "/"),
+					&caps[3]
+				),
+				_ => caps[0].to_string(),
+			},
+		)
+		.to_string()
+}
+
+// Output path a page link points at, None for links that are not pages such as images
+fn resolve_link_path(page: &Path, link: &str) -> Option<PathBuf> {
+	if !is_internal_link(link) {
+		return None;
+	}
+	let path = link.split(['#', '?']).next().unwrap_or_default();
+	let path = path.split_whitespace().next()?;
+	let extension = Path::new(path).extension().and_then(|e| e.to_str());
+	if !matches!(extension, None | Some("html" | "htm" | "md")) {
+		return None;
+	}
+
+	let mut resolved = match path.strip_prefix('/') {
+		Some(_) => PathBuf::new(),
+		None => page.parent().map(Path::to_path_buf).unwrap_or_default(),
+	};
+	for component in Path::new(path).components() {
+		match component {
+			Component::Normal(part) => resolved.push(part),
+			Component::ParentDir => {
+				resolved.pop();
+			}
+			_ => {}
+		}
+	}
+	if path.ends_with('/') {
+		resolved.push("index.html");
+	}
+	Some(resolved)
+}
+
+// The document a title, wiki link or path refers to. Several versions usually share a title, so the
+// source's own version wins, then "latest", then unversioned pages, then the newest other version.
+fn resolve_link(link: &str, source_version: Option<&str>, documents: &[Document]) -> Option<usize> {
+	let link = link.to_lowercase();
+	documents
+		.iter()
+		.enumerate()
+		.filter(|(_, doc)| link_names(&link, doc))
+		.min_by_key(|(_, doc)| match doc.version.as_deref() {
+			version if version == source_version => (0, None),
+			Some("latest") => (1, None),
+			None => (2, None),
+			// Unparsable versions sort after every semantic one
+			Some(version) => (3, Some(Reverse(parse_version(version)))),
+		})
+		.map(|(idx, _)| idx)
+}
+
+// Whether a lowercased link names the document, in any version
+fn link_names(link: &str, doc: &Document) -> bool {
+	doc.relative_path.to_string_lossy().to_lowercase() == link
+		|| doc.frontmatter.title.as_ref().is_some_and(|title| {
+			// Wiki links arrive already converted to their page-name.html form
+			title.to_lowercase() == link || format!("{}.html", wiki_link_slug(title)) == link
+		})
+}
+
+// How a page is named in the backlinks of the pages it links to
+fn backlink_title(doc: &Document) -> String {
+	doc.frontmatter
+		.title
+		.clone()
+		.unwrap_or_else(|| doc.relative_path.to_string_lossy().to_string())
+}
+
+fn link_targets(doc: &Document, documents: &[Document]) -> Vec<usize> {
+	doc.links
+		.iter()
+		.filter_map(|link| {
+			let link = link.split_once('#').map_or(link.as_str(), |(page, _)| page);
+			resolve_link(link, doc.version.as_deref(), documents)
+		})
+		.collect()
+}
+
+// Backlinks of `targets` collected again from every page's links, in the order process_backlinks
+// adds them. Returns the targets whose backlinks changed.
+fn refresh_backlinks(documents: &mut [Document], targets: &HashSet<usize>) -> Vec<usize> {
+	let mut backlinks: HashMap<usize, Vec<String>> =
+		targets.iter().map(|&idx| (idx, Vec::new())).collect();
+	for doc in documents.iter() {
+		for link in &doc.links {
+			let link = link.split_once('#').map_or(link.as_str(), |(page, _)| page);
+			// Most links name none of the targets, which is much cheaper to rule out than to resolve
+			let lowercase = link.to_lowercase();
+			if !targets
+				.iter()
+				.any(|&idx| link_names(&lowercase, &documents[idx]))
+			{
+				continue;
+			}
+			if let Some(target) = resolve_link(link, doc.version.as_deref(), documents) {
+				if let Some(backlinks) = backlinks.get_mut(&target) {
+					backlinks.push(backlink_title(doc));
+				}
+			}
+		}
+	}
+
+	let mut changed = Vec::new();
+	for (idx, backlinks) in backlinks {
+		if documents[idx].backlinks != backlinks {
+			documents[idx].backlinks = backlinks;
+			changed.push(idx);
+		}
+	}
+	changed
+}
+
+// Anything that places a page in the sidebar or the page order, or decides whether it is built
+fn same_outline(a: &Document, b: &Document) -> bool {
+	a.frontmatter.title == b.frontmatter.title
+		&& a.frontmatter.order == b.frontmatter.order
+		&& a.frontmatter.hidden == b.frontmatter.hidden
+		&& a.frontmatter.draft == b.frontmatter.draft
+		&& a.frontmatter.date == b.frontmatter.date
+		&& a.version == b.version
+		&& a.locale == b.locale
+}
+
+fn is_document_source(path: &Path) -> bool {
+	let ext = path.extension().and_then(|s| s.to_str());
+	matches!(ext, Some("md" | "rst" | "txt" | "adoc" | "ipynb"))
+}
+
+// The source line a link appears on, for pointing at it in warnings
+fn link_context(content: &str, link: &str) -> String {
+	content
+		.lines()
+		.find(|line| line.contains(link))
+		.unwrap_or(link)
+		.trim()
+		.to_string()
+}
+
+// Dated documents, newest first, documents without a date are left out of the feed
+// Stable, so documents that tie keep the order they were collected in
+fn sort_documents<D: Borrow<Document>>(documents: &mut [D], sort_by: &str, nav_order: &NavOrder) {
+	match sort_by {
+		// Newest first, undated documents after all dated ones
+		"date" => documents.sort_by_key(|doc| Reverse(doc.borrow().frontmatter.date)),
+		"title" => documents.sort_by_cached_key(|doc| {
+			let doc = doc.borrow();
+			doc.frontmatter
+				.title
+				.clone()
+				.or_else(|| {
+					doc.relative_path
+						.file_stem()
+						.map(|stem| stem.to_string_lossy().to_string())
+				})
+				.unwrap_or_default()
+				.to_lowercase()
+		}),
+		"filename" => {
+			documents.sort_by(|a, b| a.borrow().relative_path.cmp(&b.borrow().relative_path))
+		}
+		// By frontmatter order, then [navigation.order] patterns
+		_ => documents.sort_by(|a, b| {
+			resolve_nav_order(a.borrow(), nav_order)
+				.total_cmp(&resolve_nav_order(b.borrow(), nav_order))
+		}),
+	}
+}
+
+fn feed_entries(documents: &[Document], max_items: usize) -> Vec<(&Document, NaiveDate)> {
+	let mut entries: Vec<_> = documents
+		.iter()
+		.filter(|d| d.frontmatter.hidden != Some(true) && d.frontmatter.draft != Some(true))
+		.filter_map(|d| d.frontmatter.date.map(|date| (d, date)))
+		.collect();
+	entries.sort_by_key(|e| Reverse(e.1));
+	entries.truncate(max_items);
+	entries
+}
+
+fn feed_title(doc: &Document) -> String {
+	doc.frontmatter
+		.title
+		.clone()
+		.unwrap_or_else(|| doc.relative_path.to_string_lossy().to_string())
+}
+
+fn midnight_utc(date: NaiveDate) -> DateTime<FixedOffset> {
+	date.and_time(NaiveTime::MIN).and_utc().fixed_offset()
+}
+
+// Documents by tag slug, tags spelled differently but sharing a slug are merged under the first spelling
+fn group_by_tag(documents: &[Document]) -> BTreeMap<String, (String, Vec<&Document>)> {
+	group_by_name(documents, |frontmatter| frontmatter.tags.as_deref())
+}
+
+fn group_by_author(documents: &[Document]) -> BTreeMap<String, (String, Vec<&Document>)> {
+	group_by_name(documents, |frontmatter| frontmatter.authors.as_deref())
+}
+
+// Slug to display name and documents, names that slugify to the same thing share a page
+fn group_by_name(
+	documents: &[Document],
+	names: impl Fn(&Frontmatter) -> Option<&[String]>,
+) -> BTreeMap<String, (String, Vec<&Document>)> {
+	let mut groups: BTreeMap<String, (String, Vec<&Document>)> = BTreeMap::new();
+	for doc in documents
+		.iter()
+		.filter(|d| d.frontmatter.hidden != Some(true))
+	{
+		for name in names(&doc.frontmatter).into_iter().flatten() {
+			let slug = slugify(name);
+			if slug.is_empty() {
+				continue;
+			}
+			let (_, docs) = groups
+				.entry(slug)
+				.or_insert_with(|| (name.trim().to_string(), Vec::new()));
+			if !docs.iter().any(|d| std::ptr::eq(*d, doc)) {
+				docs.push(doc);
+			}
+		}
+	}
+	groups
+}
+
+// Location of a document's --print copy relative to the output directory
+pub fn print_path(doc: &Document) -> PathBuf {
+	Path::new("print").join(format!("{}.html", document_slug(doc)))
+}
+
+// Output location of a document relative to the output directory
+pub fn output_path(doc: &Document, config: &Config) -> PathBuf {
+	let relative_path = &doc.relative_path;
+	if !config.urls.pattern.is_empty() {
+		UrlResolver::resolve(doc, &config.urls.pattern)
+	} else if config.output.structure == "flat" {
+		let name = relative_path
+			.with_extension("")
+			.components()
+			.map(|c| c.as_os_str().to_string_lossy().to_string())
+			.collect::<Vec<_>>()
+			.join("-");
+		PathBuf::from(format!("{}.html", name))
+	} else {
+		relative_path.with_extension("html")
+	}
+}
+
+// --format takes a comma-separated list, e.g. html,json
+fn parse_formats(formats: &str) -> Result<Vec<&str>> {
+	let mut parsed = Vec::new();
+	for format in formats.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+		if !FORMATS.contains(&format) {
+			bail!(
+				"Unknown format '{}', expected one of: {}",
+				format,
+				FORMATS.join(", ")
+			);
+		}
+		parsed.push(format);
+	}
+	if parsed.is_empty() {
+		bail!("No output format given");
+	}
+	Ok(parsed)
+}
+
+fn page_href(doc: &Document, config: &Config) -> String {
+	let href = output_path(doc, config)
+		.to_string_lossy()
+		.replace('\\', "/");
+	format!("/{}", href)
+}
+
+impl Default for NavigationTree {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::{CollectionConfig, LintConfig};
+	use crate::templates::BASE_TEMPLATE;
+	use std::io::Read;
+
+	#[tokio::test]
+	async fn test_builds_are_reproducible() {
+		let root = std::env::temp_dir().join(format!("rum-reproducible-{}", std::process::id()));
+		let source = root.join("docs");
+		for (path, content) in [
+			(
+				"index.md",
+				"---\ntitle: Home\n---\n# Home\n\nSee [[Install]].\n",
+			),
+			(
+				"latest/install.md",
+				"---\ntitle: Install\ntags: [setup]\n---\n## Steps\n",
+			),
+			("v1/install.md", "---\ntitle: Install v1\n---\n## Steps\n"),
+			(
+				"latest/guide/usage.md",
+				"# Usage\n\n```rust\nfn main() {}\n```\n",
+			),
+		] {
+			let path = source.join(path);
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(path, content).unwrap();
+		}
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let mut outputs = Vec::new();
+		for name in ["a", "b"] {
+			let output = root.join(name);
+			Generator::new(source.clone(), output.clone(), Some(config.clone()))
+				.unwrap()
+				.with_parallel(true)
+				.build("html")
+				.await
+				.unwrap();
+
+			let files: Vec<_> = WalkDir::new(&output)
+				.sort_by_file_name()
+				.into_iter()
+				.filter_map(|e| e.ok())
+				.filter(|e| e.file_type().is_file())
+				.map(|e| {
+					let relative = e.path().strip_prefix(&output).unwrap().to_path_buf();
+					(relative, fs::read(e.path()).unwrap())
+				})
+				.collect();
+			outputs.push(files);
+		}
+		fs::remove_dir_all(&root).ok();
+
+		assert!(!outputs[0].is_empty());
+		assert!(outputs[0] == outputs[1]);
+	}
+
+	#[tokio::test]
+	async fn test_incremental_build_reuses_documents() {
+		let root = std::env::temp_dir().join(format!("rum-incremental-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("guide")).unwrap();
+		fs::write(source.join("index.md"), "# Home\n").unwrap();
+		fs::write(source.join("guide/setup.md"), "# Setup\n").unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator = Generator::new(source.clone(), output.clone(), Some(config.clone()))
+			.unwrap()
+			.with_incremental(true);
+		let first = generator.build("html").await.unwrap();
+		let second = generator.build("html").await.unwrap();
+		assert!(output.join("index.html").exists());
+		assert_eq!(first.stats.documents_from_cache, 0);
+		assert_eq!(second.stats.documents_from_cache, 2);
+
+		// The second build left a manifest matching every source
+		let cache = BuildCache::load(&output, &generator.config, BASE_TEMPLATE).unwrap();
+		generator.collect_documents(Some(&cache)).unwrap();
+		assert_eq!(cache.parsed(), 0);
+
+		fs::write(source.join("guide/setup.md"), "# Set up\n").unwrap();
+		let cache = BuildCache::load(&output, &generator.config, BASE_TEMPLATE).unwrap();
+		let documents = generator.collect_documents(Some(&cache)).unwrap();
+		assert_eq!(cache.parsed(), 1);
+		assert!(documents.iter().any(|d| d.content.contains("Set up")));
+
+		// Any config change invalidates everything
+		let mut config = generator.config.clone();
+		config.site.title = "Changed".to_string();
+		let cache = BuildCache::load(&output, &config, BASE_TEMPLATE).unwrap();
+		generator.collect_documents(Some(&cache)).unwrap();
+		assert_eq!(cache.parsed(), 2);
+
+		// And so does a template change
+		let cache = BuildCache::load(&output, &generator.config, "<html></html>").unwrap();
+		generator.collect_documents(Some(&cache)).unwrap();
+		assert_eq!(cache.parsed(), 2);
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_warning_limits() {
+		let root = std::env::temp_dir().join(format!("rum-warnings-{}", std::process::id()));
+		let source = root.join("docs");
+		fs::create_dir_all(&source).unwrap();
+		fs::write(
+			source.join("index.md"),
+			"---\ntitle: Home\n---\nSee [setup](setup.md).\n",
+		)
+		.unwrap();
+		let config = root.join("rum.toml");
+
+		let summary = |lint: LintConfig| {
+			Config {
+				lint,
+				..Default::default()
+			}
+			.save(&config)
+			.unwrap();
+			let generator =
+				Generator::new(source.clone(), root.join("dist"), Some(config.clone())).unwrap();
+			async move { generator.build("html").await.unwrap() }
+		};
+
+		// The build itself succeeds so its summary can be shown before failing
+		let strict = summary(LintConfig {
+			warnings_as_errors: true,
+			..Default::default()
+		})
+		.await;
+		assert_eq!(strict.warnings, 1);
+		assert!(strict.warnings_as_errors);
+		let error = strict.check_warnings().unwrap_err().to_string();
+		assert!(error.contains("warnings_as_errors"), "{}", error);
+
+		let limited = summary(LintConfig {
+			max_warnings: Some(0),
+			..Default::default()
+		})
+		.await;
+		assert_eq!(limited.max_warnings, Some(0));
+		let error = limited.check_warnings().unwrap_err().to_string();
+		assert!(error.contains("(1/0)"), "{}", error);
+
+		let allowed = summary(LintConfig {
+			max_warnings: Some(1),
+			..Default::default()
+		})
+		.await;
+		assert!(allowed.check_warnings().is_ok());
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_search_index_cache() {
+		let root = std::env::temp_dir().join(format!("rum-search-cache-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(&source).unwrap();
+		fs::write(source.join("index.md"), "---\ntitle: Home\n---\nHello\n").unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		// Plain builds wipe the output directory, the cache lives next to rum.toml instead
+		let generator = Generator::new(source.clone(), output.clone(), Some(config)).unwrap();
+		generator.build("html").await.unwrap();
+		let cache_path = root.join(".rum-cache/search-index.json");
+		assert!(root.join(".rum-cache/search-index-hash").exists());
+		assert_eq!(
+			fs::read_to_string(output.join("assets/search-index.json")).unwrap(),
+			fs::read_to_string(&cache_path).unwrap()
+		);
+		assert!(!output.join(".rum-cache").exists());
+
+		// An unchanged corpus takes the index from the cache as it is
+		fs::write(&cache_path, "[\"cached\"]").unwrap();
+		generator.build("html").await.unwrap();
+		let search = fs::read_to_string(output.join("assets/search-index.json")).unwrap();
+		assert_eq!(search, "[\"cached\"]");
+
+		fs::write(source.join("index.md"), "---\ntitle: Home\n---\nChanged\n").unwrap();
+		generator.build("html").await.unwrap();
+		let search = fs::read_to_string(output.join("assets/search-index.json")).unwrap();
+		assert!(search.contains("Changed"));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_rebuild_documents() {
+		let root = std::env::temp_dir().join(format!("rum-rebuild-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(&source).unwrap();
+		let page = |title: &str, body: &str| format!("---\ntitle: {}\n---\n{}\n", title, body);
+		fs::write(source.join("a.md"), page("A", "See [[B]].")).unwrap();
+		fs::write(source.join("b.md"), page("B", "Nothing.")).unwrap();
+		fs::write(source.join("c.md"), page("C", "Nothing.")).unwrap();
+		fs::write(source.join("d.md"), page("D", "See [[C]].")).unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let documents = Arc::new(RwLock::new(Vec::new()));
+		let generator = Generator::new(source.clone(), output.clone(), Some(config))
+			.unwrap()
+			.with_documents(Arc::clone(&documents));
+		generator.build("html").await.unwrap();
+		assert_eq!(documents.read().unwrap().len(), 4);
+
+		// A now links C instead of B, D is left alone
+		fs::write(source.join("a.md"), page("A", "See [[C]] and [[C]].")).unwrap();
+		let mut written = generator
+			.build_incremental(&[source.join("a.md")])
+			.await
+			.unwrap();
+		written.sort();
+		assert_eq!(
+			written,
+			[
+				output.join("a.html"),
+				output.join("b.html"),
+				output.join("c.html")
+			]
+		);
+		let backlinks = |title: &str| {
+			let documents = documents.read().unwrap();
+			let doc = documents
+				.iter()
+				.find(|d| d.frontmatter.title.as_deref() == Some(title))
+				.unwrap();
+			doc.backlinks.clone()
+		};
+		assert!(backlinks("B").is_empty());
+		let mut linking_c = backlinks("C");
+		linking_c.sort();
+		assert_eq!(linking_c, ["A", "A", "D"]);
+
+		// The same order as a full build gives
+		let full = generator.process_backlinks(generator.collect_documents(None).unwrap());
+		for doc in full {
+			assert_eq!(
+				backlinks(doc.frontmatter.title.as_deref().unwrap()),
+				doc.backlinks
+			);
+		}
+
+		// A new title moves the page in the sidebar, so every page is rendered again
+		fs::write(source.join("b.md"), page("Bee", "Nothing.")).unwrap();
+		let written = generator
+			.build_incremental(&[source.join("b.md")])
+			.await
+			.unwrap();
+		assert_eq!(written.len(), 4);
+		assert!(fs::read_to_string(output.join("d.html"))
+			.unwrap()
+			.contains("Bee"));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_print_pages() {
+		let root = std::env::temp_dir().join(format!("rum-print-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("guide")).unwrap();
+		fs::write(source.join("index.md"), "# Home\n").unwrap();
+		fs::write(source.join("guide/setup.md"), "# Setup\n\nInstall it.\n").unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator = Generator::new(source, output.clone(), Some(config))
+			.unwrap()
+			.with_print(true);
+		generator.build("html").await.unwrap();
+
+		let page = fs::read_to_string(output.join("guide/setup.html")).unwrap();
+		assert!(page.contains("<nav class=\"sidebar\">"));
+		assert!(page
+			.contains("<link rel=\"stylesheet\" href=\"/assets/css/print.css\" media=\"print\">"));
+		let print = fs::read_to_string(output.join("print/guide-setup.html")).unwrap();
+		assert!(print.contains("Install it."));
+		assert!(!print.contains("class=\"sidebar\""));
+		assert!(output.join("print/index.html").exists());
+		assert!(fs::read_to_string(output.join("assets/css/print.css"))
+			.unwrap()
+			.contains("@media print"));
+
+		assert!(generator.with_no_html(true).build("html").await.is_err());
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_no_html() {
+		let root = std::env::temp_dir().join(format!("rum-no-html-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("guide")).unwrap();
+		fs::write(source.join("index.md"), "# Home\n\nSee [[Setup]].\n").unwrap();
+		fs::write(
+			source.join("guide/setup.md"),
+			"---\ntitle: Setup\ntags: [start]\n---\nInstall it.\n",
+		)
+		.unwrap();
+		fs::write(source.join("guide/usage.md"), "# Usage\n").unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator = Generator::new(source, output.clone(), Some(config))
+			.unwrap()
+			.with_no_html(true);
+		let summary = generator.build("html").await.unwrap();
+		assert_eq!(summary.stats.html_pages_written, 0);
+		assert!(!output.join("index.html").exists());
+		assert!(output.join("assets/search-index.json").exists());
+
+		let content: Vec<Document> =
+			serde_json::from_str(&fs::read_to_string(output.join("content.json")).unwrap())
+				.unwrap();
+		assert_eq!(content.len(), 3);
+		let setup = content
+			.iter()
+			.find(|d| d.frontmatter.title.as_deref() == Some("Setup"))
+			.unwrap();
+		assert!(setup.html_content.contains("Install it."));
+		assert_eq!(setup.backlinks.len(), 1);
+
+		let navigation: serde_json::Value =
+			serde_json::from_str(&fs::read_to_string(output.join("navigation.json")).unwrap())
+				.unwrap();
+		let items = navigation["items"].as_array().unwrap();
+		// index.md and the guide directory, which holds the other two
+		assert_eq!(items.len(), 2);
+		let guide = items.iter().find(|item| item["title"] == "guide").unwrap();
+		assert_eq!(guide["children"].as_array().unwrap().len(), 2);
+
+		assert!(generator.build("html,pdf").await.is_err());
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_build_stats() {
+		let root = std::env::temp_dir().join(format!("rum-stats-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(&source).unwrap();
+		fs::write(source.join("index.md"), "# Home\n\nSee [[Guide]].\n").unwrap();
+		fs::write(
+			source.join("guide.md"),
+			"---\ntitle: Guide\n---\nNo links here.\n",
+		)
+		.unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator = Generator::new(source, output, Some(config)).unwrap();
+		let stats = generator.build("html").await.unwrap().stats;
+		assert_eq!(stats.documents_collected, 2);
+		assert_eq!(stats.documents_from_cache, 0);
+		assert_eq!(stats.backlinks_processed, 1);
+		assert_eq!(stats.html_pages_written, 2);
+		assert_eq!(stats.errors, 0);
+		assert_eq!(
+			stats.phases.keys().collect::<Vec<_>>(),
+			[
+				"collect",
+				"backlinks",
+				"navigation",
+				"search_index",
+				"asset_copy",
+				"html_generation"
+			]
+		);
+		assert!(stats.phases.values().all(|duration| !duration.is_zero()));
+
+		let json: serde_json::Value = serde_json::to_value(&stats).unwrap();
+		assert!(json["phases"]["collect"].as_f64().unwrap() > 0.0);
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_broken_links() {
+		let root = std::env::temp_dir().join(format!("rum-links-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("guide")).unwrap();
+		fs::write(
+			source.join("index.md"),
+			"# Home\n\nStart with [[Setup]] or the [guide](guide/).\n\nThen read [[Missing Page]].\n",
+		)
+		.unwrap();
+		fs::write(source.join("setup.md"), "# Setup\n").unwrap();
+		fs::write(
+			source.join("guide/index.md"),
+			"# Guide\n\n[Back](../index.html#top), [logo](../logo.png), [old](../install.html)\n",
+		)
+		.unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator =
+			Generator::new(source.clone(), output.clone(), Some(config.clone())).unwrap();
+		let documents = generator.collect_documents(None).unwrap();
+		let mut errors: Vec<_> = generator
+			.check_links(&documents)
+			.into_iter()
+			.map(|e| (e.source_path, e.target, e.context))
+			.collect();
+		errors.sort();
+		assert_eq!(
+			errors,
+			vec![
+				(
+					PathBuf::from("guide/index.md"),
+					"../install.html".to_string(),
+					"[Back](../index.html#top), [logo](../logo.png), [old](../install.html)"
+						.to_string()
+				),
+				(
+					PathBuf::from("index.md"),
+					"missing-page.html".to_string(),
+					"Then read [Missing Page](missing-page.html).".to_string()
+				),
+			]
+		);
+
+		// Broken links only warn unless the build is strict
+		generator.build("html").await.unwrap();
+		assert_eq!(generator.log().warning_count(), 2);
+		let strict = Generator::new(source, output, Some(config))
+			.unwrap()
+			.with_strict(true);
+		let error = strict.build("html").await.unwrap_err();
+		assert!(error.to_string().contains("2 broken internal link(s)"));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_syntax_themes() {
+		let root = std::env::temp_dir().join(format!("rum-syntax-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(&source).unwrap();
+		fs::write(source.join("index.md"), "```rust\nfn main() {}\n```\n").unwrap();
+		let config = root.join("rum.toml");
+		let mut settings = Config::default();
+		settings.syntax.theme_light = "Solarized (light)".to_string();
+		settings.save(&config).unwrap();
+
+		let generator =
+			Generator::new(source.clone(), output.clone(), Some(config.clone())).unwrap();
+		generator.build("html").await.unwrap();
+		let light = fs::read_to_string(output.join("assets/css/syntax-light.css")).unwrap();
+		let dark = fs::read_to_string(output.join("assets/css/syntax-dark.css")).unwrap();
+		assert_eq!(light, syntax_theme_css("Solarized (light)").unwrap());
+		assert_eq!(dark, syntax_theme_css("base16-ocean.dark").unwrap());
+		assert!(light.contains(".code {") && dark.contains(".code {"));
+		assert_ne!(light, dark);
+		let page = fs::read_to_string(output.join("index.html")).unwrap();
+		assert!(page.contains("<pre class=\"code\">"));
+		assert!(page.contains("id=\"syntax-dark\" media=\"(prefers-color-scheme: dark)\""));
+
+		settings.syntax.theme_dark = "prism-tomorrow".to_string();
+		settings.save(&config).unwrap();
+		assert!(Generator::new(source, output, Some(config)).is_err());
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_extra_assets() {
+		let root = std::env::temp_dir().join(format!("rum-assets-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(&source).unwrap();
+		fs::write(source.join("index.md"), "# Home\n").unwrap();
+		fs::write(root.join("brand.css"), "body { color: teal; }").unwrap();
+		fs::write(root.join("analytics.js"), "console.log(1);").unwrap();
+
+		let mut config = Config::default();
+		config.assets.extra_css = vec![
+			root.join("brand.css").to_string_lossy().to_string(),
+			root.join("missing.css").to_string_lossy().to_string(),
+		];
+		config.assets.extra_js = vec![root.join("analytics.js").to_string_lossy().to_string()];
+		let config_path = root.join("rum.toml");
+		config.save(&config_path).unwrap();
+
+		let generator = Generator::new(source, output.clone(), Some(config_path)).unwrap();
+		generator.build("html").await.unwrap();
+		assert_eq!(generator.log().warning_count(), 1);
+		assert_eq!(
+			fs::read_to_string(output.join("assets/css/brand.css")).unwrap(),
+			"body { color: teal; }"
+		);
+		assert!(output.join("assets/js/analytics.js").exists());
+
+		let page = fs::read_to_string(output.join("index.html")).unwrap();
+		assert!(page.contains("<link rel=\"stylesheet\" href=\"/assets/css/brand.css\">"));
+		assert!(page.contains("<script src=\"/assets/js/analytics.js\"></script>"));
+		assert!(!page.contains("{{ extra_"));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_asset_fingerprints() {
+		let root = std::env::temp_dir().join(format!("rum-fingerprint-{}", std::process::id()));
+		let source = root.join("docs");
+		fs::create_dir_all(&source).unwrap();
+		fs::write(source.join("index.md"), "# Home\n").unwrap();
+
+		let build = |minify: bool| {
+			let output = root.join(if minify { "minified" } else { "plain" });
+			let config_path = root.join("rum.toml");
+			let mut config = Config::default();
+			config.assets.fingerprint = true;
+			config.build.minify = minify;
+			config.save(&config_path).unwrap();
+			let generator = Generator::new(source.clone(), output.clone(), Some(config_path));
+			(generator.unwrap(), output)
+		};
+
+		let (generator, output) = build(false);
+		generator.build("html").await.unwrap();
+		let manifest = AssetManifest::load(&output);
+		assert_ne!(manifest, AssetManifest::default());
+		assert!(manifest.css.starts_with("style.") && manifest.css.ends_with(".css"));
+		assert_eq!(manifest.css.len(), "style.12345678.css".len());
+		assert!(output.join("assets/css").join(&manifest.css).exists());
+		assert!(output.join("assets/js").join(&manifest.js).exists());
+		assert!(!output.join("assets/css/style.css").exists());
+		let page = fs::read_to_string(output.join("index.html")).unwrap();
+		assert!(page.contains(&format!("href=\"/assets/css/{}\"", manifest.css)));
+		assert!(page.contains(&format!("src=\"/assets/js/{}\"", manifest.js)));
+
+		// Minifying changes the stylesheet bytes but not the script's
+		let (generator, output) = build(true);
+		generator.build("html").await.unwrap();
+		let minified = AssetManifest::load(&output);
+		assert_ne!(minified.css, manifest.css);
+		assert_eq!(minified.js, manifest.js);
+
+		assert_eq!(
+			fingerprinted_name("app.js", b"one"),
+			fingerprinted_name("app.js", b"one")
+		);
+		assert_ne!(
+			fingerprinted_name("app.js", b"one"),
+			fingerprinted_name("app.js", b"two")
+		);
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_drafts() {
+		let root = std::env::temp_dir().join(format!("rum-drafts-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(&source).unwrap();
+		fs::write(source.join("index.md"), "# Home\n").unwrap();
+		fs::write(
+			source.join("wip.md"),
+			"---\ntitle: Work in progress\ndraft: true\n---\nNot ready.\n",
+		)
+		.unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator =
+			Generator::new(source.clone(), output.clone(), Some(config.clone())).unwrap();
+		generator.build("html").await.unwrap();
+		assert!(output.join("index.html").exists());
+		assert!(!output.join("wip.html").exists());
+		let index = fs::read_to_string(output.join("index.html")).unwrap();
+		assert!(!index.contains("Work in progress"));
+		let search = fs::read_to_string(output.join("assets/search-index.json")).unwrap();
+		assert!(!search.contains("Not ready"));
+
+		fs::remove_dir_all(&output).ok();
+		let generator = Generator::new(source.clone(), output.clone(), Some(config.clone()))
+			.unwrap()
+			.with_include_drafts(true);
+		generator.build("html").await.unwrap();
+		let page = fs::read_to_string(output.join("wip.html")).unwrap();
+		assert!(page.contains("<div class=\"draft-banner\">⚠ Draft – not for publication</div>"));
+		let index = fs::read_to_string(output.join("index.html")).unwrap();
+		assert!(!index.contains("draft-banner"));
+		assert!(!page.contains("name=\"robots\""));
+
+		let mut production = Config::default();
+		production.build.environments.production.noindex_drafts = true;
+		production.save(&config).unwrap();
+		let generator = Generator::new(source.clone(), output.clone(), Some(config.clone()))
+			.unwrap()
+			.with_include_drafts(true);
+		generator.build("html").await.unwrap();
+		let page = fs::read_to_string(output.join("wip.html")).unwrap();
+		assert!(!page.contains("name=\"robots\""));
+
+		let generator = Generator::new(source, output.clone(), Some(config))
+			.unwrap()
+			.with_include_drafts(true)
+			.with_production(true);
+		generator.build("html").await.unwrap();
+		let page = fs::read_to_string(output.join("wip.html")).unwrap();
+		assert!(page.contains("<meta name=\"robots\" content=\"noindex, nofollow\">"));
+		let index = fs::read_to_string(output.join("index.html")).unwrap();
+		assert!(!index.contains("name=\"robots\""));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_directory_config() {
+		let root = std::env::temp_dir().join(format!("rum-dirconfig-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("api/v2")).unwrap();
+		fs::write(source.join("index.md"), "---\ntitle: Home\n---\n# Home\n").unwrap();
+		fs::write(source.join("api/index.md"), "---\ntitle: API\n---\n# API\n").unwrap();
+		fs::write(
+			source.join("api/v2/index.md"),
+			"---\ntitle: Version 2\n---\n# Version 2\n",
+		)
+		.unwrap();
+		fs::write(
+			source.join("api/rum.toml"),
+			"[site]\ntitle = \"API Reference\"\n",
+		)
+		.unwrap();
+		fs::write(
+			source.join("api/v2/rum.toml"),
+			"[navigation]\nbreadcrumbs = false\n",
+		)
+		.unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator = Generator::new(source.clone(), output.clone(), Some(config)).unwrap();
+		generator.build("html").await.unwrap();
+		let home = fs::read_to_string(output.join("index.html")).unwrap();
+		assert!(home.contains("<title>Home - Rum</title>"));
+		let api = fs::read_to_string(output.join("api/index.html")).unwrap();
+		assert!(api.contains("<title>API - API Reference</title>"));
+		assert!(api.contains("class=\"breadcrumbs\""));
+		// Nested overrides build on the parent directory's, not the root config
+		let v2 = fs::read_to_string(output.join("api/v2/index.html")).unwrap();
+		assert!(v2.contains("<title>Version 2 - API Reference</title>"));
+		assert!(!v2.contains("class=\"breadcrumbs\""));
+
+		fs::write(source.join("api/rum.toml"), "[site\n").unwrap();
+		assert!(generator.build("html").await.is_err());
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_author_pages() {
+		let root = std::env::temp_dir().join(format!("rum-authors-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(&source).unwrap();
+		fs::write(
+			source.join("old.md"),
+			"---\ntitle: Old\nauthor: Ada Lovelace\ndate: 2023-01-01\n---\n# Old\n",
+		)
+		.unwrap();
+		fs::write(
+			source.join("new.md"),
+			"---\ntitle: New\nauthors: [Ada Lovelace, Grace Hopper]\ndate: 2024-01-01\n---\n# New\n",
+		)
+		.unwrap();
+		fs::write(source.join("anonymous.md"), "# Anonymous\n").unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator = Generator::new(source, output.clone(), Some(config)).unwrap();
+		generator.build("html").await.unwrap();
+
+		let index = fs::read_to_string(output.join("authors/index.html")).unwrap();
+		assert!(index.contains(
+			"<a href=\"/authors/ada-lovelace/\">Ada Lovelace</a> <span class=\"author-count\">(2)</span>"
+		));
+		assert!(index.contains("<a href=\"/authors/grace-hopper/\">Grace Hopper</a>"));
+		let ada = fs::read_to_string(output.join("authors/ada-lovelace/index.html")).unwrap();
+		assert!(ada.find(">New</a>").unwrap() < ada.find(">Old</a>").unwrap());
+		assert!(ada.contains("<a href=\"/authors/\""));
+
+		let page = fs::read_to_string(output.join("new.html")).unwrap();
+		assert!(page.contains("By Ada Lovelace, Grace Hopper"));
+		let page = fs::read_to_string(output.join("anonymous.html")).unwrap();
+		assert!(!page.contains("document-authors"));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_root_redirect() {
+		let root = std::env::temp_dir().join(format!("rum-redirect-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("v1.0.0")).unwrap();
+		fs::create_dir_all(source.join("v2.0.0")).unwrap();
+		fs::write(source.join("index.md"), "---\ntitle: Home\n---\n# Home\n").unwrap();
+		fs::write(source.join("v1.0.0/index.md"), "# One\n").unwrap();
+		fs::write(source.join("v2.0.0/index.md"), "# Two\n").unwrap();
+		let config = root.join("rum.toml");
+		let mut settings = Config::default();
+		settings.site.versions = vec!["v1.0.0".to_string(), "v2.0.0".to_string()];
+		settings.save(&config).unwrap();
+
+		let generator =
+			Generator::new(source.clone(), output.clone(), Some(config.clone())).unwrap();
+		generator.build("html").await.unwrap();
+		let index = fs::read_to_string(output.join("index.html")).unwrap();
+		assert!(
+			index.contains("<meta http-equiv=\"refresh\" content=\"0; url=v2.0.0/index.html\">")
+		);
+		assert!(!index.contains("Home"));
+		assert!(output.join("v2.0.0/index.html").exists());
+
+		settings.site.redirect_root = false;
+		settings.save(&config).unwrap();
+		let generator = Generator::new(source, output.clone(), Some(config)).unwrap();
+		generator.build("html").await.unwrap();
+		let index = fs::read_to_string(output.join("index.html")).unwrap();
+		assert!(index.contains("<title>Home - Rum</title>"));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_collections() {
+		let root = std::env::temp_dir().join(format!("rum-collections-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		let templates = root.join("templates");
+		fs::create_dir_all(source.join("blog")).unwrap();
+		fs::create_dir_all(source.join("guides")).unwrap();
+		fs::create_dir_all(&templates).unwrap();
+		fs::write(
+			source.join("blog/launch.md"),
+			"---\ntitle: Launch\ndate: 2024-03-01\norder: 2\n---\n# Launch\n",
+		)
+		.unwrap();
+		fs::write(
+			source.join("blog/intro.md"),
+			"---\ntitle: Intro\ndate: 2023-01-01\norder: 1\n---\n# Intro\n",
+		)
+		.unwrap();
+		fs::write(
+			source.join("guides/zeta.md"),
+			"---\ntitle: Zeta\norder: 0\n---\n# Zeta\n",
+		)
+		.unwrap();
+		fs::write(templates.join("base.html"), BASE_TEMPLATE).unwrap();
+		fs::write(
+			templates.join("posts.html"),
+			"{% for page in pages %}{{ page.frontmatter.title }};{% endfor %}",
+		)
+		.unwrap();
+		let config = root.join("rum.toml");
+		let mut site_config = Config::default();
+		let collection =
+			|name: &str, glob: &str, sort_by: &str, template: Option<&str>| CollectionConfig {
+				name: name.to_string(),
+				glob: glob.to_string(),
+				sort_by: sort_by.to_string(),
+				template: template.map(str::to_string),
+			};
+		site_config.collections = vec![
+			collection("blog", "blog/*.md", "date", None),
+			collection("all", "**/*.md", "title", None),
+			collection("posts", "blog/*.md", "order", Some("posts.html")),
+		];
+		site_config.save(&config).unwrap();
+
+		let generator = Generator::new(source, output.clone(), Some(config))
+			.unwrap()
+			.with_template_dir(Some(&templates))
+			.unwrap();
+		let documents = generator.collect_documents(None).unwrap();
+		let launch = documents
+			.iter()
+			.find(|d| d.relative_path == Path::new("blog/launch.md"))
+			.unwrap();
+		assert_eq!(launch.collections, ["blog", "all", "posts"]);
+		generator.build("html").await.unwrap();
+
+		// The global order sort puts Zeta first, each index uses its own policy instead
+		let listed = |name: &str| -> Vec<String> {
+			let html = fs::read_to_string(output.join(name).join("index.html")).unwrap();
+			let listing = html.split("class=\"directory-listing\"").nth(1).unwrap();
+			["Launch", "Intro", "Zeta"]
+				.into_iter()
+				.filter_map(|title| listing.find(&format!(">{}</a>", title)).map(|i| (i, title)))
+				.collect::<BTreeMap<_, _>>()
+				.into_values()
+				.map(str::to_string)
+				.collect()
+		};
+		assert_eq!(listed("blog"), ["Launch", "Intro"]);
+		assert_eq!(listed("all"), ["Intro", "Launch", "Zeta"]);
+		assert_eq!(
+			fs::read_to_string(output.join("posts/index.html")).unwrap(),
+			"Intro;Launch;"
+		);
+
+		// The blog directory already links to /blog/, the other collections get their own entries
+		let navigation = generator.build_navigation(&documents);
+		let hrefs: Vec<_> = navigation
+			.items
+			.iter()
+			.filter_map(|item| item.href.as_deref())
+			.collect();
+		assert!(hrefs.contains(&"/all/") && hrefs.contains(&"/posts/"));
+		assert_eq!(hrefs.iter().filter(|href| **href == "/blog/").count(), 1);
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_version_aliases() {
+		let root = std::env::temp_dir().join(format!("rum-aliases-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("latest")).unwrap();
+		fs::create_dir_all(source.join("v1.9.0")).unwrap();
+		fs::write(source.join("latest/guide.md"), "# Guide\n").unwrap();
+		fs::write(source.join("v1.9.0/guide.md"), "# Old guide\n").unwrap();
+		let config = root.join("rum.toml");
+		let mut site_config = Config::default();
+		site_config.site.versions = vec!["v1.9.0".to_string(), "latest".to_string()];
+		site_config
+			.site
+			.version_aliases
+			.insert("latest".to_string(), "v2.0.0".to_string());
+		site_config.save(&config).unwrap();
+
+		let generator = Generator::new(source, output.clone(), Some(config.clone())).unwrap();
+		generator.build("html").await.unwrap();
+		let latest = fs::read_to_string(output.join("latest/guide.html")).unwrap();
+		let real = fs::read_to_string(output.join("v2.0.0/guide.html")).unwrap();
+		for page in [&latest, &real] {
+			assert!(page.contains(
+				"<option value=\"latest\" selected>latest</option>\n<option value=\"v1.9.0\">v1.9.0</option>"
+			));
+		}
+		assert!(output.join("v1.9.0/guide.html").exists());
+
+		site_config
+			.site
+			.version_aliases
+			.insert("latest".to_string(), "next".to_string());
+		site_config.save(&config).unwrap();
+		assert!(Generator::new(root.join("docs"), output, Some(config)).is_err());
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_directory_listings() {
+		let root = std::env::temp_dir().join(format!("rum-listings-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		for (dir, count) in [("twenty", 20), ("twentyone", 21), ("forty", 40)] {
+			fs::create_dir_all(source.join(dir)).unwrap();
+			for n in 1..=count {
+				fs::write(
+					source.join(dir).join(format!("doc{:02}.md", n)),
+					format!("---\ntitle: {} {:02}\ntags: [sample]\n---\nText\n", dir, n),
+				)
+				.unwrap();
+			}
+		}
+		fs::create_dir_all(source.join("indexed")).unwrap();
+		fs::write(source.join("indexed/index.md"), "# Own index\n").unwrap();
+		fs::write(source.join("indexed/page.md"), "# Page\n").unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator =
+			Generator::new(source.clone(), output.clone(), Some(config.clone())).unwrap();
+		generator.build("html").await.unwrap();
+
+		let items = |path: &str| {
+			let page = fs::read_to_string(output.join(path)).unwrap();
+			let start = page.find("<ul class=\"directory-listing\">").unwrap();
+			let end = start + page[start..].find("</ul>").unwrap();
+			page[start..end].matches("<li>").count()
+		};
+		let exists = |path: &str| output.join(path).exists();
+
+		assert_eq!(items("twenty/index.html"), 20);
+		assert!(!exists("twenty/page/2/index.html"));
+
+		assert_eq!(items("twentyone/index.html"), 20);
+		assert_eq!(items("twentyone/page/2/index.html"), 1);
+		assert!(!exists("twentyone/page/3/index.html"));
+
+		assert_eq!(items("forty/index.html"), 20);
+		assert_eq!(items("forty/page/2/index.html"), 20);
+		assert!(!exists("forty/page/3/index.html"));
+
+		let first = fs::read_to_string(output.join("forty/index.html")).unwrap();
+		assert!(first.contains("<a href=\"/forty/page/2/\" rel=\"next\">"));
+		assert!(first.contains("<span>Page 1 of 2</span>"));
+		assert!(first.contains("<a href=\"/tags/sample/\">sample</a>"));
+		let last = fs::read_to_string(output.join("forty/page/2/index.html")).unwrap();
+		assert!(last.contains("<a href=\"/forty/\" rel=\"prev\">"));
+		assert!(!last.contains("rel=\"next\""));
+		assert!(!fs::read_to_string(output.join("twenty/index.html"))
+			.unwrap()
+			.contains("class=\"pagination\""));
+
+		// Directories with their own index page keep it, and the sidebar links the listings
+		assert!(fs::read_to_string(output.join("indexed/index.html"))
+			.unwrap()
+			.contains("Own index"));
+		assert!(first.contains("<a href=\"/twentyone/\">twentyone</a>"));
+		assert!(!first.contains("<a href=\"/indexed/\">"));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_i18n() {
+		let root = std::env::temp_dir().join(format!("rum-i18n-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("fr/v1")).unwrap();
+		fs::create_dir_all(source.join("v1")).unwrap();
+		fs::write(source.join("index.md"), "# Home\n").unwrap();
+		fs::write(source.join("guide.md"), "# Guide\n").unwrap();
+		fs::write(source.join("v1/setup.md"), "# Setup\n").unwrap();
+		fs::write(source.join("fr/index.md"), "# Accueil\n").unwrap();
+		fs::write(source.join("fr/v1/setup.md"), "# Installation\n").unwrap();
+		let config = root.join("rum.toml");
+		let mut settings = Config::default();
+		settings.i18n.locales = vec!["en".to_string(), "fr".to_string()];
+		settings.save(&config).unwrap();
+
+		let generator =
+			Generator::new(source.clone(), output.clone(), Some(config.clone())).unwrap();
+		let documents = generator.collect_documents(None).unwrap();
+		let setup = documents
+			.iter()
+			.find(|d| d.relative_path == Path::new("fr/v1/setup.md"))
+			.unwrap();
+		assert_eq!(setup.locale.as_deref(), Some("fr"));
+		assert_eq!(setup.version.as_deref(), Some("v1"));
+
+		generator.build("html").await.unwrap();
+		let page = |path: &str| fs::read_to_string(output.join(path)).unwrap();
+		let home = page("index.html");
+		assert!(home.contains("<html lang=\"en\""));
+		assert!(home.contains("<option value=\"/index.html\" selected>en</option>"));
+		assert!(home.contains("<option value=\"/fr/index.html\">fr</option>"));
+
+		let accueil = page("fr/index.html");
+		assert!(accueil.contains("<html lang=\"fr\""));
+		assert!(accueil.contains("<option value=\"/fr/index.html\" selected>fr</option>"));
+		assert!(!accueil.contains("i18n-fallback-banner"));
+
+		// Guide has no French version, so the English one stands in for it
+		let guide = page("fr/guide.html");
+		assert!(guide.contains("<html lang=\"fr\""));
+		assert!(guide.contains("class=\"i18n-fallback-banner\""));
+		assert!(guide.contains("Guide"));
+		assert!(page("guide.html").contains("<option value=\"/fr/guide.html\">fr</option>"));
+
+		assert_eq!(
+			generator.untranslated().unwrap(),
+			vec![("fr".to_string(), vec![PathBuf::from("guide.md")])]
+		);
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_prev_next_links() {
+		let root = std::env::temp_dir().join(format!("rum-prev-next-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		for version in ["v1", "v2"] {
+			fs::create_dir_all(source.join(version)).unwrap();
+			for (order, name) in [(1, "install"), (2, "usage"), (3, "faq")] {
+				fs::write(
+					source.join(version).join(format!("{}.md", name)),
+					format!(
+						"---\ntitle: {} {}\norder: {}\n---\nText\n",
+						version, name, order
+					),
+				)
+				.unwrap();
+			}
+		}
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator =
+			Generator::new(source.clone(), output.clone(), Some(config.clone())).unwrap();
+		generator.build("html").await.unwrap();
+		// Tera escapes the slashes of an href, which browsers read back the same
+		let page = |path: &str| {
+			fs::read_to_string(output.join(path))
+				.unwrap()
+				.replace("&#x2F;", "/")
+		};
+		for version in ["v1", "v2"] {
+			let first = page(&format!("{}/install.html", version));
+			assert!(!first.contains("page-nav-prev"));
+			assert!(first.contains(&format!(
+				"<a class=\"page-nav-next\" href=\"/{}/usage.html\" rel=\"next\">{} usage →</a>",
+				version, version
+			)));
+
+			let middle = page(&format!("{}/usage.html", version));
+			assert!(middle.contains(&format!("href=\"/{}/install.html\" rel=\"prev\"", version)));
+			assert!(middle.contains(&format!("href=\"/{}/faq.html\" rel=\"next\"", version)));
+
+			let last = page(&format!("{}/faq.html", version));
+			assert!(last.contains(&format!("href=\"/{}/usage.html\" rel=\"prev\"", version)));
+			assert!(!last.contains("page-nav-next"));
+		}
+
+		let mut settings = Config::default();
+		settings.navigation.prev_next = false;
+		settings.save(&config).unwrap();
+		let generator = Generator::new(source, output.clone(), Some(config)).unwrap();
+		generator.build("html").await.unwrap();
+		assert!(!page("v1/usage.html").contains("page-nav"));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_copies_images() {
+		let root = std::env::temp_dir().join(format!("rum-images-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("guide/img")).unwrap();
+		fs::create_dir_all(source.join("shared")).unwrap();
+		fs::write(source.join("guide/img/flow.png"), b"\x89PNG").unwrap();
+		fs::write(source.join("shared/logo.svg"), "<svg/>").unwrap();
+		fs::write(
+			source.join("guide/setup.md"),
+			"# Setup\n\n![Flow](img/flow.png)\n![Logo](../shared/logo.svg)\n![Gone](img/gone.webp)\n![Remote](https://example.com/x.png)\n",
+		)
+		.unwrap();
+
+		let mut config = Config::default();
+		config.output.structure = "flat".to_string();
+		let config_path = root.join("rum.toml");
+		config.save(&config_path).unwrap();
+
+		let generator = Generator::new(source, output.clone(), Some(config_path)).unwrap();
+		let documents = generator.collect_documents(None).unwrap();
+		assert_eq!(
+			documents[0].images,
+			vec![
+				PathBuf::from("guide/img/flow.png"),
+				PathBuf::from("shared/logo.svg"),
+				PathBuf::from("guide/img/gone.webp"),
+			]
+		);
+
+		generator.build("html").await.unwrap();
+		assert_eq!(generator.log().warning_count(), 1);
+		assert_eq!(
+			fs::read(output.join("guide/img/flow.png")).unwrap(),
+			b"\x89PNG"
+		);
+		assert!(output.join("shared/logo.svg").exists());
+
+		let page = fs::read_to_string(output.join("guide-setup.html")).unwrap();
+		assert!(page.contains("src=\"/guide/img/flow.png\""));
+		assert!(page.contains("src=\"/shared/logo.svg\""));
+		assert!(page.contains("src=\"https://example.com/x.png\""));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn test_parse_git_log() {
+		use std::os::unix::process::ExitStatusExt;
+		use std::process::ExitStatus;
+
+		let output = |code: i32, stdout: &str, stderr: &str| {
+			Ok(Output {
+				status: ExitStatus::from_raw(code << 8),
+				stdout: stdout.as_bytes().to_vec(),
+				stderr: stderr.as_bytes().to_vec(),
+			})
+		};
+
+		let date = parse_git_log(output(0, "2024-03-05T14:30:00+02:00\n", "")).unwrap();
+		assert_eq!(date.to_rfc3339(), "2024-03-05T12:30:00+00:00");
+
+		// Untracked file or empty repository
+		assert_eq!(parse_git_log(output(0, "", "")), None);
+		assert_eq!(
+			parse_git_log(output(128, "", "fatal: not a git repository")),
+			None
+		);
+		assert_eq!(
+			parse_git_log(Err(io::Error::from(io::ErrorKind::NotFound))),
+			None
+		);
+	}
+
+	#[tokio::test]
+	async fn test_not_found_page() {
+		let root = std::env::temp_dir().join(format!("rum-not-found-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("errors")).unwrap();
+		fs::write(source.join("index.md"), "# Home\n").unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator =
+			Generator::new(source.clone(), output.clone(), Some(config.clone())).unwrap();
+		generator.build("html").await.unwrap();
+		let page = fs::read_to_string(output.join("404.html")).unwrap();
+		assert!(page.contains("Page not found"));
+		assert!(page.contains("The page you are looking for does not exist."));
+
+		fs::write(
+			source.join("errors/404.md"),
+			"---\ntitle: Lost\n---\nTry the [home page](/index.html).\n",
+		)
+		.unwrap();
+		generator.build("html").await.unwrap();
+		let page = fs::read_to_string(output.join("404.html")).unwrap();
+		assert!(page.contains("Try the <a href=\"/index.html\">home page</a>."));
+		assert!(page.contains("<nav class=\"sidebar\">"));
+		assert!(!page.contains("does not exist"));
+		assert!(!output.join("errors/404.html").exists());
+
+		let documents = generator.collect_documents(None).unwrap();
+		let navigation = generator.build_navigation(&documents);
+		assert!(navigation
+			.flatten_all()
+			.iter()
+			.all(|item| item.path != Path::new("errors/404.md")));
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_custom_template_dir() {
+		let root = std::env::temp_dir().join(format!("rum-templates-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		let templates = root.join("templates");
+		fs::create_dir_all(&source).unwrap();
+		fs::create_dir_all(&templates).unwrap();
+		fs::write(
+			source.join("index.md"),
+			"---\ntitle: Home & Away\nnoindex: true\n---\nLiteral {{TITLE}} stays put.\n",
+		)
+		.unwrap();
+		fs::write(source.join("guide.md"), "---\ntitle: Guide\n---\n# Guide\n").unwrap();
+		fs::write(
+			templates.join("base.html"),
+			"<title>{{ title }}</title>{% if doc.frontmatter.noindex %}<noindex/>{% endif %}\n\
+			 <ul>{% for item in navigation.items %}<li>{{ item.title }}</li>{% endfor %}</ul>\n\
+			 {% include \"footer.html\" %}{{ content_html | safe }}",
+		)
+		.unwrap();
+		fs::write(
+			templates.join("footer.html"),
+			"<footer>{{ config.site.title }}</footer>",
+		)
+		.unwrap();
+		let config = root.join("rum.toml");
+		Config::default().save(&config).unwrap();
+
+		let generator = Generator::new(source, output.clone(), Some(config))
+			.unwrap()
+			.with_template_dir(Some(&templates))
+			.unwrap();
+		generator.build("html").await.unwrap();
+		let page = fs::read_to_string(output.join("index.html")).unwrap();
+		assert!(page.contains("<title>Home &amp; Away</title><noindex/>"));
+		assert!(page.contains("<li>Guide</li>"));
+		assert!(page.contains(&format!(
+			"<footer>{}</footer>",
+			Config::default().site.title
+		)));
+		assert!(page.contains("Literal {{TITLE}} stays put."));
+		let guide = fs::read_to_string(output.join("guide.html")).unwrap();
+		assert!(!guide.contains("<noindex/>"));
+
+		fs::write(templates.join("base.html"), "{% if %}").unwrap();
+		assert!(TemplateEngine::from_dir(Some(&templates)).is_err());
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[tokio::test]
+	async fn test_url_pattern() {
+		let root = std::env::temp_dir().join(format!("rum-urls-{}", std::process::id()));
+		let source = root.join("docs");
+		let output = root.join("dist");
+		fs::create_dir_all(source.join("guide")).unwrap();
+		fs::write(
+			source.join("guide/setup.md"),
+			"---\ntitle: Setup\n---\n# Setup\n",
+		)
+		.unwrap();
+		fs::write(
+			source.join("intro.md"),
+			"---\ntitle: Intro\n---\nSee [[Setup]].\n",
+		)
+		.unwrap();
+		let config = root.join("rum.toml");
+		let mut settings = Config::default();
+		settings.urls.pattern = "/pages/:slug/".to_string();
+		settings.save(&config).unwrap();
+
+		let generator = Generator::new(source, output.clone(), Some(config.clone())).unwrap();
+		generator.build("html").await.unwrap();
+		assert!(output.join("pages/intro/index.html").exists());
+		let page = fs::read_to_string(output.join("pages/setup/index.html")).unwrap();
+		assert!(page.contains("href=\"/pages/intro/index.html\""));
+		assert!(page.contains("<li><a href=\"/pages/intro/index.html\">Intro</a></li>"));
+		assert!(!output.join("guide/setup.html").exists());
+
+		settings.output.structure = "flat".to_string();
+		settings.save(&config).unwrap();
+		assert!(Generator::new(root.join("docs"), output, Some(config)).is_err());
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[test]
+	fn test_generate_sitemap() {
+		let root = std::env::temp_dir().join(format!("rum-sitemap-{}", std::process::id()));
+		fs::create_dir_all(root.join("guide")).unwrap();
+		fs::create_dir_all(root.join("print")).unwrap();
+		for page in [
+			"index.html",
+			"guide/setup.html",
+			"guide/wip.html",
+			"guide/hidden.html",
+			"print/guide-setup.html",
+		] {
+			fs::write(root.join(page), "<html></html>").unwrap();
+		}
+		fs::write(root.join("guide/notes.txt"), "").unwrap();
+
+		let doc = |path: &str, draft: Option<bool>, noindex: Option<bool>| Document {
+			frontmatter: crate::content::Frontmatter {
+				draft,
+				noindex,
+				..Default::default()
+			},
+			path: PathBuf::from(path),
+			relative_path: PathBuf::from(path),
+			..Default::default()
+		};
+		let documents = vec![
+			doc("index.md", None, None),
+			doc("guide/setup.md", Some(false), Some(false)),
+			doc("guide/wip.md", Some(true), None),
+			doc("guide/hidden.md", None, Some(true)),
+		];
+
+		let mut config = Config::default();
+		config.sitemap.enabled = true;
+		config.sitemap.base_url = Some("https://docs.example.com/".to_string());
+		generate_sitemap(&documents, &config, &root).unwrap();
+
+		let sitemap = fs::read_to_string(root.join("sitemap.xml")).unwrap();
+		assert!(sitemap.contains("<loc>https://docs.example.com/index.html</loc>"));
+		assert!(sitemap.contains("<loc>https://docs.example.com/guide/setup.html</loc>"));
+		assert!(sitemap.contains("<changefreq>weekly</changefreq>"));
+		assert!(sitemap.contains("<priority>0.5</priority>"));
+		assert!(!sitemap.contains("wip"));
+		assert!(!sitemap.contains("hidden"));
+		assert!(!sitemap.contains("notes.txt"));
+		assert!(!sitemap.contains("print/"));
+		assert_eq!(sitemap.matches("<url>").count(), 2);
+
+		config.sitemap.compress = true;
+		generate_sitemap(&documents, &config, &root).unwrap();
+		let mut decompressed = String::new();
+		flate2::read::GzDecoder::new(fs::File::open(root.join("sitemap.xml.gz")).unwrap())
+			.read_to_string(&mut decompressed)
+			.unwrap();
+		assert_eq!(decompressed, sitemap);
+
+		config.sitemap.base_url = None;
+		assert!(generate_sitemap(&documents, &config, &root).is_err());
+
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[test]
+	fn test_search_entry_score() {
+		let entry = SearchEntry {
+			title: "Install Guide".to_string(),
+			content: "Install with cargo. Cargo builds it, then install the man pages.".to_string(),
+			path: "guide/install.md".to_string(),
+			version: None,
+		};
+		let terms =
+			|q: &str| -> Vec<String> { q.split_whitespace().map(str::to_lowercase).collect() };
+		assert_eq!(entry.score(&terms("install")), 3 + 2);
+		assert_eq!(entry.score(&terms("CARGO")), 2);
+		assert_eq!(entry.score(&terms("install cargo")), 5 + 2);
+		assert_eq!(entry.score(&terms("guide")), 3);
+		assert_eq!(entry.score(&terms("missing")), 0);
+		assert_eq!(entry.score(&terms("")), 0);
+	}
+
+	#[test]
+	fn test_parse_formats() {
+		assert_eq!(parse_formats("html").unwrap(), vec!["html"]);
+		assert_eq!(parse_formats("html, json").unwrap(), vec!["html", "json"]);
+		assert!(parse_formats("html,docx").is_err());
+		assert!(parse_formats(",").is_err());
+	}
+
+	#[test]
+	fn test_resolve_link_by_version() {
+		let doc = |path: &str, version: Option<&str>| Document {
+			frontmatter: crate::content::Frontmatter {
+				title: Some("Configuration".to_string()),
+				..Default::default()
+			},
+			path: PathBuf::from(path),
+			relative_path: PathBuf::from(path),
+			version: version.map(str::to_string),
+			..Default::default()
+		};
+		let corpus = vec![
+			doc("v1.0.0/config.md", Some("v1.0.0")),
+			doc("latest/config.md", Some("latest")),
+			doc("v2.0.0/config.md", Some("v2.0.0")),
+		];
+		let resolve = |link: &str, version: Option<&str>, documents: &[Document]| {
+			resolve_link(link, version, documents).map(|idx| documents[idx].relative_path.clone())
+		};
+
+		// Each version's pages link within that version, by title or wiki link
+		for version in ["v1.0.0", "latest", "v2.0.0"] {
+			let own = PathBuf::from(format!("{}/config.md", version));
+			assert_eq!(
+				resolve("Configuration", Some(version), &corpus),
+				Some(own.clone())
+			);
+			assert_eq!(
+				resolve("configuration.html", Some(version), &corpus),
+				Some(own)
+			);
+		}
+		// A path names one page exactly, whatever the source's version
+		assert_eq!(
+			resolve("v1.0.0/config.md", Some("v2.0.0"), &corpus),
+			Some(PathBuf::from("v1.0.0/config.md"))
+		);
+
+		// A version without the page falls back to latest, then unversioned, then the newest
+		assert_eq!(
+			resolve("Configuration", Some("v3.0.0"), &corpus),
+			Some(PathBuf::from("latest/config.md"))
+		);
+		let mut corpus = corpus;
+		corpus.remove(1);
+		corpus.push(doc("config.md", None));
+		assert_eq!(
+			resolve("Configuration", Some("v3.0.0"), &corpus),
+			Some(PathBuf::from("config.md"))
+		);
+		corpus.pop();
+		corpus.push(doc("nightly/config.md", Some("nightly")));
+		assert_eq!(
+			resolve("Configuration", Some("v3.0.0"), &corpus),
+			Some(PathBuf::from("v2.0.0/config.md"))
+		);
+		assert_eq!(resolve("Missing", Some("v2.0.0"), &corpus), None);
+	}
+
+	#[test]
+	fn test_sort_documents() {
+		let doc =
+			|path: &str, title: Option<&str>, date: Option<&str>, order: Option<f64>| Document {
+				frontmatter: crate::content::Frontmatter {
+					title: title.map(str::to_string),
+					date: date.and_then(crate::content::parse_date),
+					order,
+					..Default::default()
+				},
+				path: PathBuf::from(path),
+				relative_path: PathBuf::from(path),
+				..Default::default()
+			};
+		let corpus = vec![
+			doc(
+				"guide/setup.md",
+				Some("Setup"),
+				Some("2024-01-10"),
+				Some(2.0),
+			),
+			doc("zebra.md", None, None, None),
+			doc("about.md", Some("about us"), Some("2024-03-01"), None),
+			doc("changelog.md", Some("Changelog"), None, Some(1.0)),
+			doc(
+				"intro.md",
+				Some("Introduction"),
+				Some("2023-12-24"),
+				Some(f64::NAN),
+			),
+			doc("api.md", Some("API"), None, None),
+		];
+		let nav_order = NavOrder::new(&BTreeMap::new()).unwrap();
+		let sorted = |sort_by: &str| -> Vec<String> {
+			let mut documents = corpus.clone();
+			sort_documents(&mut documents, sort_by, &nav_order);
+			documents
+				.iter()
+				.map(|d| d.relative_path.to_string_lossy().to_string())
+				.collect()
+		};
+
+		assert_eq!(
+			sorted("order"),
+			vec![
+				"changelog.md",
+				"guide/setup.md",
+				"zebra.md",
+				"about.md",
+				"api.md",
+				"intro.md"
+			]
+		);
+		// Undated documents keep their relative order at the end
+		assert_eq!(
+			sorted("date"),
+			vec![
+				"about.md",
+				"guide/setup.md",
+				"intro.md",
+				"zebra.md",
+				"changelog.md",
+				"api.md"
+			]
+		);
+		// Untitled documents sort by file name, case is ignored
+		assert_eq!(
+			sorted("title"),
+			vec![
+				"about.md",
+				"api.md",
+				"changelog.md",
+				"intro.md",
+				"guide/setup.md",
+				"zebra.md"
+			]
+		);
+		assert_eq!(
+			sorted("filename"),
+			vec![
+				"about.md",
+				"api.md",
+				"changelog.md",
+				"guide/setup.md",
+				"intro.md",
+				"zebra.md"
+			]
+		);
+
+		// Patterns fill in for documents without a frontmatter order
+		let nav_order = NavOrder::new(&BTreeMap::from([
+			("api.md".to_string(), 1),
+			("*.md".to_string(), 500),
+		]))
+		.unwrap();
+		let mut documents = corpus.clone();
+		sort_documents(&mut documents, "order", &nav_order);
+		assert_eq!(
+			documents
+				.iter()
+				.map(|d| d.relative_path.to_string_lossy().to_string())
+				.collect::<Vec<_>>(),
+			vec![
+				"changelog.md",
+				"api.md",
+				"guide/setup.md",
+				"zebra.md",
+				"about.md",
+				"intro.md"
+			]
+		);
+	}
+
+	#[test]
+	fn test_feed_entries() {
+		let doc = |path: &str, date: Option<&str>| Document {
+			frontmatter: crate::content::Frontmatter {
+				date: date.and_then(crate::content::parse_date),
+				..Default::default()
+			},
+			path: PathBuf::from(path),
+			relative_path: PathBuf::from(path),
+			..Default::default()
+		};
+		let documents = vec![
+			doc("old.md", Some("2023-05-01")),
+			doc("undated.md", None),
+			doc("new.md", Some("2024-02-10")),
+			doc("mid.md", Some("2023-11-30T08:00:00Z")),
+		];
+
+		let paths = |entries: Vec<(&Document, NaiveDate)>| -> Vec<String> {
+			entries
+				.iter()
+				.map(|(d, _)| d.relative_path.to_string_lossy().to_string())
+				.collect()
+		};
+		assert_eq!(
+			paths(feed_entries(&documents, 20)),
+			vec!["new.md", "mid.md", "old.md"]
+		);
+		assert_eq!(paths(feed_entries(&documents, 1)), vec!["new.md"]);
+	}
+
+	#[test]
+	fn test_group_by_tag() {
+		let doc = |path: &str, tags: &[&str]| Document {
+			frontmatter: crate::content::Frontmatter {
+				tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+				..Default::default()
+			},
+			path: PathBuf::from(path),
+			relative_path: PathBuf::from(path),
+			..Default::default()
+		};
+		let documents = vec![
+			doc("a.md", &["Rust", "CLI"]),
+			doc("b.md", &["rust"]),
+			doc("c.md", &["cli", "CLI"]),
+		];
+
+		let tags = group_by_tag(&documents);
+		let grouped: Vec<_> = tags
+			.iter()
+			.map(|(slug, (name, docs))| {
+				let paths: Vec<_> = docs
+					.iter()
+					.map(|d| d.relative_path.to_str().unwrap())
+					.collect();
+				(slug.as_str(), name.as_str(), paths)
+			})
+			.collect();
+		assert_eq!(
+			grouped,
+			vec![
+				("cli", "CLI", vec!["a.md", "c.md"]),
+				("rust", "Rust", vec!["a.md", "b.md"]),
+			]
+		);
+	}
+
+	#[test]
+	fn test_navigation_flatten() {
+		let mut tree = NavigationTree::new();
+		for path in [
+			"index.md",
+			"guide/intro.md",
+			"guide/advanced/macros.md",
+			"guide/setup.md",
+		] {
+			tree.add_path(Path::new(path), path.to_string(), None, String::new());
+		}
+
+		let pages: Vec<_> = tree.flatten().iter().map(|i| i.title.as_str()).collect();
+		assert_eq!(
+			pages,
+			vec![
+				"index.md",
+				"guide/intro.md",
+				"guide/advanced/macros.md",
+				"guide/setup.md"
+			]
+		);
+
+		let all: Vec<_> = tree
+			.flatten_all()
+			.iter()
+			.map(|i| i.title.as_str())
+			.collect();
+		assert_eq!(
+			all,
+			vec![
+				"index.md",
+				"guide",
+				"guide/intro.md",
+				"advanced",
+				"guide/advanced/macros.md",
+				"guide/setup.md"
+			]
+		);
+		let again: Vec<_> = tree.flatten().iter().map(|i| i.title.as_str()).collect();
+		assert_eq!(pages, again);
 	}
 }
@@ -0,0 +1,431 @@
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+use crate::content::Document;
+
+// Directed link graph over a document set, edges point from the linking document to its target
+pub struct DocumentGraph<'a> {
+	documents: &'a [Document],
+	outgoing: Vec<Vec<usize>>,
+	incoming: Vec<Vec<usize>>,
+}
+
+impl<'a> DocumentGraph<'a> {
+	pub fn new(documents: &'a [Document]) -> Self {
+		let keys = link_keys(documents);
+		let titles: HashMap<String, usize> = documents
+			.iter()
+			.enumerate()
+			.map(|(idx, doc)| (doc_title(doc).to_lowercase(), idx))
+			.collect();
+
+		let mut edges = HashSet::new();
+		for (from, doc) in documents.iter().enumerate() {
+			for link in &doc.links {
+				if let Some(&to) = keys.get(&normalize_link(link)) {
+					edges.insert((from, to));
+				}
+			}
+			// Backlinks hold the titles of the documents linking here
+			for title in &doc.backlinks {
+				if let Some(&linker) = titles.get(&title.to_lowercase()) {
+					edges.insert((linker, from));
+				}
+			}
+		}
+
+		let mut outgoing = vec![Vec::new(); documents.len()];
+		let mut incoming = vec![Vec::new(); documents.len()];
+		let mut edges: Vec<_> = edges.into_iter().filter(|(from, to)| from != to).collect();
+		edges.sort();
+		for (from, to) in edges {
+			outgoing[from].push(to);
+			incoming[to].push(from);
+		}
+
+		Self {
+			documents,
+			outgoing,
+			incoming,
+		}
+	}
+
+	pub fn orphans(&self) -> Vec<&'a Document> {
+		self.documents
+			.iter()
+			.enumerate()
+			.filter(|(idx, _)| self.incoming[*idx].is_empty())
+			.map(|(_, doc)| doc)
+			.collect()
+	}
+
+	// One node per document, versioned documents grouped in a cluster per version and documents
+	// without any link in or out greyed out
+	pub fn to_dot(&self) -> String {
+		let mut unversioned = Vec::new();
+		let mut versions: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+		for (idx, doc) in self.documents.iter().enumerate() {
+			match &doc.version {
+				Some(version) => versions.entry(version.as_str()).or_default().push(idx),
+				None => unversioned.push(idx),
+			}
+		}
+
+		let mut dot =
+			String::from("digraph rum {\n\tnode [shape=box, style=filled, fillcolor=white];\n");
+		for idx in unversioned {
+			dot.push_str(&format!("\t{}\n", self.dot_node(idx)));
+		}
+		for (version, members) in versions {
+			dot.push_str(&format!(
+				"\tsubgraph {} {{\n\t\tlabel={};\n",
+				dot_quote(&format!("cluster_{}", version)),
+				dot_quote(version)
+			));
+			for idx in members {
+				dot.push_str(&format!("\t\t{}\n", self.dot_node(idx)));
+			}
+			dot.push_str("\t}\n");
+		}
+		for (from, targets) in self.outgoing.iter().enumerate() {
+			for &to in targets {
+				dot.push_str(&format!(
+					"\t{} -> {};\n",
+					dot_quote(&node_id(&self.documents[from])),
+					dot_quote(&node_id(&self.documents[to]))
+				));
+			}
+		}
+		dot.push_str("}\n");
+		dot
+	}
+
+	fn dot_node(&self, idx: usize) -> String {
+		let doc = &self.documents[idx];
+		let isolated = if self.incoming[idx].is_empty() && self.outgoing[idx].is_empty() {
+			", fillcolor=lightgrey"
+		} else {
+			""
+		};
+		format!(
+			"{} [label={}{}];",
+			dot_quote(&node_id(doc)),
+			dot_quote(&doc_title(doc)),
+			isolated
+		)
+	}
+
+	pub fn to_json(&self) -> Value {
+		let nodes: Vec<_> = self
+			.documents
+			.iter()
+			.map(|doc| {
+				json!({
+					"id": node_id(doc),
+					"title": doc_title(doc),
+					"version": doc.version,
+					"tags": doc.frontmatter.tags.clone().unwrap_or_default(),
+				})
+			})
+			.collect();
+		let edges: Vec<_> = self
+			.outgoing
+			.iter()
+			.enumerate()
+			.flat_map(|(from, targets)| {
+				targets.iter().map(move |&to| {
+					json!({
+						"source": node_id(&self.documents[from]),
+						"target": node_id(&self.documents[to]),
+					})
+				})
+			})
+			.collect();
+		json!({ "nodes": nodes, "edges": edges })
+	}
+}
+
+// Queries for link analysis, only the tests call them until `rum graph` grows options for them
+impl<'a> DocumentGraph<'a> {
+	#[cfg_attr(
+		not(test),
+		allow(dead_code, reason = "kept for a `rum graph --hubs` report")
+	)]
+	pub fn hubs(&self, n: usize) -> Vec<(&'a Document, usize)> {
+		let mut hubs: Vec<_> = self
+			.documents
+			.iter()
+			.enumerate()
+			.map(|(idx, doc)| (doc, self.incoming[idx].len()))
+			.filter(|(_, degree)| *degree > 0)
+			.collect();
+		hubs.sort_by_key(|h| Reverse(h.1));
+		hubs.truncate(n);
+		hubs
+	}
+
+	// Tarjan's algorithm, iterative so deep link chains cannot overflow the stack
+	#[cfg_attr(
+		not(test),
+		allow(dead_code, reason = "kept for reporting link cycles from `rum check`")
+	)]
+	pub fn strongly_connected_components(&self) -> Vec<Vec<&'a Document>> {
+		let count = self.documents.len();
+		let mut index = vec![None; count];
+		let mut lowlink = vec![0; count];
+		let mut on_stack = vec![false; count];
+		let mut stack = Vec::new();
+		let mut next_index = 0;
+		let mut components = Vec::new();
+
+		for root in 0..count {
+			if index[root].is_some() {
+				continue;
+			}
+
+			// (node, position in its edge list)
+			let mut work = vec![(root, 0)];
+			while let Some(&mut (node, ref mut edge)) = work.last_mut() {
+				if *edge == 0 && index[node].is_none() {
+					index[node] = Some(next_index);
+					lowlink[node] = next_index;
+					next_index += 1;
+					stack.push(node);
+					on_stack[node] = true;
+				}
+
+				if let Some(&next) = self.outgoing[node].get(*edge) {
+					*edge += 1;
+					match index[next] {
+						None => work.push((next, 0)),
+						Some(next_idx) if on_stack[next] => {
+							lowlink[node] = lowlink[node].min(next_idx);
+						}
+						Some(_) => {}
+					}
+					continue;
+				}
+
+				work.pop();
+				if let Some(&(parent, _)) = work.last() {
+					lowlink[parent] = lowlink[parent].min(lowlink[node]);
+				}
+
+				if Some(lowlink[node]) == index[node] {
+					let mut component = Vec::new();
+					while let Some(member) = stack.pop() {
+						on_stack[member] = false;
+						component.push(&self.documents[member]);
+						if member == node {
+							break;
+						}
+					}
+					components.push(component);
+				}
+			}
+		}
+
+		components
+	}
+
+	// Shortest link path by breadth-first search, both ends included
+	#[cfg_attr(
+		not(test),
+		allow(dead_code, reason = "kept for a `rum graph --path` query")
+	)]
+	pub fn path_between(&self, from: &Path, to: &Path) -> Option<Vec<&'a Path>> {
+		let position = |path: &Path| self.documents.iter().position(|d| d.relative_path == path);
+		let (start, goal) = (position(from)?, position(to)?);
+
+		let mut previous = vec![None::<usize>; self.documents.len()];
+		let mut visited = vec![false; self.documents.len()];
+		let mut queue = VecDeque::from([start]);
+		visited[start] = true;
+
+		while let Some(node) = queue.pop_front() {
+			if node == goal {
+				let mut path = vec![self.documents[node].relative_path.as_path()];
+				let mut current = node;
+				while let Some(prev) = previous[current] {
+					path.push(self.documents[prev].relative_path.as_path());
+					current = prev;
+				}
+				path.reverse();
+				return Some(path);
+			}
+
+			for &next in &self.outgoing[node] {
+				if !visited[next] {
+					visited[next] = true;
+					previous[next] = Some(node);
+					queue.push_back(next);
+				}
+			}
+		}
+
+		None
+	}
+}
+
+fn node_id(doc: &Document) -> String {
+	doc.relative_path.to_string_lossy().replace('\\', "/")
+}
+
+fn dot_quote(value: &str) -> String {
+	format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn doc_title(doc: &Document) -> String {
+	doc.frontmatter
+		.title
+		.clone()
+		.unwrap_or_else(|| doc.relative_path.to_string_lossy().to_string())
+}
+
+// Every spelling a link to a document may take: title, source path, output path or wiki link slug
+fn link_keys(documents: &[Document]) -> HashMap<String, usize> {
+	let mut keys = HashMap::new();
+	for (idx, doc) in documents.iter().enumerate() {
+		let title = doc_title(doc).to_lowercase();
+		let path = doc.relative_path.to_string_lossy().replace('\\', "/");
+		let html = doc
+			.relative_path
+			.with_extension("html")
+			.to_string_lossy()
+			.replace('\\', "/");
+
+		for key in [
+			format!("{}.html", title.replace(' ', "-")),
+			title,
+			path,
+			html,
+		] {
+			keys.entry(key.to_lowercase()).or_insert(idx);
+		}
+	}
+	keys
+}
+
+fn normalize_link(link: &str) -> String {
+	let link = link.split('#').next().unwrap_or_default();
+	link.trim_start_matches("./")
+		.trim_start_matches('/')
+		.to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::path::PathBuf;
+
+	fn doc(path: &str, links: &[&str]) -> Document {
+		Document {
+			path: PathBuf::from(path),
+			relative_path: PathBuf::from(path),
+			links: links.iter().map(|l| l.to_string()).collect(),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_document_graph() {
+		let documents = vec![
+			doc("a.md", &["b.html"]),
+			doc("b.md", &["c.md#usage"]),
+			doc("c.md", &["b.md"]),
+			doc("d.md", &[]),
+		];
+		let graph = DocumentGraph::new(&documents);
+
+		let orphans: Vec<_> = graph
+			.orphans()
+			.iter()
+			.map(|d| d.relative_path.as_path())
+			.collect();
+		assert_eq!(orphans, vec![Path::new("a.md"), Path::new("d.md")]);
+		assert_eq!(graph.hubs(1)[0].0.relative_path, Path::new("b.md"));
+		assert_eq!(graph.strongly_connected_components().len(), 3);
+		assert_eq!(
+			graph.path_between(Path::new("a.md"), Path::new("c.md")),
+			Some(vec![
+				Path::new("a.md"),
+				Path::new("b.md"),
+				Path::new("c.md")
+			])
+		);
+		assert_eq!(
+			graph.path_between(Path::new("c.md"), Path::new("a.md")),
+			None
+		);
+	}
+
+	#[test]
+	fn test_path_between() {
+		let documents = vec![
+			doc("a.md", &["b.md", "d.md"]),
+			doc("b.md", &["c.md"]),
+			doc("c.md", &[]),
+			doc("d.md", &["c.md"]),
+		];
+		let graph = DocumentGraph::new(&documents);
+
+		assert_eq!(
+			graph.path_between(Path::new("a.md"), Path::new("a.md")),
+			Some(vec![Path::new("a.md")])
+		);
+		// Ties go to the document listed first
+		assert_eq!(
+			graph.path_between(Path::new("a.md"), Path::new("c.md")),
+			Some(vec![
+				Path::new("a.md"),
+				Path::new("b.md"),
+				Path::new("c.md")
+			])
+		);
+		assert_eq!(
+			graph.path_between(Path::new("a.md"), Path::new("missing.md")),
+			None
+		);
+	}
+
+	#[test]
+	fn test_graph_export() {
+		let mut documents = vec![
+			doc("v1/a.md", &["Setup"]),
+			doc("v1/b.md", &["v1/a.md"]),
+			doc("v2/a.md", &["Setup"]),
+			doc("setup.md", &[]),
+			doc("lonely.md", &[]),
+		];
+		documents[3].frontmatter.title = Some("Setup".to_string());
+		documents[3].frontmatter.tags = Some(vec!["intro".to_string()]);
+		for doc in &mut documents[..3] {
+			doc.version = doc
+				.relative_path
+				.components()
+				.next()
+				.map(|c| c.as_os_str().to_string_lossy().to_string());
+		}
+		let graph = DocumentGraph::new(&documents);
+		let dot = graph.to_dot();
+		assert!(dot.starts_with("digraph rum {"));
+		assert_eq!(dot.matches(" -> ").count(), 3);
+		assert!(dot.contains("\"v1/b.md\" -> \"v1/a.md\";"));
+		assert!(dot.contains("subgraph \"cluster_v1\" {"));
+		assert!(dot.contains("subgraph \"cluster_v2\" {"));
+		assert!(dot.contains("\"setup.md\" [label=\"Setup\"];"));
+		assert!(dot.contains("\"lonely.md\" [label=\"lonely.md\", fillcolor=lightgrey];"));
+
+		let json = graph.to_json();
+		assert_eq!(json["nodes"].as_array().unwrap().len(), 5);
+		assert_eq!(json["edges"].as_array().unwrap().len(), 3);
+		assert_eq!(json["nodes"][0]["version"], "v1");
+		assert_eq!(json["nodes"][3]["tags"][0], "intro");
+		assert!(json["edges"]
+			.as_array()
+			.unwrap()
+			.contains(&json!({ "source": "v2/a.md", "target": "setup.md" })));
+	}
+}
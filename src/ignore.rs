@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Source files left out of the build, gitignore style: the last matching pattern decides,
+// so a later !pattern brings back a file an earlier pattern excluded
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+	set: GlobSet,
+	negated: Vec<bool>,
+}
+
+impl IgnoreRules {
+	pub fn new(patterns: &[String]) -> Result<Self> {
+		let mut builder = GlobSetBuilder::new();
+		let mut negated = Vec::new();
+		for pattern in patterns {
+			let (negate, glob) = match pattern.strip_prefix('!') {
+				Some(glob) => (true, glob),
+				None => (false, pattern.as_str()),
+			};
+			// Like .gitignore, a pattern without a slash matches at any depth and a trailing
+			// slash covers everything under the directory
+			let glob = match glob.strip_prefix('/') {
+				Some(anchored) => anchored.to_string(),
+				None if !glob.trim_end_matches('/').contains('/') => format!("**/{}", glob),
+				None => glob.to_string(),
+			};
+			let glob = match glob.strip_suffix('/') {
+				Some(dir) => format!("{}/**", dir),
+				None => glob,
+			};
+			builder.add(
+				GlobBuilder::new(&glob)
+					.literal_separator(true)
+					.build()
+					.with_context(|| format!("Invalid ignore pattern '{}'", pattern))?,
+			);
+			negated.push(negate);
+		}
+		Ok(Self {
+			set: builder.build()?,
+			negated,
+		})
+	}
+
+	// Paths are relative to the source directory
+	pub fn is_ignored(&self, path: &Path) -> bool {
+		self.set
+			.matches(path)
+			.into_iter()
+			.max()
+			.is_some_and(|last| !self.negated[last])
+	}
+}
+
+// One pattern per line, blank lines and # comments are skipped
+pub fn read_ignore_file(path: &Path) -> Result<Vec<String>> {
+	let content = match fs::read_to_string(path) {
+		Ok(content) => content,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+	};
+	Ok(content
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.map(str::to_string)
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn compile(patterns: &[&str]) -> IgnoreRules {
+		IgnoreRules::new(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>()).unwrap()
+	}
+
+	#[test]
+	fn test_double_star() {
+		let rules = compile(&["**/private/**", "*.secret.md", "/drafts/"]);
+		assert!(rules.is_ignored(Path::new("private/keys.md")));
+		assert!(rules.is_ignored(Path::new("team/private/notes/plan.md")));
+		assert!(rules.is_ignored(Path::new("passwords.secret.md")));
+		assert!(rules.is_ignored(Path::new("ops/db.secret.md")));
+		assert!(rules.is_ignored(Path::new("drafts/idea.md")));
+		assert!(!rules.is_ignored(Path::new("guide/drafts/idea.md")));
+		assert!(!rules.is_ignored(Path::new("privateer.md")));
+		assert!(!rules.is_ignored(Path::new("guide/setup.md")));
+	}
+
+	#[test]
+	fn test_negation() {
+		let rules = compile(&["internal/**", "!internal/public.md"]);
+		assert!(rules.is_ignored(Path::new("internal/roadmap.md")));
+		assert!(!rules.is_ignored(Path::new("internal/public.md")));
+
+		// A negation only brings back what came before it
+		let rules = compile(&["!internal/public.md", "internal/**"]);
+		assert!(rules.is_ignored(Path::new("internal/public.md")));
+	}
+
+	#[test]
+	fn test_overlapping_patterns() {
+		let rules = compile(&["*.md", "!guide/*.md", "guide/old-*.md"]);
+		assert!(rules.is_ignored(Path::new("index.md")));
+		assert!(!rules.is_ignored(Path::new("guide/setup.md")));
+		assert!(rules.is_ignored(Path::new("guide/old-setup.md")));
+		assert!(!rules.is_ignored(Path::new("index.rst")));
+
+		assert!(IgnoreRules::new(&["a[".to_string()]).is_err());
+	}
+
+	#[test]
+	fn test_read_ignore_file() {
+		let path = std::env::temp_dir().join(format!("rum-ignore-{}", std::process::id()));
+		assert!(read_ignore_file(&path).unwrap().is_empty());
+		fs::write(&path, "# drafts\n\ndrafts/\n  !drafts/keep.md  \n").unwrap();
+		assert_eq!(
+			read_ignore_file(&path).unwrap(),
+			vec!["drafts/".to_string(), "!drafts/keep.md".to_string()]
+		);
+		fs::remove_file(&path).ok();
+	}
+}
@@ -0,0 +1,320 @@
+use anyhow::Result;
+use reqwest::header::LOCATION;
+use reqwest::redirect::Policy;
+use reqwest::{Client, StatusCode, Url};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::config::LinkCheckConfig;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkStatus {
+	Ok(u16),
+	Redirect { status: u16, location: String },
+	ClientError(u16),
+	ServerError(u16),
+	Failed(String),
+}
+
+impl LinkStatus {
+	// Redirects still lead somewhere, they are reported but not counted as broken
+	pub fn is_broken(&self) -> bool {
+		matches!(
+			self,
+			LinkStatus::ClientError(_) | LinkStatus::ServerError(_) | LinkStatus::Failed(_)
+		)
+	}
+
+	fn label(&self) -> &'static str {
+		match self {
+			LinkStatus::Ok(_) => "ok",
+			LinkStatus::Redirect { .. } => "redirect",
+			LinkStatus::ClientError(_) => "client",
+			LinkStatus::ServerError(_) => "server",
+			LinkStatus::Failed(_) => "failed",
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct LinkChecker {
+	client: Client,
+	concurrency: usize,
+	retries: u32,
+}
+
+impl LinkChecker {
+	pub fn new(config: &LinkCheckConfig) -> Result<Self> {
+		// Redirects are reported with their target rather than followed
+		let client = Client::builder()
+			.redirect(Policy::none())
+			.timeout(Duration::from_secs(config.timeout_secs))
+			.user_agent(concat!("rum/", env!("CARGO_PKG_VERSION")))
+			.build()?;
+		Ok(Self {
+			client,
+			concurrency: config.concurrency.max(1),
+			retries: config.retries,
+		})
+	}
+
+	// Results come back sorted by URL
+	pub async fn check_all(&self, urls: Vec<String>) -> Vec<(String, LinkStatus)> {
+		let overall = Arc::new(Semaphore::new(self.concurrency));
+		let mut hosts: HashMap<String, Arc<Semaphore>> = HashMap::new();
+
+		let mut tasks = JoinSet::new();
+		for url in urls {
+			let host = Url::parse(&url)
+				.ok()
+				.and_then(|u| u.host_str().map(str::to_string))
+				.unwrap_or_default();
+			let host = Arc::clone(
+				hosts
+					.entry(host)
+					.or_insert_with(|| Arc::new(Semaphore::new(1))),
+			);
+			let overall = Arc::clone(&overall);
+			let checker = self.clone();
+			tasks.spawn(async move {
+				let _host = host.acquire_owned().await;
+				let _permit = overall.acquire_owned().await;
+				let status = checker.check(&url).await;
+				(url, status)
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(result) = tasks.join_next().await {
+			if let Ok(result) = result {
+				results.push(result);
+			}
+		}
+		results.sort_by(|a, b| a.0.cmp(&b.0));
+		results
+	}
+
+	// Server errors and failed connections are retried, client errors are final
+	pub async fn check(&self, url: &str) -> LinkStatus {
+		let mut status = self.request(url).await;
+		for _ in 0..self.retries {
+			if !matches!(status, LinkStatus::ServerError(_) | LinkStatus::Failed(_)) {
+				break;
+			}
+			status = self.request(url).await;
+		}
+		status
+	}
+
+	async fn request(&self, url: &str) -> LinkStatus {
+		let Ok(parsed) = Url::parse(url) else {
+			return LinkStatus::Failed("invalid URL".to_string());
+		};
+		let response = match self.client.head(parsed.clone()).send().await {
+			// Some servers refuse HEAD but answer GET just fine
+			Ok(response)
+				if matches!(
+					response.status(),
+					StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+				) =>
+			{
+				self.client.get(parsed.clone()).send().await
+			}
+			response => response,
+		};
+		let response = match response {
+			Ok(response) => response,
+			Err(e) => return LinkStatus::Failed(error_message(&e)),
+		};
+
+		let status = response.status();
+		let code = status.as_u16();
+		if status.is_redirection() {
+			let location = response
+				.headers()
+				.get(LOCATION)
+				.and_then(|value| value.to_str().ok())
+				.map(|location| {
+					parsed
+						.join(location)
+						.map(|u| u.to_string())
+						.unwrap_or_else(|_| location.to_string())
+				})
+				.unwrap_or_default();
+			LinkStatus::Redirect {
+				status: code,
+				location,
+			}
+		} else if status.is_client_error() {
+			LinkStatus::ClientError(code)
+		} else if status.is_server_error() {
+			LinkStatus::ServerError(code)
+		} else {
+			LinkStatus::Ok(code)
+		}
+	}
+}
+
+fn error_message(error: &reqwest::Error) -> String {
+	if error.is_timeout() {
+		"timed out".to_string()
+	} else if error.is_connect() {
+		"connection failed".to_string()
+	} else {
+		error.to_string()
+	}
+}
+
+pub fn print_report(results: &[(String, LinkStatus)], sources: &BTreeMap<String, Vec<PathBuf>>) {
+	for (url, status) in results {
+		let detail = match status {
+			LinkStatus::Ok(code)
+			| LinkStatus::ClientError(code)
+			| LinkStatus::ServerError(code) => code.to_string(),
+			LinkStatus::Redirect { status, location } => format!("{} -> {}", status, location),
+			LinkStatus::Failed(reason) => reason.clone(),
+		};
+		println!("{:<8}  {}  ({})", status.label(), url, detail);
+		if status.is_broken() {
+			for path in sources.get(url).into_iter().flatten() {
+				println!("          in {}", path.display());
+			}
+		}
+	}
+
+	let broken = results.iter().filter(|(_, s)| s.is_broken()).count();
+	let redirects = results
+		.iter()
+		.filter(|(_, s)| matches!(s, LinkStatus::Redirect { .. }))
+		.count();
+	println!(
+		"\n{} link(s) checked, {} broken, {} redirected",
+		results.len(),
+		broken,
+		redirects
+	);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use wiremock::matchers::{method, path};
+	use wiremock::{Mock, MockServer, ResponseTemplate};
+
+	fn checker(retries: u32) -> LinkChecker {
+		LinkChecker::new(&LinkCheckConfig {
+			concurrency: 4,
+			timeout_secs: 2,
+			retries,
+		})
+		.unwrap()
+	}
+
+	async fn mock(server: &MockServer, verb: &str, route: &str, response: ResponseTemplate) {
+		Mock::given(method(verb))
+			.and(path(route))
+			.respond_with(response)
+			.mount(server)
+			.await;
+	}
+
+	#[tokio::test]
+	async fn test_response_types() {
+		let server = MockServer::start().await;
+		mock(&server, "HEAD", "/ok", ResponseTemplate::new(200)).await;
+		mock(
+			&server,
+			"HEAD",
+			"/moved",
+			ResponseTemplate::new(301).insert_header("Location", "/ok"),
+		)
+		.await;
+		mock(&server, "HEAD", "/missing", ResponseTemplate::new(404)).await;
+		mock(&server, "HEAD", "/broken", ResponseTemplate::new(503)).await;
+		mock(&server, "HEAD", "/get-only", ResponseTemplate::new(405)).await;
+		mock(&server, "GET", "/get-only", ResponseTemplate::new(200)).await;
+
+		let url = |route: &str| format!("{}{}", server.uri(), route);
+		let results = checker(0)
+			.check_all(
+				["/ok", "/moved", "/missing", "/broken", "/get-only"]
+					.iter()
+					.map(|route| url(route))
+					.collect(),
+			)
+			.await;
+		let status = |route: &str| {
+			results
+				.iter()
+				.find(|(u, _)| *u == url(route))
+				.map(|(_, s)| s.clone())
+				.unwrap()
+		};
+
+		assert_eq!(status("/ok"), LinkStatus::Ok(200));
+		assert_eq!(
+			status("/moved"),
+			LinkStatus::Redirect {
+				status: 301,
+				location: url("/ok"),
+			}
+		);
+		assert_eq!(status("/missing"), LinkStatus::ClientError(404));
+		assert_eq!(status("/broken"), LinkStatus::ServerError(503));
+		assert_eq!(status("/get-only"), LinkStatus::Ok(200));
+		assert!(!status("/moved").is_broken());
+		assert!(status("/missing").is_broken());
+
+		// Sorted by URL
+		let urls: Vec<&String> = results.iter().map(|(u, _)| u).collect();
+		let mut sorted = urls.clone();
+		sorted.sort();
+		assert_eq!(urls, sorted);
+	}
+
+	#[tokio::test]
+	async fn test_retries() {
+		let server = MockServer::start().await;
+		Mock::given(method("HEAD"))
+			.and(path("/flaky"))
+			.respond_with(ResponseTemplate::new(500))
+			.up_to_n_times(1)
+			.mount(&server)
+			.await;
+		mock(&server, "HEAD", "/flaky", ResponseTemplate::new(200)).await;
+		let url = format!("{}/flaky", server.uri());
+		assert_eq!(checker(1).check(&url).await, LinkStatus::Ok(200));
+
+		// Client errors are not worth retrying
+		let server = MockServer::start().await;
+		Mock::given(method("HEAD"))
+			.and(path("/gone"))
+			.respond_with(ResponseTemplate::new(410))
+			.expect(1)
+			.mount(&server)
+			.await;
+		let url = format!("{}/gone", server.uri());
+		assert_eq!(checker(3).check(&url).await, LinkStatus::ClientError(410));
+	}
+
+	#[tokio::test]
+	async fn test_connection_failure() {
+		// Bind and drop a listener so the port is known to be closed
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}/", listener.local_addr().unwrap());
+		drop(listener);
+
+		assert!(matches!(
+			checker(1).check(&url).await,
+			LinkStatus::Failed(_)
+		));
+		assert_eq!(
+			checker(0).check("not a url").await,
+			LinkStatus::Failed("invalid URL".to_string())
+		);
+	}
+}
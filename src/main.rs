@@ -1,18 +1,52 @@
+mod buildlog;
+mod cache;
+mod check;
 mod cli;
+mod collections;
 mod config;
 mod content;
+mod diagnostics;
+mod diff;
 mod export;
 mod generator;
+mod graph;
+mod ignore;
+mod linkcheck;
+mod minify;
+mod order;
+mod scaffold;
+mod schema;
 mod server;
+mod summary;
 mod templates;
+mod urls;
+mod utils;
 
 use anyhow::Result;
 use clap::Parser;
+use tracing_subscriber::EnvFilter;
 
 use crate::cli::Cli;
 
 #[tokio::main]
 async fn main() -> Result<()> {
 	let cli = Cli::parse();
+
+	// RUST_LOG takes precedence over --verbose/--quiet
+	let level = if cli.verbose {
+		"debug"
+	} else if cli.quiet {
+		"warn"
+	} else {
+		"info"
+	};
+	let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+	tracing_subscriber::fmt()
+		.with_env_filter(filter)
+		.with_writer(std::io::stderr)
+		.with_target(false)
+		.without_time()
+		.init();
+
 	cli.run().await
 }
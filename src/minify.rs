@@ -0,0 +1,155 @@
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub struct MinifiedCss {
+	pub css: String,
+	// (generated column, original line, original column) for every kept source line
+	mappings: Vec<(usize, usize, usize)>,
+}
+
+impl MinifiedCss {
+	/// Build a v3 source map pointing back at the original stylesheet
+	pub fn source_map(&self, file: &str, source_name: &str, source: &str) -> String {
+		let mut mappings = String::new();
+		let mut prev = (0i64, 0i64, 0i64);
+
+		// Everything is on a single generated line, so segments are only comma separated
+		for (idx, &(gen_col, line, col)) in self.mappings.iter().enumerate() {
+			if idx > 0 {
+				mappings.push(',');
+			}
+			let (gen_col, line, col) = (gen_col as i64, line as i64, col as i64);
+			encode_vlq(&mut mappings, gen_col - prev.0);
+			encode_vlq(&mut mappings, 0);
+			encode_vlq(&mut mappings, line - prev.1);
+			encode_vlq(&mut mappings, col - prev.2);
+			prev = (gen_col, line, col);
+		}
+
+		serde_json::json!({
+			"version": 3,
+			"file": file,
+			"sources": [source_name],
+			"sourcesContent": [source],
+			"names": [],
+			"mappings": mappings,
+		})
+		.to_string()
+	}
+}
+
+pub fn minify_css(source: &str) -> MinifiedCss {
+	let mut css = String::new();
+	let mut mappings = Vec::new();
+	let mut in_comment = false;
+
+	for (line_idx, line) in source.lines().enumerate() {
+		// Strip comments, which may span several lines
+		let mut stripped = String::new();
+		let mut rest = line;
+		loop {
+			if in_comment {
+				match rest.find("*/") {
+					Some(end) => {
+						rest = &rest[end + 2..];
+						in_comment = false;
+					}
+					None => break,
+				}
+			} else {
+				match rest.find("/*") {
+					Some(start) => {
+						stripped.push_str(&rest[..start]);
+						rest = &rest[start + 2..];
+						in_comment = true;
+					}
+					None => {
+						stripped.push_str(rest);
+						break;
+					}
+				}
+			}
+		}
+
+		let compacted = compact(&stripped);
+		if compacted.is_empty() {
+			continue;
+		}
+
+		// A line break separates values like `margin: 0\n auto`, keep it as a space where needed
+		if !css.is_empty()
+			&& !css.ends_with(|c: char| "{};:,>".contains(c))
+			&& !compacted.starts_with(|c: char| "{};,>".contains(c))
+		{
+			css.push(' ');
+		}
+		let col = line.len() - line.trim_start().len();
+		mappings.push((css.len(), line_idx, col));
+		css.push_str(&compacted);
+	}
+
+	MinifiedCss { css, mappings }
+}
+
+fn compact(line: &str) -> String {
+	let mut out = String::new();
+
+	for ch in line.trim().chars() {
+		if ch.is_whitespace() {
+			if out.ends_with(|c: char| c.is_whitespace() || "{};:,>".contains(c)) {
+				continue;
+			}
+			out.push(' ');
+		} else {
+			// Keep the space before ':' so descendant pseudo selectors survive
+			if "{};,>".contains(ch) && out.ends_with(' ') {
+				out.pop();
+			}
+			out.push(ch);
+		}
+	}
+
+	out
+}
+
+fn encode_vlq(out: &mut String, value: i64) {
+	let mut vlq = if value < 0 {
+		((-value) << 1) | 1
+	} else {
+		value << 1
+	};
+
+	loop {
+		let mut digit = vlq & 0b11111;
+		vlq >>= 5;
+		if vlq > 0 {
+			digit |= 0b100000;
+		}
+		out.push(BASE64_CHARS[digit as usize] as char);
+		if vlq == 0 {
+			break;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_minify_css_with_mappings() {
+		let source = "/* Header */\n.header {\n    margin: 0 auto;\n}\n";
+		let minified = minify_css(source);
+		assert_eq!(minified.css, ".header{margin:0 auto;}");
+		assert_eq!(minified.mappings, vec![(0, 1, 0), (8, 2, 4), (22, 3, 0)]);
+
+		let minified = minify_css(".box {\n  margin: 0\n  auto;\n}\n.a,\n.b\n{ color: red }\n");
+		assert_eq!(minified.css, ".box{margin:0 auto;}.a,.b{color:red}");
+		assert_eq!(minified.mappings[2], (14, 2, 2));
+
+		let mut vlq = String::new();
+		for value in [0, 1, -1, 16] {
+			encode_vlq(&mut vlq, value);
+		}
+		assert_eq!(vlq, "ACDgB");
+	}
+}
@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::content::Document;
+
+// Documents without an order of their own or a matching pattern sort after everything else
+pub const DEFAULT_ORDER: f64 = 999.0;
+
+// `[navigation.order]` patterns, tried from the lowest priority value up
+#[derive(Debug, Clone)]
+pub struct NavOrder {
+	set: GlobSet,
+	priorities: Vec<u32>,
+}
+
+impl NavOrder {
+	pub fn new(patterns: &BTreeMap<String, u32>) -> Result<Self> {
+		let mut patterns: Vec<(&String, u32)> = patterns.iter().map(|(p, o)| (p, *o)).collect();
+		patterns.sort_by_key(|(_, priority)| *priority);
+
+		let mut builder = GlobSetBuilder::new();
+		let mut priorities = Vec::new();
+		for (pattern, priority) in patterns {
+			builder.add(
+				GlobBuilder::new(pattern.trim_start_matches('/'))
+					.literal_separator(true)
+					.build()
+					.with_context(|| format!("Invalid navigation.order pattern '{}'", pattern))?,
+			);
+			priorities.push(priority);
+		}
+		Ok(Self {
+			set: builder.build()?,
+			priorities,
+		})
+	}
+
+	// Paths are relative to the source directory
+	pub fn priority(&self, path: &Path) -> Option<u32> {
+		self.set
+			.matches(path)
+			.into_iter()
+			.min()
+			.map(|first| self.priorities[first])
+	}
+}
+
+// Frontmatter order first, then the first matching pattern. NaN sorts last alongside infinity.
+pub fn resolve_nav_order(doc: &Document, nav_order: &NavOrder) -> f64 {
+	match doc.frontmatter.order {
+		Some(order) if order.is_nan() => f64::INFINITY,
+		Some(order) => order,
+		None => nav_order
+			.priority(&doc.relative_path)
+			.map_or(DEFAULT_ORDER, f64::from),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::content::Frontmatter;
+	use std::path::PathBuf;
+
+	fn compile(patterns: &[(&str, u32)]) -> NavOrder {
+		NavOrder::new(&patterns.iter().map(|(p, o)| (p.to_string(), *o)).collect()).unwrap()
+	}
+
+	fn doc(path: &str, order: Option<f64>) -> Document {
+		Document {
+			frontmatter: Frontmatter {
+				order,
+				..Default::default()
+			},
+			path: PathBuf::from(path),
+			relative_path: PathBuf::from(path),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_wildcards() {
+		let order = compile(&[
+			("getting-started/*", 1),
+			("api/**", 2),
+			("changelog.md", 100),
+			("*.md", 50),
+		]);
+		assert_eq!(
+			order.priority(Path::new("getting-started/install.md")),
+			Some(1)
+		);
+		assert_eq!(
+			order.priority(Path::new("getting-started/deep/more.md")),
+			None
+		);
+		assert_eq!(order.priority(Path::new("api/index.md")), Some(2));
+		assert_eq!(order.priority(Path::new("api/v1/users/list.md")), Some(2));
+		assert_eq!(order.priority(Path::new("index.md")), Some(50));
+		assert_eq!(order.priority(Path::new("guide/setup.md")), None);
+
+		assert!(NavOrder::new(&BTreeMap::from([("a[".to_string(), 1)])).is_err());
+	}
+
+	#[test]
+	fn test_overlapping_patterns() {
+		// The lowest priority value wins, whatever order the table lists them in
+		let order = compile(&[("changelog.md", 100), ("*.md", 50), ("**", 200)]);
+		assert_eq!(order.priority(Path::new("changelog.md")), Some(50));
+		assert_eq!(order.priority(Path::new("guide/setup.md")), Some(200));
+
+		let order = compile(&[("api/**", 2), ("api/internal/**", 1)]);
+		assert_eq!(order.priority(Path::new("api/internal/db.md")), Some(1));
+		assert_eq!(order.priority(Path::new("api/users.md")), Some(2));
+	}
+
+	#[test]
+	fn test_priority_hierarchy() {
+		let order = compile(&[("api/**", 2), ("changelog.md", 100)]);
+		assert_eq!(resolve_nav_order(&doc("api/users.md", None), &order), 2.0);
+		assert_eq!(
+			resolve_nav_order(&doc("api/users.md", Some(0.5)), &order),
+			0.5
+		);
+		assert_eq!(
+			resolve_nav_order(&doc("changelog.md", Some(f64::NAN)), &order),
+			f64::INFINITY
+		);
+		assert_eq!(
+			resolve_nav_order(&doc("guide/setup.md", None), &order),
+			DEFAULT_ORDER
+		);
+	}
+}
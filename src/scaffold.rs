@@ -0,0 +1,135 @@
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::config::{Config, SectionConfig};
+
+pub struct ProjectTemplate {
+	pub name: &'static str,
+	pub description: &'static str,
+	files: &'static [(&'static str, &'static str)],
+	configure: fn(&mut Config),
+}
+
+pub const TEMPLATES: &[ProjectTemplate] = &[
+	ProjectTemplate {
+		name: "minimal",
+		description: "A single docs/ directory with an index page",
+		files: &[(
+			"docs/index.md",
+			include_str!("../templates/init/minimal/docs/index.md"),
+		)],
+		configure: configure_minimal,
+	},
+	ProjectTemplate {
+		name: "full",
+		description: "Versioned docs with getting-started and reference sections",
+		files: &[
+			(
+				"docs/index.md",
+				include_str!("../templates/init/full/docs/index.md"),
+			),
+			(
+				"docs/latest/index.md",
+				include_str!("../templates/init/full/docs/latest/index.md"),
+			),
+			(
+				"docs/latest/getting-started/installation.md",
+				include_str!("../templates/init/full/docs/latest/getting-started/installation.md"),
+			),
+			(
+				"docs/latest/reference/configuration.md",
+				include_str!("../templates/init/full/docs/latest/reference/configuration.md"),
+			),
+		],
+		configure: configure_full,
+	},
+	ProjectTemplate {
+		name: "api",
+		description: "API reference with examples, glossary and changelog",
+		files: &[
+			(
+				"docs/index.md",
+				include_str!("../templates/init/api/docs/index.md"),
+			),
+			(
+				"docs/examples/index.md",
+				include_str!("../templates/init/api/docs/examples/index.md"),
+			),
+			(
+				"docs/glossary/index.md",
+				include_str!("../templates/init/api/docs/glossary/index.md"),
+			),
+			(
+				"docs/changelog/index.md",
+				include_str!("../templates/init/api/docs/changelog/index.md"),
+			),
+		],
+		configure: configure_api,
+	},
+];
+
+pub fn find_template(name: &str) -> Result<&'static ProjectTemplate> {
+	match TEMPLATES.iter().find(|t| t.name == name) {
+		Some(template) => Ok(template),
+		None => {
+			let names: Vec<_> = TEMPLATES.iter().map(|t| t.name).collect();
+			bail!(
+				"Unknown template '{}', available: {}",
+				name,
+				names.join(", ")
+			)
+		}
+	}
+}
+
+impl ProjectTemplate {
+	pub fn write(&self, dir: &Path) -> Result<()> {
+		for (path, content) in self.files {
+			let target = dir.join(path);
+			if let Some(parent) = target.parent() {
+				fs::create_dir_all(parent)?;
+			}
+			fs::write(target, content)?;
+		}
+
+		let mut config = Config::default();
+		(self.configure)(&mut config);
+		config.save(&dir.join("rum.toml"))
+	}
+}
+
+fn configure_minimal(config: &mut Config) {
+	config.site.versions = vec![];
+	config.site.default_version = None;
+	config.navigation.breadcrumbs = false;
+}
+
+fn configure_full(config: &mut Config) {
+	config
+		.search
+		.index_fields
+		.extend(["tags".to_string(), "description".to_string()]);
+	config.lint.warn_orphans = true;
+}
+
+fn configure_api(config: &mut Config) {
+	config.site.title = "API Reference".to_string();
+	config.site.versions = vec![];
+	config.site.default_version = None;
+	config.navigation.sections = vec![
+		section("examples", "Examples", 1),
+		section("glossary", "Glossary", 2),
+		section("changelog", "Changelog", 3),
+	];
+	config.search.index_fields.push("tags".to_string());
+}
+
+fn section(dir: &str, title: &str, order: u32) -> SectionConfig {
+	SectionConfig {
+		dir: dir.to_string(),
+		title: title.to_string(),
+		icon: None,
+		order,
+	}
+}
@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Context, Result};
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::Validator;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::content::Frontmatter;
+
+#[derive(Debug, Clone)]
+pub struct FrontmatterError {
+	pub document_path: PathBuf,
+	pub field: String,
+	pub message: String,
+}
+
+// `validation.schema`, compiled once and checked against every document
+pub struct FrontmatterSchema {
+	validator: Validator,
+}
+
+impl fmt::Debug for FrontmatterSchema {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("FrontmatterSchema").finish_non_exhaustive()
+	}
+}
+
+impl FrontmatterSchema {
+	pub fn load(path: &Path) -> Result<Self> {
+		let schema = fs::read_to_string(path)
+			.with_context(|| format!("Failed to read schema {}", path.display()))?;
+		let schema: Value = serde_json::from_str(&schema)
+			.with_context(|| format!("Schema {} is not valid JSON", path.display()))?;
+		let validator = jsonschema::draft7::new(&schema)
+			.map_err(|e| anyhow!("Invalid schema {}: {}", path.display(), e))?;
+		Ok(Self { validator })
+	}
+
+	pub fn validate(
+		&self,
+		document_path: &Path,
+		frontmatter: &Frontmatter,
+	) -> Vec<FrontmatterError> {
+		let mut instance = match serde_json::to_value(frontmatter) {
+			Ok(instance) => instance,
+			Err(e) => {
+				return vec![FrontmatterError {
+					document_path: document_path.to_path_buf(),
+					field: String::new(),
+					message: e.to_string(),
+				}]
+			}
+		};
+		// Unset fields serialize as null, drop them so `required` sees them as missing
+		if let Value::Object(fields) = &mut instance {
+			fields.retain(|_, value| !value.is_null());
+		}
+
+		self.validator
+			.iter_errors(&instance)
+			.map(|error| {
+				let field = match &error.kind {
+					ValidationErrorKind::Required { property } => property
+						.as_str()
+						.map_or_else(|| property.to_string(), str::to_string),
+					_ => error
+						.instance_path
+						.to_string()
+						.trim_start_matches('/')
+						.replace('/', "."),
+				};
+				FrontmatterError {
+					document_path: document_path.to_path_buf(),
+					field,
+					message: error.to_string(),
+				}
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn frontmatter(yaml: &str) -> Frontmatter {
+		serde_yaml::from_str(yaml).unwrap()
+	}
+
+	#[test]
+	fn test_validate() {
+		let schema = FrontmatterSchema::load(
+			&PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+				.join("tests/fixtures/frontmatter.schema.json"),
+		)
+		.unwrap();
+		let path = Path::new("guide.md");
+
+		assert!(schema
+			.validate(path, &frontmatter("title: Guide"))
+			.is_empty());
+
+		let errors = schema.validate(path, &frontmatter("titlee: Guide"));
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].document_path, path);
+		assert_eq!(errors[0].field, "title");
+		assert_eq!(errors[0].message, "\"title\" is a required property");
+
+		let errors = schema.validate(path, &frontmatter("title: ''"));
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].field, "title");
+	}
+}
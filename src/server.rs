@@ -1,137 +1,1131 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use axum::{
-	extract::Path as AxumPath,
-	http::StatusCode,
-	response::{Html, IntoResponse},
-	routing::get,
-	Router,
+	body::{self, Body},
+	extract::{
+		ws::{Message, WebSocket, WebSocketUpgrade},
+		Path as AxumPath, Query, Request, State,
+	},
+	http::{header, StatusCode},
+	middleware::{self, Next},
+	response::{Html, IntoResponse, Redirect, Response},
+	routing::{get, MethodRouter},
+	serve::Listener,
+	Json, Router,
 };
+use base64::{engine::general_purpose::STANDARD, Engine};
 use notify::{RecursiveMode, Watcher};
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tower::ServiceBuilder;
-use tower_http::services::ServeDir;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock as SyncRwLock};
+use std::time::SystemTime;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, watch, RwLock};
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tower::{ServiceBuilder, ServiceExt};
+use tower_http::services::{ServeDir, ServeFile};
+use tracing::{debug, error, info};
 
-use crate::generator::Generator;
+use crate::buildlog::BuildLog;
+use crate::config::Config;
+use crate::content::Document;
+use crate::diagnostics::diagnostic_sink;
+use crate::export::document_slug;
+use crate::generator::{DocumentEntry, Generator, NavigationTree, SearchEntry};
+use crate::utils::{escape_html, rfc3339};
 
+// Appended to every served page, reloads once a burst of rebuild notifications settles. When only
+// stylesheets changed they are fetched again in place, keeping the scroll position and page state.
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>
+(() => {
+	const scheme = location.protocol === "https:" ? "wss" : "ws";
+	const socket = new WebSocket(`${scheme}://${location.host}/__rum_ws`);
+	let timer;
+	let paths = [];
+	socket.addEventListener("message", (event) => {
+		let message;
+		try {
+			message = JSON.parse(event.data);
+		} catch {
+			message = { type: "reload", paths: [] };
+		}
+		// An empty list means the changes are not known, which always reloads the page
+		paths = message.paths.length && paths !== null ? paths.concat(message.paths) : null;
+		clearTimeout(timer);
+		timer = setTimeout(() => {
+			if (paths && paths.every((path) => path.endsWith(".css"))) {
+				for (const link of document.querySelectorAll('link[rel="stylesheet"]')) {
+					const url = new URL(link.href);
+					url.searchParams.set("rum-reload", Date.now());
+					link.href = url.href;
+				}
+			} else {
+				location.reload();
+			}
+			paths = [];
+		}, 200);
+	});
+})();
+</script>
+"#;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum BuildStatus {
+	Idle {
+		last_rebuild: Option<String>,
+		error: Option<String>,
+	},
+	Building,
+}
+
+impl BuildStatus {
+	fn finished<T>(result: &Result<T>) -> Self {
+		BuildStatus::Idle {
+			last_rebuild: Some(rfc3339(SystemTime::now())),
+			error: result.as_ref().err().map(|e| format!("{:#}", e)),
+		}
+	}
+}
+
+// Shared with the API handlers, the generator refreshes it after every rebuild
+#[derive(Clone)]
+struct AppState {
+	search: Arc<SyncRwLock<Vec<SearchEntry>>>,
+	navigation: Arc<SyncRwLock<NavigationTree>>,
+	documents: Arc<SyncRwLock<Vec<Document>>>,
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+	#[serde(default)]
+	q: String,
+	limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct SearchResult<'a> {
+	score: usize,
+	#[serde(flatten)]
+	entry: &'a SearchEntry,
+}
+
+#[derive(Clone)]
 pub struct DevServer {
 	source_dir: PathBuf,
 	port: u16,
 	config: Option<PathBuf>,
+	template_dir: Option<PathBuf>,
+	include_drafts: bool,
+	output_dir: Option<PathBuf>,
+	output_format: String,
 	generator: Arc<RwLock<Option<Generator>>>,
+	// The last build's documents, a change re-parses only the files it touched
+	documents: Arc<SyncRwLock<Vec<Document>>>,
+	// The config file and template directory, a change to either rebuilds with a new generator
+	watched: Arc<SyncRwLock<Vec<PathBuf>>>,
+	reload: Arc<broadcast::Sender<Vec<String>>>,
 }
 
 impl DevServer {
-	pub fn new(source_dir: PathBuf, port: u16, config: Option<PathBuf>) -> Result<Self> {
+	pub fn new(
+		source_dir: PathBuf,
+		port: u16,
+		config: Option<PathBuf>,
+		template_dir: Option<PathBuf>,
+		include_drafts: bool,
+	) -> Result<Self> {
 		let generator = Arc::new(RwLock::new(None));
+		let (reload, _) = broadcast::channel(16);
 
 		Ok(Self {
 			source_dir,
 			port,
 			config,
+			template_dir,
+			include_drafts,
+			output_dir: None,
+			output_format: "text".to_string(),
 			generator,
+			documents: Arc::new(SyncRwLock::new(Vec::new())),
+			watched: Arc::new(SyncRwLock::new(Vec::new())),
+			reload: Arc::new(reload),
 		})
 	}
 
+	// Without one the build goes to a per-port temp directory, removed again on Ctrl+C
+	pub fn with_output_dir(mut self, output_dir: Option<PathBuf>) -> Self {
+		self.output_dir = output_dir;
+		self
+	}
+
+	// How rebuild warnings are reported, see diagnostic_sink
+	pub fn with_output_format(mut self, output_format: String) -> Self {
+		self.output_format = output_format;
+		self
+	}
+
 	pub async fn serve(&self) -> Result<()> {
-		// Create temp output directory
-		let output_dir = std::env::temp_dir().join("rum");
+		// One temp directory per port, so two servers never share an output
+		let output_dir = self
+			.output_dir
+			.clone()
+			.unwrap_or_else(|| std::env::temp_dir().join(format!("rum-{}", self.port)));
 
-		// Initial build
-		let generator = Generator::new(
-			self.source_dir.clone(),
-			output_dir.clone(),
-			self.config.clone(),
-		)?;
+		let state = AppState {
+			search: Arc::new(SyncRwLock::new(Vec::new())),
+			navigation: Arc::new(SyncRwLock::new(NavigationTree::new())),
+			documents: Arc::clone(&self.documents),
+		};
 
-		let gen = generator;
-		gen.build("html").await?;
-		*self.generator.write().await = Some(gen);
+		// Credentials are checked before the first build, a missing ${VAR} fails right away
+		let config = Config::load(self.config.as_deref())?;
+		let credentials = match &config.server.basic_auth {
+			Some(auth) => {
+				Some(STANDARD.encode(format!("{}:{}", auth.username, auth.resolved_password()?)))
+			}
+			None => None,
+		};
 
-		// Get a handle to the current tokio runtime to use inside the watcher thread
-		let rt = tokio::runtime::Handle::current();
+		// Initial build
+		let gen = self.generator(&output_dir, &state)?;
+		gen.build("html").await?;
 
-		let mut watcher = notify::recommended_watcher({
-			let _source_dir = self.source_dir.clone();
-			let generator = Arc::clone(&self.generator);
-			let _output_dir = output_dir.clone();
-			let rt = rt.clone();
+		// Polled through /_status so tooling can wait for rebuilds to settle
+		let (status_tx, status_rx) = watch::channel(BuildStatus::finished(&Ok(())));
 
-			move |event: Result<notify::Event, notify::Error>| {
+		// The watcher thread only forwards events, they are handled one batch at a time below
+		let (events_tx, events_rx) = mpsc::unbounded_channel();
+		let mut watcher =
+			notify::recommended_watcher(move |event: Result<notify::Event, notify::Error>| {
 				if let Ok(event) = event {
-					if event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove() {
-						let generator = Arc::clone(&generator);
-
-						rt.spawn(async move {
-							if let Some(gen) = generator.write().await.take() {
-								let g = gen;
-								if let Err(e) = g.build("html").await {
-									eprintln!("Rebuild error: {}", e);
-								}
-								*generator.write().await = Some(g);
-							}
-						});
-					}
+					// Only fails once the server is shutting down
+					let _ = events_tx.send(event);
 				}
-			}
-		})?;
+			})?;
+		let source_dir = fs::canonicalize(&self.source_dir)?;
+		watcher.watch(&source_dir, RecursiveMode::Recursive)?;
+		self.update_watches(&mut watcher, &gen)?;
+		*self.generator.write().await = Some(gen);
+
+		let events = tokio::spawn(self.clone().handle_events(
+			watcher,
+			events_rx,
+			status_tx,
+			output_dir.clone(),
+			state.clone(),
+		));
 
-		watcher.watch(&self.source_dir, RecursiveMode::Recursive)?;
+		// Setup HTTP server, pages and assets alike come straight from the output directory
+		let html_fallback: MethodRouter =
+			get(serve_html_fallback).with_state((output_dir.clone(), false));
+		let files = ServeDir::new(&output_dir)
+			.append_index_html_on_directories(true)
+			.fallback(html_fallback);
+		let mut app = Router::new()
+			.route(
+				"/_status",
+				get(move || {
+					let status = status_rx.borrow().clone();
+					async move { Json(status) }
+				}),
+			)
+			.route(
+				"/__rum_ws",
+				get(live_reload).with_state(Arc::clone(&self.reload)),
+			)
+			.merge(api_router(state));
+		if let Some(latest) = config.site.root_redirect() {
+			app = app.merge(root_redirect_router(latest));
+		}
+		app = app
+			.fallback_service(files)
+			.layer(ServiceBuilder::new())
+			.layer(middleware::from_fn(inject_live_reload));
 
-		// Setup HTTP server
-		let app = Router::new()
-			.route("/", get(serve_index))
-			.route("/{*path}", get(serve_page))
-			.nest_service("/assets", ServeDir::new(output_dir.join("assets")))
-			.layer(ServiceBuilder::new());
+		// Protect previews with basic auth when configured
+		if let Some(credentials) = credentials {
+			let expected = Arc::new(format!("Basic {}", credentials));
+			app = app.layer(middleware::from_fn_with_state(expected, basic_auth));
+		}
 
 		let addr = format!("0.0.0.0:{}", self.port);
-		let listener = tokio::net::TcpListener::bind(&addr).await?;
+		let listener = TcpListener::bind(&addr).await?;
 
-		println!(
+		info!(
 			"Development server running at http://localhost:{}",
 			self.port
 		);
-		println!("Watching for changes...");
+		info!("Watching for changes...");
 
-		axum::serve(listener, app).await?;
+		axum::serve(listener, app)
+			.with_graceful_shutdown(async {
+				tokio::signal::ctrl_c().await.ok();
+			})
+			.await?;
+		events.abort();
 
+		if self.output_dir.is_none() {
+			if let Err(e) = fs::remove_dir_all(&output_dir) {
+				error!("Failed to remove {}: {}", output_dir.display(), e);
+			}
+		}
 		Ok(())
 	}
 
-	async fn rebuild(&self) -> Result<()> {
-		if let Some(ref mut gen) = *self.generator.write().await {
-			gen.build("html").await?;
+	fn generator(&self, output_dir: &Path, state: &AppState) -> Result<Generator> {
+		Generator::new(
+			self.source_dir.clone(),
+			output_dir.to_path_buf(),
+			self.config.clone(),
+		)?
+		.with_log(BuildLog::default().with_sink(diagnostic_sink(&self.output_format)?))
+		.with_include_drafts(self.include_drafts)
+		.with_search_entries(Arc::clone(&state.search))
+		.with_documents(Arc::clone(&self.documents))
+		.with_navigation(Arc::clone(&state.navigation))
+		.with_template_dir(self.template_dir.as_deref())
+	}
+
+	// The config file, or rum.toml in the working directory like Config::load
+	fn config_file(&self) -> Result<PathBuf> {
+		match &self.config {
+			Some(config) => Ok(config.clone()),
+			None => Ok(std::env::current_dir()?.join("rum.toml")),
 		}
+	}
+
+	// Watches the config file, the generator's template directory and the [assets] files, dropping
+	// the watches the config no longer points to. The source directory is watched already.
+	fn update_watches(&self, watcher: &mut impl Watcher, generator: &Generator) -> Result<()> {
+		let source_dir = fs::canonicalize(&self.source_dir)?;
+		let assets = &generator.config().assets;
+		let wanted: Vec<PathBuf> = [
+			Some(self.config_file()?),
+			generator.template_dir().map(Path::to_path_buf),
+		]
+		.into_iter()
+		.flatten()
+		.chain(
+			assets
+				.extra_css
+				.iter()
+				.chain(&assets.extra_js)
+				.map(PathBuf::from),
+		)
+		.filter_map(|path| fs::canonicalize(path).ok())
+		.collect();
+
+		let mut watched = self.watched.write().unwrap();
+		for path in watched.iter().filter(|path| !wanted.contains(path)) {
+			if !path.starts_with(&source_dir) {
+				let (target, _) = watch_target(path);
+				// Gone along with the directory it watched
+				let _ = watcher.unwatch(&target);
+			}
+		}
+		for path in wanted.iter().filter(|path| !watched.contains(path)) {
+			if !path.starts_with(&source_dir) {
+				let (target, mode) = watch_target(path);
+				watcher.watch(&target, mode)?;
+			}
+		}
+		*watched = wanted;
 		Ok(())
 	}
+
+	async fn handle_events(
+		self,
+		mut watcher: impl Watcher,
+		mut events: mpsc::UnboundedReceiver<notify::Event>,
+		status_tx: watch::Sender<BuildStatus>,
+		output_dir: PathBuf,
+		state: AppState,
+	) {
+		let Ok(source_dir) = fs::canonicalize(&self.source_dir) else {
+			return;
+		};
+		while let Some(batch) = next_batch(&mut events).await {
+			let watched = self.watched.read().unwrap().clone();
+			let reconfigure = batch
+				.iter()
+				.flat_map(|e| e.paths.iter())
+				.any(|path| watched.iter().any(|w| path.starts_with(w)));
+			let changed = changed_paths(&batch, &source_dir);
+			if !reconfigure && changed.is_empty() {
+				continue;
+			}
+			// New and deleted files change every sidebar, edits only touch dependents
+			let full_rebuild = batch.iter().any(|e| !e.kind.is_modify());
+
+			status_tx.send_replace(BuildStatus::Building);
+			let result = if reconfigure {
+				self.reconfigure(&mut watcher, &output_dir, &state).await
+			} else {
+				match self.generator.read().await.as_ref() {
+					Some(g) if full_rebuild => g.build("html").await.map(|_| Vec::new()),
+					Some(g) => g.build_incremental(&changed).await,
+					None => Ok(Vec::new()),
+				}
+			};
+			status_tx.send_replace(BuildStatus::finished(&result));
+			match result {
+				Ok(written) => {
+					for path in written {
+						info!("Rebuilt {}", path.display());
+					}
+					// Nobody listening just means no page is open
+					let _ = self.reload.send(reload_paths(&batch, &source_dir));
+				}
+				Err(e) => error!("Rebuild error: {:#}", e),
+			}
+		}
+	}
+
+	// A changed config or template starts over with a new generator, a broken one keeps the old
+	async fn reconfigure(
+		&self,
+		watcher: &mut impl Watcher,
+		output_dir: &Path,
+		state: &AppState,
+	) -> Result<Vec<PathBuf>> {
+		info!("Config, templates or assets changed, rebuilding everything");
+		let gen = self.generator(output_dir, state)?;
+		gen.build("html").await?;
+		self.update_watches(watcher, &gen)?;
+		*self.generator.write().await = Some(gen);
+		Ok(Vec::new())
+	}
 }
 
-async fn serve_index() -> impl IntoResponse {
-	let output_dir = std::env::temp_dir().join("rum");
-	let index_path = output_dir.join("index.html");
+// Waits for the next change along with the events queued behind it, a single save tends to arrive
+// as several events and they are built together. Events that touch no file are dropped.
+pub async fn next_batch(
+	events: &mut mpsc::UnboundedReceiver<notify::Event>,
+) -> Option<Vec<notify::Event>> {
+	loop {
+		let mut batch = vec![events.recv().await?];
+		while let Ok(event) = events.try_recv() {
+			batch.push(event);
+		}
+		batch.retain(|e| e.kind.is_modify() || e.kind.is_create() || e.kind.is_remove());
+		if !batch.is_empty() {
+			return Some(batch);
+		}
+	}
+}
+
+// Each file under `dir` the batch touched, once
+pub fn changed_paths(batch: &[notify::Event], dir: &Path) -> Vec<PathBuf> {
+	let mut changed: Vec<PathBuf> = batch
+		.iter()
+		.flat_map(|e| e.paths.iter())
+		.filter(|path| path.starts_with(dir))
+		.cloned()
+		.collect();
+	changed.sort();
+	changed.dedup();
+	changed
+}
 
-	if index_path.exists() {
-		match tokio::fs::read_to_string(&index_path).await {
-			Ok(content) => Html(content).into_response(),
-			Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read file").into_response(),
+// Directories are watched whole, a file through its directory since editors often save by
+// replacing the file, which would end a watch on the file itself
+fn watch_target(path: &Path) -> (PathBuf, RecursiveMode) {
+	match path.parent() {
+		Some(parent) if !path.is_dir() => (parent.to_path_buf(), RecursiveMode::NonRecursive),
+		_ => (path.to_path_buf(), RecursiveMode::Recursive),
+	}
+}
+
+// Serves an existing build as is, with no rebuilding or live reload
+pub struct StaticServer {
+	dir: PathBuf,
+	port: u16,
+	tls: Option<(PathBuf, PathBuf)>,
+	directory_listing: bool,
+}
+
+impl StaticServer {
+	pub fn new(dir: PathBuf, port: u16) -> Self {
+		Self {
+			dir,
+			port,
+			tls: None,
+			directory_listing: false,
 		}
-	} else {
-		(StatusCode::NOT_FOUND, "Not found").into_response()
+	}
+
+	pub fn with_directory_listing(mut self, directory_listing: bool) -> Self {
+		self.directory_listing = directory_listing;
+		self
+	}
+
+	pub fn with_tls(mut self, cert: PathBuf, key: PathBuf) -> Self {
+		self.tls = Some((cert, key));
+		self
+	}
+
+	pub async fn serve(&self) -> Result<()> {
+		if !self.dir.is_dir() {
+			bail!(
+				"{} does not exist, run `rum build` first",
+				self.dir.display()
+			);
+		}
+		let app = static_router(&self.dir, self.directory_listing);
+		let listener = TcpListener::bind(format!("0.0.0.0:{}", self.port)).await?;
+		let shutdown = async {
+			tokio::signal::ctrl_c().await.ok();
+		};
+
+		match &self.tls {
+			Some((cert, key)) => {
+				let acceptor = TlsAcceptor::from(Arc::new(tls_config(cert, key)?));
+				info!(
+					"Serving {} at https://localhost:{}",
+					self.dir.display(),
+					self.port
+				);
+				axum::serve(TlsListener::new(listener, acceptor)?, app)
+					.with_graceful_shutdown(shutdown)
+					.await?;
+			}
+			None => {
+				info!(
+					"Serving {} at http://localhost:{}",
+					self.dir.display(),
+					self.port
+				);
+				axum::serve(listener, app)
+					.with_graceful_shutdown(shutdown)
+					.await?;
+			}
+		}
+		Ok(())
+	}
+}
+
+// JSON endpoints for a client-side frontend, html builds write the same data to api/
+fn api_router(state: AppState) -> Router {
+	Router::new()
+		.route("/api/search", get(search))
+		.route("/api/navigation", get(navigation))
+		.route("/api/documents", get(documents))
+		.route("/api/documents/{slug}", get(document))
+		.with_state(state)
+}
+
+// `/` goes to the newest version's index, like the index.html an html build writes
+fn root_redirect_router(latest: &str) -> Router {
+	let target = format!("/{}/index.html", latest);
+	Router::new().route(
+		"/",
+		get(move || {
+			let redirect = Redirect::temporary(&target);
+			async move { redirect }
+		}),
+	)
+}
+
+// ServeDir answers Range requests itself, anything it cannot find gets the build's 404.html
+fn static_router(dir: &Path, directory_listing: bool) -> Router {
+	let html_fallback: MethodRouter =
+		get(serve_html_fallback).with_state((dir.to_path_buf(), directory_listing));
+	Router::new().fallback_service(
+		ServeDir::new(dir)
+			.append_index_html_on_directories(true)
+			.fallback(html_fallback),
+	)
+}
+
+fn tls_config(cert: &Path, key: &Path) -> Result<ServerConfig> {
+	let certs = CertificateDer::pem_file_iter(cert)
+		.and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+		.with_context(|| format!("Failed to read certificate {}", cert.display()))?;
+	if certs.is_empty() {
+		bail!("No certificate found in {}", cert.display());
+	}
+	let key = PrivateKeyDer::from_pem_file(key)
+		.with_context(|| format!("Failed to read private key {}", key.display()))?;
+
+	// ring is what reqwest already pulls in, naming it avoids relying on a process-wide default
+	let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+		.with_safe_default_protocol_versions()?
+		.with_no_client_auth()
+		.with_single_cert(certs, key)
+		.context("Certificate and private key do not match")?;
+	config.alpn_protocols = vec![b"http/1.1".to_vec()];
+	Ok(config)
+}
+
+// Handshakes run on their own tasks so a slow client cannot hold up the next connection
+struct TlsListener {
+	addr: SocketAddr,
+	streams: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+}
+
+impl TlsListener {
+	fn new(listener: TcpListener, acceptor: TlsAcceptor) -> Result<Self> {
+		let addr = listener.local_addr()?;
+		let (tx, streams) = mpsc::channel(64);
+		tokio::spawn(async move {
+			let mut listener = listener;
+			loop {
+				// axum's own accept already retries and backs off on errors
+				let (stream, peer) = Listener::accept(&mut listener).await;
+				// The server has shut down and dropped its end
+				if tx.is_closed() {
+					break;
+				}
+				let (acceptor, tx) = (acceptor.clone(), tx.clone());
+				tokio::spawn(async move {
+					match acceptor.accept(stream).await {
+						Ok(stream) => {
+							let _ = tx.send((stream, peer)).await;
+						}
+						Err(e) => debug!("TLS handshake with {} failed: {}", peer, e),
+					}
+				});
+			}
+		});
+		Ok(Self { addr, streams })
 	}
 }
 
-async fn serve_page(AxumPath(path): AxumPath<String>) -> impl IntoResponse {
-	let output_dir = std::env::temp_dir().join("rum");
-	let page_path = output_dir.join(&path);
+impl Listener for TlsListener {
+	type Io = TlsStream<TcpStream>;
+	type Addr = SocketAddr;
 
-	if page_path.exists() && page_path.is_file() {
-		match tokio::fs::read_to_string(&page_path).await {
-			Ok(content) => Html(content).into_response(),
-			Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read file").into_response(),
+	async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+		match self.streams.recv().await {
+			Some(connection) => connection,
+			// Only once the server has shut down, nothing is waiting on this anymore
+			None => std::future::pending().await,
 		}
+	}
+
+	fn local_addr(&self) -> io::Result<Self::Addr> {
+		Ok(self.addr)
+	}
+}
+
+// Looks at every byte whatever the first mismatch, so response times don't give the credentials
+// away a character at a time. Only the length can leak, and that of a base64 header says little.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn basic_auth(State(expected): State<Arc<String>>, request: Request, next: Next) -> Response {
+	// Assets carry nothing sensitive and are requested without credentials by some browsers
+	if request.uri().path().starts_with("/assets/") {
+		return next.run(request).await;
+	}
+
+	let authorized = request
+		.headers()
+		.get(header::AUTHORIZATION)
+		.and_then(|value| value.to_str().ok())
+		.is_some_and(|value| constant_time_eq(value.as_bytes(), expected.as_bytes()));
+
+	if authorized {
+		next.run(request).await
 	} else {
-		(StatusCode::NOT_FOUND, "Not found").into_response()
+		(
+			StatusCode::UNAUTHORIZED,
+			[(header::WWW_AUTHENTICATE, "Basic realm=\"rum preview\"")],
+			"Unauthorized",
+		)
+			.into_response()
+	}
+}
+
+// GET /api/search?q=install+cargo&limit=5, best matches first
+async fn search(State(state): State<AppState>, Query(query): Query<SearchQuery>) -> Response {
+	let terms: Vec<String> = query.q.split_whitespace().map(str::to_lowercase).collect();
+	let entries = state.search.read().unwrap();
+	let mut results: Vec<SearchResult> = entries
+		.iter()
+		.map(|entry| SearchResult {
+			score: entry.score(&terms),
+			entry,
+		})
+		.filter(|result| result.score > 0)
+		.collect();
+	results.sort_by_key(|result| Reverse(result.score));
+	results.truncate(query.limit.unwrap_or(10));
+	Json(results).into_response()
+}
+
+// GET /api/navigation, the sidebar tree as the last build left it
+async fn navigation(State(state): State<AppState>) -> Response {
+	Json(&*state.navigation.read().unwrap()).into_response()
+}
+
+// GET /api/documents, title, slug, path, version, tags and date of every page
+async fn documents(State(state): State<AppState>) -> Response {
+	let documents = state.documents.read().unwrap();
+	let entries: Vec<DocumentEntry> = documents.iter().map(DocumentEntry::new).collect();
+	Json(entries).into_response()
+}
+
+// GET /api/documents/guide-setup, the whole document. A trailing .json is accepted too, matching
+// the files the build writes to api/documents/.
+async fn document(State(state): State<AppState>, AxumPath(slug): AxumPath<String>) -> Response {
+	let slug = slug.strip_suffix(".json").unwrap_or(&slug);
+	let documents = state.documents.read().unwrap();
+	match documents.iter().find(|doc| document_slug(doc) == slug) {
+		Some(doc) => Json(doc).into_response(),
+		None => StatusCode::NOT_FOUND.into_response(),
+	}
+}
+
+async fn live_reload(
+	ws: WebSocketUpgrade,
+	State(reload): State<Arc<broadcast::Sender<Vec<String>>>>,
+) -> Response {
+	let mut rebuilds = reload.subscribe();
+	ws.on_upgrade(move |mut socket: WebSocket| async move {
+		loop {
+			// A lagging receiver lost the changed paths, so it asks for a full reload
+			let paths = match rebuilds.recv().await {
+				Ok(paths) => paths,
+				Err(broadcast::error::RecvError::Lagged(_)) => Vec::new(),
+				Err(broadcast::error::RecvError::Closed) => break,
+			};
+			let message = serde_json::json!({ "type": "reload", "paths": paths }).to_string();
+			if socket.send(Message::Text(message.into())).await.is_err() {
+				break;
+			}
+		}
+	})
+}
+
+// Changed files of a rebuild as sent to the browser, relative to the source directory where
+// they are inside it
+fn reload_paths(batch: &[notify::Event], source_dir: &Path) -> Vec<String> {
+	let mut paths: Vec<String> = batch
+		.iter()
+		.flat_map(|e| e.paths.iter())
+		.map(|path| {
+			path.strip_prefix(source_dir)
+				.unwrap_or(path)
+				.to_string_lossy()
+				.replace('\\', "/")
+		})
+		.collect();
+	paths.sort();
+	paths.dedup();
+	paths
+}
+
+async fn inject_live_reload(request: Request, next: Next) -> Response {
+	let response = next.run(request).await;
+	// The 404 page reloads too, so it picks up the page once it exists
+	let is_page = matches!(response.status(), StatusCode::OK | StatusCode::NOT_FOUND)
+		&& response
+			.headers()
+			.get(header::CONTENT_TYPE)
+			.and_then(|value| value.to_str().ok())
+			.is_some_and(|value| value.starts_with("text/html"));
+	if !is_page {
+		return response;
+	}
+
+	let (mut parts, body) = response.into_parts();
+	let mut html = match body::to_bytes(body, usize::MAX).await {
+		Ok(bytes) => bytes.to_vec(),
+		Err(e) => {
+			error!("Failed to read page for live reload: {}", e);
+			return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read page").into_response();
+		}
+	};
+	html.extend_from_slice(LIVE_RELOAD_SCRIPT.as_bytes());
+	parts.headers.remove(header::CONTENT_LENGTH);
+	Response::from_parts(parts, Body::from(html))
+}
+
+// Extensionless paths like /guide/setup resolve to guide/setup.html, directories without an
+// index.html get a listing when that is turned on
+async fn serve_html_fallback(
+	State((output_dir, directory_listing)): State<(PathBuf, bool)>,
+	request: Request,
+) -> Response {
+	let uri_path = request.uri().path().to_string();
+	let path = Path::new(uri_path.trim_start_matches('/'));
+	let is_safe = path.components().all(|c| matches!(c, Component::Normal(_)));
+	let is_page = path.extension().is_none() && path.components().next().is_some() && is_safe;
+
+	if directory_listing && is_safe && uri_path.ends_with('/') {
+		if let Ok(listing) = render_directory_listing(&output_dir.join(path), &uri_path) {
+			return Html(listing).into_response();
+		}
+	}
+
+	if is_page {
+		let page = output_dir.join(path).with_extension("html");
+		if page.is_file() {
+			let Ok(response) = ServeFile::new(page).oneshot(request).await;
+			return response.into_response();
+		}
+	}
+
+	// Same page a static host would serve, plain text only if the build has none
+	match tokio::fs::read_to_string(output_dir.join("404.html")).await {
+		Ok(page) => (StatusCode::NOT_FOUND, Html(page)).into_response(),
+		Err(_) => (StatusCode::NOT_FOUND, "Not found").into_response(),
+	}
+}
+
+// Subdirectories first, then pages by the text of their <title>
+fn render_directory_listing(dir: &Path, uri_path: &str) -> io::Result<String> {
+	let mut dirs = Vec::new();
+	let mut pages = Vec::new();
+	for entry in fs::read_dir(dir)? {
+		let entry = entry?;
+		let name = entry.file_name().to_string_lossy().to_string();
+		// Dotfiles include the build cache
+		if name.starts_with('.') {
+			continue;
+		}
+		let path = entry.path();
+		if path.is_dir() {
+			dirs.push(name);
+		} else if path.extension().is_some_and(|ext| ext == "html") {
+			let title = fs::read_to_string(&path)
+				.ok()
+				.and_then(|html| page_title(&html))
+				.unwrap_or_else(|| escape_html(&name));
+			pages.push((name, title));
+		}
+	}
+	dirs.sort();
+	pages.sort_by(|a, b| a.1.cmp(&b.1));
+
+	let mut items = String::new();
+	if uri_path != "/" {
+		items.push_str("<li><a href=\"../\">../</a></li>\n");
+	}
+	for name in dirs {
+		let name = escape_html(&name);
+		items.push_str(&format!("<li><a href=\"{0}/\">{0}/</a></li>\n", name));
+	}
+	for (name, title) in pages {
+		items.push_str(&format!(
+			"<li><a href=\"{}\">{}</a></li>\n",
+			escape_html(&name),
+			title
+		));
+	}
+	let uri_path = escape_html(uri_path);
+	Ok(format!(
+		"<!DOCTYPE html>\n<html>\n<head><meta charset=\"UTF-8\"><title>Index of {0}</title></head>\n\
+		 <body>\n<h1>Index of {0}</h1>\n<ul>\n{1}</ul>\n</body>\n</html>\n",
+		uri_path, items
+	))
+}
+
+// Already escaped in the page, so it is used as written
+fn page_title(html: &str) -> Option<String> {
+	let start = html.find("<title>")? + "<title>".len();
+	let end = html[start..].find("</title>")? + start;
+	let title = html[start..end].trim();
+	(!title.is_empty()).then(|| title.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::http::Request;
+
+	#[tokio::test]
+	async fn test_static_router() {
+		let dir = std::env::temp_dir().join(format!("rum-serve-{}", std::process::id()));
+		fs::create_dir_all(dir.join("guide")).unwrap();
+		fs::write(dir.join("index.html"), "<p>Home</p>").unwrap();
+		fs::write(dir.join("guide/setup.html"), "<p>Setup</p>").unwrap();
+		fs::write(dir.join("404.html"), "<p>Lost</p>").unwrap();
+		fs::write(dir.join("logo.bin"), b"0123456789").unwrap();
+
+		let fetch = |uri: &str, range: Option<&str>| {
+			let mut request = Request::builder().uri(uri);
+			if let Some(range) = range {
+				request = request.header(header::RANGE, range);
+			}
+			let router = static_router(&dir, false);
+			async move {
+				let response = router
+					.oneshot(request.body(Body::empty()).unwrap())
+					.await
+					.unwrap();
+				let status = response.status();
+				let body = body::to_bytes(response.into_body(), usize::MAX)
+					.await
+					.unwrap();
+				(status, String::from_utf8_lossy(&body).to_string())
+			}
+		};
+
+		assert_eq!(
+			fetch("/", None).await,
+			(StatusCode::OK, "<p>Home</p>".to_string())
+		);
+		assert_eq!(
+			fetch("/guide/setup", None).await,
+			(StatusCode::OK, "<p>Setup</p>".to_string())
+		);
+		assert_eq!(
+			fetch("/missing.png", None).await,
+			(StatusCode::NOT_FOUND, "<p>Lost</p>".to_string())
+		);
+		assert_eq!(
+			fetch("/logo.bin", Some("bytes=2-5")).await,
+			(StatusCode::PARTIAL_CONTENT, "2345".to_string())
+		);
+
+		fs::remove_dir_all(&dir).ok();
+	}
+	#[tokio::test]
+	async fn test_directory_listing() {
+		let dir = std::env::temp_dir().join(format!("rum-listing-{}", std::process::id()));
+		fs::create_dir_all(dir.join("guide/advanced")).unwrap();
+		fs::create_dir_all(dir.join(".rum-cache")).unwrap();
+		fs::write(
+			dir.join("guide/b.html"),
+			"<title>Alpha &amp; Co - Rum</title>",
+		)
+		.unwrap();
+		fs::write(dir.join("guide/a.html"), "<p>No title</p>").unwrap();
+		fs::write(dir.join("guide/logo.png"), b"png").unwrap();
+		fs::write(dir.join("404.html"), "<p>Lost</p>").unwrap();
+
+		let fetch = |uri: &'static str, directory_listing: bool| {
+			let router = static_router(&dir, directory_listing);
+			async move {
+				let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+				let response = router.oneshot(request).await.unwrap();
+				let status = response.status();
+				let body = body::to_bytes(response.into_body(), usize::MAX)
+					.await
+					.unwrap();
+				(status, String::from_utf8_lossy(&body).to_string())
+			}
+		};
+
+		// Off unless opted in
+		assert_eq!(
+			fetch("/guide/", false).await,
+			(StatusCode::NOT_FOUND, "<p>Lost</p>".to_string())
+		);
+
+		let (status, listing) = fetch("/guide/", true).await;
+		assert_eq!(status, StatusCode::OK);
+		assert!(listing.contains("<title>Index of /guide/</title>"));
+		let items: Vec<_> = listing.lines().filter(|l| l.starts_with("<li>")).collect();
+		assert_eq!(
+			items,
+			vec![
+				"<li><a href=\"../\">../</a></li>",
+				"<li><a href=\"advanced/\">advanced/</a></li>",
+				"<li><a href=\"b.html\">Alpha &amp; Co - Rum</a></li>",
+				"<li><a href=\"a.html\">a.html</a></li>",
+			]
+		);
+
+		let (_, root) = fetch("/", true).await;
+		assert!(root.contains("<a href=\"guide/\">guide/</a>"));
+		assert!(!root.contains("../") && !root.contains(".rum-cache"));
+
+		fs::remove_dir_all(&dir).ok();
+	}
+
+	#[tokio::test]
+	async fn test_config_reload() {
+		let root = std::env::temp_dir().join(format!("rum-reload-{}", std::process::id()));
+		let source = root.join("docs");
+		fs::create_dir_all(&source).unwrap();
+		fs::write(source.join("index.md"), "---\ntitle: Home\n---\n# Home\n").unwrap();
+		let config = root.join("rum.toml");
+		let mut settings = Config::default();
+		settings.site.title = "Before".to_string();
+		settings.save(&config).unwrap();
+
+		// A free port, given up again for the server to bind
+		let port = std::net::TcpListener::bind("127.0.0.1:0")
+			.unwrap()
+			.local_addr()
+			.unwrap()
+			.port();
+		let server = DevServer::new(source, port, Some(config.clone()), None, false)
+			.unwrap()
+			.with_output_dir(Some(root.join("dist")));
+		let handle = tokio::spawn(async move { server.serve().await });
+
+		let page_contains = |needle: &'static str| async move {
+			for _ in 0..100 {
+				let url = format!("http://127.0.0.1:{}/index.html", port);
+				if let Ok(response) = reqwest::get(url).await {
+					if response.text().await.unwrap_or_default().contains(needle) {
+						return true;
+					}
+				}
+				tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+			}
+			false
+		};
+
+		assert!(page_contains("<title>Home - Before</title>").await);
+		settings.site.title = "After".to_string();
+		settings.save(&config).unwrap();
+		assert!(page_contains("<title>Home - After</title>").await);
+
+		handle.abort();
+		fs::remove_dir_all(&root).ok();
+	}
+
+	#[test]
+	fn test_reload_paths() {
+		let source = Path::new("/site/docs");
+		let event =
+			|path: &str| notify::Event::new(notify::EventKind::Any).add_path(PathBuf::from(path));
+		let batch = [
+			event("/site/docs/guide/style.css"),
+			event("/site/theme.css"),
+			event("/site/docs/guide/style.css"),
+		];
+		assert_eq!(
+			reload_paths(&batch, source),
+			["/site/theme.css", "guide/style.css"]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_next_batch() {
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		let event = |kind, path: &str| notify::Event::new(kind).add_path(PathBuf::from(path));
+		let modify = notify::EventKind::Modify(notify::event::ModifyKind::Any);
+		tx.send(event(
+			notify::EventKind::Access(notify::event::AccessKind::Any),
+			"/docs/a.md",
+		))
+		.unwrap();
+		tx.send(event(modify, "/docs/a.md")).unwrap();
+		tx.send(event(modify, "/docs/a.md")).unwrap();
+		tx.send(event(modify, "/site/rum.toml")).unwrap();
+
+		let batch = next_batch(&mut rx).await.unwrap();
+		assert_eq!(batch.len(), 3);
+		assert_eq!(
+			changed_paths(&batch, Path::new("/docs")),
+			[PathBuf::from("/docs/a.md")]
+		);
+		drop(tx);
+		assert!(next_batch(&mut rx).await.is_none());
+	}
+
+	#[tokio::test]
+	async fn test_root_redirect() {
+		let dir = std::env::temp_dir().join(format!("rum-redirect-serve-{}", std::process::id()));
+		fs::create_dir_all(dir.join("v2.0.0")).unwrap();
+		fs::write(dir.join("v2.0.0/index.html"), "<p>Two</p>").unwrap();
+
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+		let app = root_redirect_router("v2.0.0").merge(static_router(&dir, false));
+		tokio::spawn(async move { axum::serve(listener, app).await });
+
+		let response = reqwest::get(format!("http://{}/", addr)).await.unwrap();
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(response.url().path(), "/v2.0.0/index.html");
+		assert_eq!(response.text().await.unwrap(), "<p>Two</p>");
+
+		fs::remove_dir_all(&dir).ok();
+	}
+
+	#[tokio::test]
+	async fn test_api_router() {
+		let mut navigation = NavigationTree::new();
+		navigation.add_path(
+			Path::new("guide/setup.md"),
+			"Setup".to_string(),
+			None,
+			"/guide/setup.html".to_string(),
+		);
+		let mut setup: Document = serde_json::from_value(serde_json::json!({
+			"frontmatter": {"title": "Setup", "tags": ["start"]},
+			"content": "Install it.",
+			"html_content": "<p>Install it.</p>",
+			"toc_html": "",
+			"path": "docs/guide/setup.md",
+			"relative_path": "guide/setup.md",
+			"version": null,
+			"locale": null,
+			"backlinks": [],
+			"links": [],
+			"images": [],
+			"word_count": 2,
+			"reading_time_minutes": 1,
+			"collections": [],
+		}))
+		.unwrap();
+		setup.backlinks.push("Home".to_string());
+		let state = AppState {
+			search: Arc::new(SyncRwLock::new(Vec::new())),
+			navigation: Arc::new(SyncRwLock::new(navigation)),
+			documents: Arc::new(SyncRwLock::new(vec![setup])),
+		};
+
+		let fetch = |uri: &str| {
+			let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+			let router = api_router(state.clone());
+			async move {
+				let response = router.oneshot(request).await.unwrap();
+				let status = response.status();
+				let body = body::to_bytes(response.into_body(), usize::MAX)
+					.await
+					.unwrap();
+				(status, serde_json::from_slice(&body).unwrap_or_default())
+			}
+		};
+
+		let (status, navigation): (_, serde_json::Value) = fetch("/api/navigation").await;
+		assert_eq!(status, StatusCode::OK);
+		let tree: NavigationTree = serde_json::from_value(navigation).unwrap();
+		assert_eq!(tree.items[0].title, "guide");
+		assert_eq!(tree.items[0].children[0].title, "Setup");
+
+		let (_, index) = fetch("/api/documents").await;
+		assert_eq!(
+			index,
+			serde_json::json!([{
+				"title": "Setup",
+				"slug": "guide-setup",
+				"path": "guide/setup.md",
+				"version": null,
+				"tags": ["start"],
+				"date": null,
+			}])
+		);
+
+		for uri in [
+			"/api/documents/guide-setup",
+			"/api/documents/guide-setup.json",
+		] {
+			let (status, doc) = fetch(uri).await;
+			assert_eq!(status, StatusCode::OK);
+			assert_eq!(doc["html_content"], "<p>Install it.</p>");
+			assert_eq!(doc["backlinks"], serde_json::json!(["Home"]));
+		}
+		assert_eq!(
+			fetch("/api/documents/missing").await.0,
+			StatusCode::NOT_FOUND
+		);
 	}
 }
@@ -0,0 +1,245 @@
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::info;
+use walkdir::WalkDir;
+
+use crate::cache::BuildCache;
+use crate::config::Config;
+use crate::content::Document;
+use crate::generator::output_path;
+
+const CATEGORIES: [&str; 8] = [
+	"HTML",
+	"PDF",
+	"EPUB",
+	"Man pages",
+	"LaTeX",
+	"Assets",
+	"Search index",
+	"Other",
+];
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct FileStats {
+	pub files: usize,
+	pub bytes: u64,
+}
+
+impl FileStats {
+	fn add(&mut self, bytes: u64) {
+		self.files += 1;
+		self.bytes += bytes;
+	}
+}
+
+// Counts from one build and how long each phase took, phases keep the order they ran in
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BuildStats {
+	pub documents_collected: usize,
+	pub documents_from_cache: usize,
+	pub backlinks_processed: usize,
+	pub html_pages_written: usize,
+	pub warnings: usize,
+	pub errors: usize,
+	#[serde(serialize_with = "phase_millis")]
+	pub phases: IndexMap<String, Duration>,
+}
+
+impl BuildStats {
+	pub fn record(&mut self, phase: &str, started: Instant) {
+		self.phases.insert(phase.to_string(), started.elapsed());
+	}
+
+	pub fn print(&self) {
+		info!("Build statistics:");
+		for (name, value) in [
+			("Documents", self.documents_collected),
+			("From cache", self.documents_from_cache),
+			("Backlinks", self.backlinks_processed),
+			("HTML pages", self.html_pages_written),
+			("Errors", self.errors),
+		] {
+			info!("  {:<16} {:>8}", format!("{}:", name), value);
+		}
+		for (phase, duration) in &self.phases {
+			info!(
+				"  {:<16} {:>8.1} ms",
+				format!("{}:", phase),
+				duration.as_secs_f64() * 1000.0
+			);
+		}
+	}
+}
+
+// Phase timings go into JSON as fractional milliseconds
+fn phase_millis<S: Serializer>(
+	phases: &IndexMap<String, Duration>,
+	serializer: S,
+) -> Result<S::Ok, S::Error> {
+	serializer.collect_map(
+		phases
+			.iter()
+			.map(|(phase, duration)| (phase, duration.as_secs_f64() * 1000.0)),
+	)
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BuildSummary {
+	pub types: IndexMap<&'static str, FileStats>,
+	pub versions: IndexMap<String, FileStats>,
+	pub duration_ms: u128,
+	pub warnings: usize,
+	pub max_warnings: Option<usize>,
+	pub warnings_as_errors: bool,
+	pub stats: BuildStats,
+}
+
+impl BuildSummary {
+	// Tallies everything in the output directory, pages are attributed to their document's version
+	pub fn collect(
+		output_dir: &Path,
+		documents: &[Document],
+		config: &Config,
+		duration: Duration,
+	) -> Self {
+		let page_versions: HashMap<PathBuf, Option<String>> = documents
+			.iter()
+			.map(|doc| (output_path(doc, config), doc.version.clone()))
+			.collect();
+
+		let mut types: HashMap<&'static str, FileStats> = HashMap::new();
+		let mut versions: HashMap<String, FileStats> = HashMap::new();
+
+		for entry in WalkDir::new(output_dir)
+			.into_iter()
+			.filter_map(|e| e.ok())
+			.filter(|e| e.file_type().is_file())
+		{
+			let bytes = entry.metadata().map(|m| m.len()).unwrap_or_default();
+			let relative = entry
+				.path()
+				.strip_prefix(output_dir)
+				.unwrap_or(entry.path());
+			if BuildCache::is_cache_path(relative) {
+				continue;
+			}
+			types.entry(category(relative)).or_default().add(bytes);
+
+			if let Some(version) = page_versions.get(relative) {
+				let version = version.clone().unwrap_or_else(|| "unversioned".to_string());
+				versions.entry(version).or_default().add(bytes);
+			}
+		}
+
+		let mut versions: Vec<_> = versions.into_iter().collect();
+		versions.sort_by(|a, b| a.0.cmp(&b.0));
+
+		Self {
+			types: CATEGORIES
+				.iter()
+				.filter_map(|name| types.get(name).map(|stats| (*name, *stats)))
+				.collect(),
+			versions: versions.into_iter().collect(),
+			duration_ms: duration.as_millis(),
+			..Default::default()
+		}
+	}
+
+	pub fn print(&self) {
+		info!("Build summary:");
+		for (name, stats) in &self.types {
+			info!("  {:<14} {}", format!("{}:", name), describe(stats));
+		}
+		if !self.versions.is_empty() {
+			info!("Pages by version:");
+			for (version, stats) in &self.versions {
+				info!("  {:<14} {}", format!("{}:", version), describe(stats));
+			}
+		}
+		match (self.max_warnings, self.warnings_as_errors) {
+			(_, true) => info!("Warnings: {} (treated as errors)", self.warnings),
+			(Some(limit), false) => info!("Warnings: {} (max {})", self.warnings, limit),
+			(None, false) => info!("Warnings: {}", self.warnings),
+		}
+		self.stats.print();
+		info!("Finished in {:.2}s", self.duration_ms as f64 / 1000.0);
+	}
+
+	// lint.warnings_as_errors and lint.max_warnings, checked after the summary has been shown
+	pub fn check_warnings(&self) -> Result<()> {
+		if self.warnings_as_errors && self.warnings > 0 {
+			bail!(
+				"Build produced {} warning(s) and lint.warnings_as_errors is set",
+				self.warnings
+			);
+		}
+		if let Some(limit) = self.max_warnings.filter(|limit| self.warnings > *limit) {
+			bail!(
+				"Build exceeded maximum warning count ({}/{})",
+				self.warnings,
+				limit
+			);
+		}
+		Ok(())
+	}
+}
+
+fn category(relative: &Path) -> &'static str {
+	if relative == Path::new("assets/search-index.json") {
+		return "Search index";
+	}
+	if relative.starts_with("assets") {
+		return "Assets";
+	}
+	if relative.starts_with("latex") {
+		return "LaTeX";
+	}
+	if relative.starts_with("man") {
+		return "Man pages";
+	}
+	match relative.extension().and_then(|e| e.to_str()) {
+		Some("html") => "HTML",
+		Some("pdf") => "PDF",
+		Some("epub") => "EPUB",
+		_ => "Other",
+	}
+}
+
+fn describe(stats: &FileStats) -> String {
+	let noun = if stats.files == 1 { "file" } else { "files" };
+	format!("{} {} ({})", stats.files, noun, human_size(stats.bytes))
+}
+
+fn human_size(bytes: u64) -> String {
+	const KB: f64 = 1024.0;
+	let bytes = bytes as f64;
+	if bytes < KB {
+		format!("{} B", bytes)
+	} else if bytes < KB * KB {
+		format!("{:.0} KB", bytes / KB)
+	} else {
+		format!("{:.1} MB", bytes / (KB * KB))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_category_and_size() {
+		assert_eq!(
+			category(Path::new("assets/search-index.json")),
+			"Search index"
+		);
+		assert_eq!(category(Path::new("assets/css/style.css")), "Assets");
+		assert_eq!(category(Path::new("latest/index.html")), "HTML");
+		assert_eq!(human_size(512), "512 B");
+		assert_eq!(human_size(320 * 1024), "320 KB");
+		assert_eq!(human_size(1258291), "1.2 MB");
+	}
+}
@@ -1,30 +1,222 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use chrono::SecondsFormat;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Write};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tera::Tera;
+use tracing::warn;
+use walkdir::WalkDir;
 
-use crate::config::Config;
-use crate::content::Document;
-use crate::generator::NavigationTree;
+use crate::config::{default_date_format, Config};
+use crate::content::{document_locale, translation_key, Document, Frontmatter};
+use crate::generator::{output_path, NavigationItem, NavigationTree};
+use crate::utils::{absolute_url, escape_html, is_valid_lang, slugify};
+
+pub const BASE_TEMPLATE: &str = include_str!("../templates/base.html");
+
+// Everything a page render needs, prev and next are its neighbours within the version
+pub struct PageContext<'a> {
+	pub doc: &'a Document,
+	pub prev: Option<&'a Document>,
+	pub next: Option<&'a Document>,
+	pub all_docs: &'a [&'a Document],
+	pub navigation: &'a NavigationTree,
+	pub config: &'a Config,
+	pub assets: &'a AssetManifest,
+	// Rendered for print/, without the sidebar
+	pub print: bool,
+}
+
+impl<'a> PageContext<'a> {
+	// Generated pages outside the document sequence
+	fn standalone(
+		doc: &'a Document,
+		navigation: &'a NavigationTree,
+		config: &'a Config,
+		assets: &'a AssetManifest,
+	) -> Self {
+		Self {
+			doc,
+			prev: None,
+			next: None,
+			all_docs: &[],
+			navigation,
+			config,
+			assets,
+			print: false,
+		}
+	}
+}
+
+// One page of a generated directory listing, page counts from 1
+pub struct Listing<'a> {
+	pub dir: &'a Path,
+	pub items: &'a [&'a Document],
+	pub page: usize,
+	pub total_pages: usize,
+	// Rendered with this template instead of base.html
+	pub template: Option<&'a str>,
+}
+
+static PARTIAL_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r"\{\{>\s*([^\s}]+)\s*\}\}").unwrap());
+
+// Template names are relative to the template directory with / separators
+fn template_name(dir: &Path, path: &Path) -> String {
+	path.strip_prefix(dir)
+		.unwrap_or(path)
+		.components()
+		.map(|c| c.as_os_str().to_string_lossy())
+		.collect::<Vec<_>>()
+		.join("/")
+}
+
+// Files under partials/, keyed without the partials/ prefix
+fn load_partials(dir: &Path) -> Result<HashMap<String, String>> {
+	let partials_dir = dir.join("partials");
+	let mut partials = HashMap::new();
+	if !partials_dir.is_dir() {
+		return Ok(partials);
+	}
+	for entry in WalkDir::new(&partials_dir) {
+		let entry = entry?;
+		if !entry.file_type().is_file() {
+			continue;
+		}
+		let content = fs::read_to_string(entry.path())
+			.with_context(|| format!("Failed to read {}", entry.path().display()))?;
+		partials.insert(template_name(&partials_dir, entry.path()), content);
+	}
+
+	// Partials are expanded once, so any include inside one is an error; cycles get named
+	let mut names: Vec<&String> = partials.keys().collect();
+	names.sort();
+	for name in names {
+		let mut chain = vec![name.as_str()];
+		if let Some(cycle) = find_partial_cycle(&partials, &mut chain) {
+			bail!("Partial include cycle: {}", cycle.join(" -> "));
+		}
+		if let Some(cap) = PARTIAL_REGEX.captures(&partials[name]) {
+			bail!(
+				"Partial {} includes {}, partials cannot include other partials",
+				name,
+				&cap[1]
+			);
+		}
+	}
+	Ok(partials)
+}
+
+fn find_partial_cycle<'a>(
+	partials: &'a HashMap<String, String>,
+	chain: &mut Vec<&'a str>,
+) -> Option<Vec<&'a str>> {
+	let current = partials.get(*chain.last()?)?;
+	for cap in PARTIAL_REGEX.captures_iter(current) {
+		let (name, _) = partials.get_key_value(&cap[1])?;
+		if chain.contains(&name.as_str()) {
+			chain.push(name);
+			return Some(chain.clone());
+		}
+		chain.push(name);
+		if let Some(cycle) = find_partial_cycle(partials, chain) {
+			return Some(cycle);
+		}
+		chain.pop();
+	}
+	None
+}
+
+fn expand_partials(source: &str, partials: &HashMap<String, String>) -> Result<String> {
+	let mut expanded = String::with_capacity(source.len());
+	let mut last = 0;
+	for cap in PARTIAL_REGEX.captures_iter(source) {
+		let directive = cap.get(0).unwrap();
+		let Some(partial) = partials.get(&cap[1]) else {
+			bail!("Unknown partial {}", &cap[1]);
+		};
+		expanded.push_str(&source[last..directive.start()]);
+		expanded.push_str(partial);
+		last = directive.end();
+	}
+	expanded.push_str(&source[last..]);
+	Ok(expanded)
+}
 
 pub struct TemplateEngine {
+	tera: Tera,
 	base_template: String,
 }
 
 impl TemplateEngine {
-	pub fn new() -> Result<Self> {
-		let base_template = include_str!("../templates/base.html").to_string();
-		Ok(Self { base_template })
+	// A custom directory replaces the embedded base.html, see the README for the variables it gets.
+	// Every .html file in it is loaded so base.html can extend or include the others.
+	// {{> name.html}} pastes in partials/name.html as is, so partials see the page's variables.
+	pub fn from_dir(dir: Option<&Path>) -> Result<Self> {
+		let (tera, base_template) = match dir {
+			Some(dir) => {
+				if !dir.is_dir() {
+					bail!("Template directory {} does not exist", dir.display());
+				}
+				let base = dir.join("base.html");
+				if !base.is_file() {
+					bail!("Template directory {} has no base.html", dir.display());
+				}
+				let partials = load_partials(dir)?;
+
+				// {{> name.html}} is expanded before Tera parses anything, it would not accept it
+				let mut templates = Vec::new();
+				for entry in WalkDir::new(dir).sort_by_file_name() {
+					let entry = entry?;
+					let path = entry.path();
+					if !entry.file_type().is_file()
+						|| path.extension().and_then(|e| e.to_str()) != Some("html")
+					{
+						continue;
+					}
+					let name = template_name(dir, path);
+					let source = fs::read_to_string(path)
+						.with_context(|| format!("Failed to read {}", path.display()))?;
+					templates.push((name, expand_partials(&source, &partials)?));
+				}
+				let base_template = templates
+					.iter()
+					.find(|(name, _)| name == "base.html")
+					.map(|(_, source)| source.clone())
+					.unwrap_or_default();
+
+				let mut tera = Tera::default();
+				tera.add_raw_templates(templates)
+					.with_context(|| format!("Invalid template in {}", dir.display()))?;
+				(tera, base_template)
+			}
+			None => {
+				let mut tera = Tera::default();
+				tera.add_raw_template("base.html", BASE_TEMPLATE)
+					.context("Invalid built-in base.html")?;
+				(tera, BASE_TEMPLATE.to_string())
+			}
+		};
+		Ok(Self {
+			tera,
+			base_template,
+		})
 	}
 
-	pub fn render_page(
-		&self,
-		doc: &Document,
-		all_docs: &[&Document],
-		navigation: &NavigationTree,
-		config: &Config,
-		output_path: &Path,
-	) -> Result<()> {
-		let html = self.render(doc, all_docs, navigation, config)?;
+	pub fn base_template(&self) -> &str {
+		&self.base_template
+	}
+
+	pub fn render_page(&self, page: &PageContext, output_path: &Path) -> Result<()> {
+		let html = self.render(&PageContext {
+			config: page.doc.effective_config.as_ref().unwrap_or(page.config),
+			..*page
+		})?;
 
 		// Create parent directory if needed
 		if let Some(parent) = output_path.parent() {
@@ -35,25 +227,234 @@ impl TemplateEngine {
 		Ok(())
 	}
 
-	fn render(
+	pub fn render_tag_index(
 		&self,
-		doc: &Document,
-		_all_docs: &[&Document],
+		tags: &[(&str, &str, usize)],
+		navigation: &NavigationTree,
+		config: &Config,
+		assets: &AssetManifest,
+	) -> Result<String> {
+		let mut content = String::from("<ul class=\"tag-index\">\n");
+		for (slug, name, count) in tags {
+			content.push_str(&format!(
+				"<li><a href=\"/tags/{}/\">{}</a> <span class=\"tag-count\">({})</span></li>\n",
+				slug,
+				escape_html(name),
+				count
+			));
+		}
+		content.push_str("</ul>");
+
+		let page = listing_document("Tags", "tags/index.html", content);
+		self.render(&PageContext::standalone(&page, navigation, config, assets))
+	}
+
+	// Stand-in for sites without a 404 source document
+	pub fn render_not_found(
+		&self,
+		navigation: &NavigationTree,
+		config: &Config,
+		assets: &AssetManifest,
+	) -> Result<String> {
+		let content = format!(
+			"<p>The page you are looking for does not exist.</p>\n<p><a href=\"/\">Back to {}</a></p>",
+			escape_html(&config.site.title)
+		);
+		let page = listing_document("Page not found", "404.html", content);
+		self.render(&PageContext::standalone(&page, navigation, config, assets))
+	}
+
+	pub fn render_tag_page(
+		&self,
+		slug: &str,
+		name: &str,
+		docs: &[&Document],
+		navigation: &NavigationTree,
+		config: &Config,
+		assets: &AssetManifest,
+	) -> Result<String> {
+		let mut content = String::from("<ul class=\"tag-pages\">\n");
+		for doc in docs {
+			content.push_str(&document_list_item(doc, config));
+			content.push_str("</li>\n");
+		}
+		content.push_str("</ul>");
+
+		let page = listing_document(
+			&format!("Tagged: {}", name),
+			&format!("tags/{}/index.html", slug),
+			content,
+		);
+		self.render(&PageContext::standalone(&page, navigation, config, assets))
+	}
+
+	pub fn render_author_index(
+		&self,
+		authors: &[(&str, &str, usize)],
+		navigation: &NavigationTree,
+		config: &Config,
+		assets: &AssetManifest,
+	) -> Result<String> {
+		let mut content = String::from("<ul class=\"author-index\">\n");
+		for (slug, name, count) in authors {
+			content.push_str(&format!(
+				"<li><a href=\"/authors/{}/\">{}</a> <span class=\"author-count\">({})</span></li>\n",
+				slug,
+				escape_html(name),
+				count
+			));
+		}
+		content.push_str("</ul>");
+
+		let page = listing_document("Authors", "authors/index.html", content);
+		self.render(&PageContext::standalone(&page, navigation, config, assets))
+	}
+
+	pub fn render_author_page(
+		&self,
+		slug: &str,
+		name: &str,
+		docs: &[&Document],
 		navigation: &NavigationTree,
 		config: &Config,
+		assets: &AssetManifest,
+	) -> Result<String> {
+		let mut content = String::from("<ul class=\"author-pages\">\n");
+		for doc in docs {
+			content.push_str(&document_list_item(doc, config));
+			content.push_str("</li>\n");
+		}
+		content.push_str("</ul>");
+
+		let page = listing_document(
+			&format!("By {}", name),
+			&format!("authors/{}/index.html", slug),
+			content,
+		);
+		self.render(&PageContext::standalone(&page, navigation, config, assets))
+	}
+
+	pub fn render_list_page(
+		&self,
+		listing: &Listing,
+		navigation: &NavigationTree,
+		config: &Config,
+		assets: &AssetManifest,
+	) -> Result<String> {
+		let Listing {
+			dir,
+			items,
+			page,
+			total_pages,
+			template,
+		} = *listing;
+		let base = format!("/{}/", dir.to_string_lossy().replace('\\', "/"));
+		let page_href = |n: usize| {
+			if n == 1 {
+				base.clone()
+			} else {
+				format!("{}page/{}/", base, n)
+			}
+		};
+
+		let mut content = String::from("<ul class=\"directory-listing\">\n");
+		for doc in items {
+			content.push_str(&document_list_item(doc, config));
+			if let Some(tags) = doc.frontmatter.tags.as_ref().filter(|t| !t.is_empty()) {
+				content.push_str("<p class=\"listing-tags\">");
+				for tag in tags {
+					content.push_str(&format!(
+						"<a href=\"/tags/{}/\">{}</a> ",
+						slugify(tag),
+						escape_html(tag)
+					));
+				}
+				content.push_str("</p>");
+			}
+			content.push_str("</li>\n");
+		}
+		content.push_str("</ul>\n");
+
+		if total_pages > 1 {
+			content.push_str("<nav class=\"pagination\">\n");
+			if page > 1 {
+				content.push_str(&format!(
+					"<a href=\"{}\" rel=\"prev\">← Previous</a>\n",
+					page_href(page - 1)
+				));
+			}
+			content.push_str(&format!("<span>Page {} of {}</span>\n", page, total_pages));
+			if page < total_pages {
+				content.push_str(&format!(
+					"<a href=\"{}\" rel=\"next\">Next →</a>\n",
+					page_href(page + 1)
+				));
+			}
+			content.push_str("</nav>");
+		}
+
+		let name = dir
+			.file_name()
+			.map(|n| n.to_string_lossy().to_string())
+			.unwrap_or_default();
+		let (title, relative_path) = if page == 1 {
+			(name, dir.join("index.html"))
+		} else {
+			(
+				format!("{} (page {})", name, page),
+				dir.join("page").join(page.to_string()).join("index.html"),
+			)
+		};
+		let page = listing_document(&title, &relative_path.to_string_lossy(), content);
+		self.render_with(
+			&PageContext::standalone(&page, navigation, config, assets),
+			template.unwrap_or("base.html"),
+			items,
+		)
+	}
+
+	pub fn has_template(&self, name: &str) -> bool {
+		self.tera.get_template_names().any(|t| t == name)
+	}
+
+	fn render(&self, page: &PageContext) -> Result<String> {
+		self.render_with(page, "base.html", &[])
+	}
+
+	fn render_with(
+		&self,
+		page: &PageContext,
+		template: &str,
+		pages: &[&Document],
 	) -> Result<String> {
+		let PageContext {
+			doc,
+			all_docs,
+			navigation,
+			config,
+			assets,
+			..
+		} = *page;
 		let title = doc
 			.frontmatter
 			.title
-			.as_ref()
-			.map(|t| t.clone())
+			.clone()
 			.unwrap_or_else(|| "Untitled".to_string());
 
 		let site_title = &config.site.title;
 		let page_title = format!("{} - {}", title, site_title);
 
 		// Render sidebar
-		let sidebar_html = self.render_sidebar(navigation, &doc.relative_path);
+		let sidebar_html = if page.print {
+			String::new()
+		} else {
+			self.render_sidebar(
+				navigation,
+				&doc.relative_path,
+				config.navigation.max_depth,
+				config.navigation.expand_active_path,
+			)
+		};
 
 		// Render breadcrumbs
 		let breadcrumbs_html = if config.navigation.breadcrumbs {
@@ -64,46 +465,290 @@ impl TemplateEngine {
 
 		// Render backlinks
 		let backlinks_html = if !doc.backlinks.is_empty() {
-			self.render_backlinks(&doc.backlinks)
+			self.render_backlinks(&doc.backlinks, all_docs, config)
 		} else {
 			String::new()
 		};
 
 		// Render version selector
-		let version_selector = self.render_version_selector(&config.site.versions, &doc.version);
-
-		// Replace template variables
-		let html = self
-			.base_template
-			.replace("{{SITE_TITLE}}", site_title)
-			.replace("{{PAGE_TITLE}}", &page_title)
-			.replace("{{TITLE}}", &title)
-			.replace("{{CONTENT}}", &doc.html_content)
-			.replace("{{SIDEBAR}}", &sidebar_html)
-			.replace("{{BREADCRUMBS}}", &breadcrumbs_html)
-			.replace("{{BACKLINKS}}", &backlinks_html)
-			.replace("{{VERSION_SELECTOR}}", &version_selector)
-			.replace(
-				"{{DEFAULT_THEME}}",
-				config.theme.default_theme.as_deref().unwrap_or("light"),
-			)
-			.replace(
-				"{{SEARCH_ENABLED}}",
-				if config.search.enabled {
-					"true"
-				} else {
-					"false"
-				},
+		let version_selector = self.render_version_selector(
+			&config.site.versions,
+			&config.site.version_aliases,
+			&doc.version,
+		);
+		let locale_switcher = self.render_locale_switcher(doc, all_docs, config);
+
+		let body_class = self.render_body_class(doc);
+		let html_lang = self.html_lang(doc, config);
+
+		// Render OpenGraph and Twitter Card meta tags
+		let social_meta = self.render_social_meta(doc, &page_title, config);
+
+		// Pre-rendered fragments carry a _html suffix and are inserted with the safe filter
+		let mut context = tera::Context::new();
+		// Custom frontmatter as extra_<key>, the built-in keys below win if a name collides
+		for (key, value) in &doc.frontmatter.extra {
+			context.insert(format!("extra_{}", key), &extra_value(value));
+		}
+		context.insert("doc", doc);
+		context.insert("navigation", navigation);
+		context.insert("config", config);
+		context.insert("title", &title);
+		context.insert("page_title", &page_title);
+		context.insert("site_title", site_title);
+		context.insert("content_html", &doc.html_content);
+		context.insert("toc_html", &doc.toc_html);
+		context.insert("sidebar_html", &sidebar_html);
+		context.insert("breadcrumbs_html", &breadcrumbs_html);
+		context.insert("backlinks_html", &backlinks_html);
+		context.insert("version_selector_html", &version_selector);
+		context.insert("locale_switcher_html", &locale_switcher);
+		context.insert("locale", &page_locale(doc, config));
+		context.insert(
+			"last_modified_html",
+			&self.render_last_modified(doc, config),
+		);
+		context.insert("social_meta_html", &social_meta);
+		context.insert("feed_link_html", &self.render_feed_link(config));
+		context.insert("extra_css_html", &self.render_extra_css(config));
+		context.insert("extra_js_html", &self.render_extra_js(config));
+		context.insert("html_lang", &html_lang);
+		context.insert("body_class", &body_class);
+		context.insert(
+			"default_theme",
+			config.theme.default_theme.as_deref().unwrap_or("light"),
+		);
+		context.insert("search_enabled", &config.search.enabled);
+		context.insert("is_draft", &(doc.frontmatter.draft == Some(true)));
+		let date = doc
+			.frontmatter
+			.date
+			.filter(|_| config.display.show_date)
+			.map(|date| format_date(|out, format| write!(out, "{}", date.format(format)), config));
+		context.insert("date", &date);
+		context.insert(
+			"authors",
+			&doc.frontmatter
+				.authors
+				.as_ref()
+				.map(|authors| authors.join(", "))
+				.unwrap_or_default(),
+		);
+		context.insert("word_count", &doc.word_count);
+		// Listing pages have no words of their own and stay at zero
+		let reading_time = Some(doc.reading_time_minutes)
+			.filter(|&minutes| minutes > 0 && config.display.show_reading_time);
+		context.insert("reading_time", &reading_time);
+		context.insert("edit_url", &edit_url(doc, config));
+		// The documents a listing page links to, empty everywhere else
+		context.insert("pages", pages);
+		context.insert("css_filename", &assets.css);
+		context.insert("js_filename", &assets.js);
+		for (key, neighbour) in [("prev", page.prev), ("next", page.next)] {
+			let neighbour = neighbour.filter(|_| config.navigation.prev_next);
+			context.insert(
+				format!("{}_title", key),
+				&neighbour.map(page_title_or_stem).unwrap_or_default(),
+			);
+			context.insert(
+				format!("{}_url", key),
+				&neighbour
+					.map(|doc| {
+						format!(
+							"/{}",
+							output_path(doc, config)
+								.to_string_lossy()
+								.replace('\\', "/")
+						)
+					})
+					.unwrap_or_default(),
 			);
+		}
+
+		self.tera
+			.render(template, &context)
+			.with_context(|| format!("Failed to render {}", doc.relative_path.display()))
+	}
+
+	// Invalid tags are reported as W013 while parsing and building, here they are just skipped
+	fn html_lang(&self, doc: &Document, config: &Config) -> String {
+		[
+			doc.frontmatter.lang.as_deref(),
+			page_locale(doc, config),
+			Some(config.site.language.as_str()),
+		]
+		.into_iter()
+		.flatten()
+		.find(|lang| is_valid_lang(lang))
+		.unwrap_or("en")
+		.to_string()
+	}
+
+	// Extra body classes from frontmatter, prefixed with a space to follow the theme class
+	fn render_body_class(&self, doc: &Document) -> String {
+		let Some(classes) = &doc.frontmatter.body_class else {
+			return String::new();
+		};
+
+		let mut html = String::new();
+		for class in classes.split_whitespace() {
+			if class
+				.chars()
+				.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+			{
+				html.push(' ');
+				html.push_str(class);
+			} else {
+				warn!(
+					"Ignoring invalid body_class '{}' in {}",
+					class,
+					doc.relative_path.display()
+				);
+			}
+		}
+		html
+	}
+
+	fn render_feed_link(&self, config: &Config) -> String {
+		let feed = &config.feed;
+		if !feed.enabled {
+			return String::new();
+		}
+		let mime = if feed.format == "atom" {
+			"application/atom+xml"
+		} else {
+			"application/rss+xml"
+		};
+		format!(
+			"<link rel=\"alternate\" type=\"{}\" title=\"{}\" href=\"/{}\">",
+			mime,
+			escape_html(&config.site.title),
+			feed.output_path.trim_start_matches('/')
+		)
+	}
+
+	fn render_last_modified(&self, doc: &Document, config: &Config) -> String {
+		let Some(date) = doc.frontmatter.last_modified else {
+			return String::new();
+		};
+		let formatted = format_date(|out, format| write!(out, "{}", date.format(format)), config);
+		format!(
+			"<p class=\"last-modified\">Last updated <time datetime=\"{}\">{}</time></p>",
+			date.to_rfc3339_opts(SecondsFormat::Secs, true),
+			escape_html(&formatted)
+		)
+	}
 
-		Ok(html)
+	fn render_extra_css(&self, config: &Config) -> String {
+		config
+			.assets
+			.extra_css
+			.iter()
+			.filter_map(|path| extra_asset_name(path))
+			.map(|name| {
+				format!(
+					"<link rel=\"stylesheet\" href=\"/assets/css/{}\">",
+					escape_html(&name)
+				)
+			})
+			.collect::<Vec<_>>()
+			.join("\n    ")
 	}
 
-	fn render_sidebar(&self, navigation: &NavigationTree, current_path: &Path) -> String {
+	fn render_extra_js(&self, config: &Config) -> String {
+		config
+			.assets
+			.extra_js
+			.iter()
+			.filter_map(|path| extra_asset_name(path))
+			.map(|name| {
+				format!(
+					"<script src=\"/assets/js/{}\"></script>",
+					escape_html(&name)
+				)
+			})
+			.collect::<Vec<_>>()
+			.join("\n    ")
+	}
+
+	// OpenGraph and Twitter Card tags, each one left out when the page has nothing for it
+	fn render_social_meta(&self, doc: &Document, page_title: &str, config: &Config) -> String {
+		let frontmatter = &doc.frontmatter;
+		let base_url = config
+			.sitemap
+			.base_url
+			.as_deref()
+			.or(config.site.base_url.as_deref());
+
+		let image = frontmatter
+			.image
+			.as_deref()
+			.map(|image| absolute_url(base_url, image));
+		let mut tags = vec![
+			("property", "og:type", Some(config.social.og_type.clone())),
+			("property", "og:title", frontmatter.title.clone()),
+			(
+				"property",
+				"og:description",
+				frontmatter.description.clone(),
+			),
+			("property", "og:image", image.clone()),
+		];
+
+		// Twitter Cards only make sense with a handle to attribute them to
+		let handle = config
+			.social
+			.twitter_handle
+			.as_ref()
+			.or(config.site.twitter_handle.as_ref());
+		if let Some(handle) = handle.filter(|_| config.seo.twitter_cards) {
+			let description = frontmatter
+				.description
+				.clone()
+				.unwrap_or_else(|| config.site.description.clone());
+			tags.extend([
+				(
+					"name",
+					"twitter:card",
+					Some("summary_large_image".to_string()),
+				),
+				("name", "twitter:site", Some(handle.clone())),
+				("name", "twitter:title", Some(page_title.to_string())),
+				("name", "twitter:description", Some(description)),
+				("name", "twitter:image", image),
+			]);
+		}
+
+		tags.into_iter()
+			.filter_map(|(attribute, name, content)| {
+				Some(format!(
+					"<meta {}=\"{}\" content=\"{}\">",
+					attribute,
+					name,
+					escape_html(&content?)
+				))
+			})
+			.collect::<Vec<_>>()
+			.join("\n    ")
+	}
+
+	fn render_sidebar(
+		&self,
+		navigation: &NavigationTree,
+		current_path: &Path,
+		max_depth: Option<usize>,
+		expand_active_path: bool,
+	) -> String {
 		let mut html = String::from("<nav class=\"sidebar\">\n<ul>\n");
 
 		for item in &navigation.items {
-			html.push_str(&self.render_nav_item(item, current_path, 0));
+			html.push_str(&self.render_nav_item(
+				item,
+				current_path,
+				0,
+				max_depth,
+				expand_active_path,
+			));
 		}
 
 		html.push_str("</ul>\n</nav>");
@@ -112,49 +757,53 @@ impl TemplateEngine {
 
 	fn render_nav_item(
 		&self,
-		item: &crate::generator::NavigationItem,
+		item: &NavigationItem,
 		current_path: &Path,
 		depth: usize,
+		max_depth: Option<usize>,
+		expand_active_path: bool,
 	) -> String {
 		let indent = "  ".repeat(depth);
-		let is_active =
-			!item.path.as_os_str().is_empty() && item.path.file_stem() == current_path.file_stem();
+		let is_active = is_nav_page(item, current_path);
 		let active_class = if is_active { " class=\"active\"" } else { "" };
 
 		let mut html = format!("{}<li{}>\n", indent, active_class);
 
-		if !item.path.as_os_str().is_empty() {
-			let mut href = item.path.to_string_lossy().replace('\\', "/");
-			// Change .md to .html
-			if href.ends_with(".md") {
-				href = href.replace(".md", ".html");
-			}
-			// Add version prefix if needed
-			if let Some(version) = &item.version {
-				// Only prepend if the path doesn't already start with the version
-				if !href.starts_with(version) {
-					href = format!("{}/{}", version, href);
-				}
-			}
-			href = format!("/{}", href);
+		let icon = item
+			.icon
+			.as_ref()
+			.map(|icon| format!("<span class=\"nav-icon\">{}</span> ", icon))
+			.unwrap_or_default();
+		if let Some(href) = &item.href {
 			html.push_str(&format!(
-				"{}<a href=\"{}\">{}</a>\n",
+				"{}<a href=\"{}\">{}{}</a>\n",
 				"  ".repeat(depth + 1),
 				href,
+				icon,
 				item.title
 			));
 		} else {
 			html.push_str(&format!(
-				"{}<span>{}</span>\n",
+				"{}<span>{}{}</span>\n",
 				"  ".repeat(depth + 1),
+				icon,
 				item.title
 			));
 		}
 
-		if !item.children.is_empty() {
+		// Levels past max_depth are cut, except on the way down to the current page
+		let expand = depth + 1 < max_depth.unwrap_or(usize::MAX)
+			|| (expand_active_path && contains_nav_page(item, current_path));
+		if !item.children.is_empty() && expand {
 			html.push_str(&format!("{}<ul>\n", "  ".repeat(depth + 1)));
 			for child in &item.children {
-				html.push_str(&self.render_nav_item(child, current_path, depth + 1));
+				html.push_str(&self.render_nav_item(
+					child,
+					current_path,
+					depth + 1,
+					max_depth,
+					expand_active_path,
+				));
 			}
 			html.push_str(&format!("{}</ul>\n", "  ".repeat(depth + 1)));
 		}
@@ -190,41 +839,116 @@ impl TemplateEngine {
 		html
 	}
 
-	fn render_backlinks(&self, backlinks: &[String]) -> String {
+	// Backlinks hold the linking page's title, or its source path when it has none
+	fn render_backlinks(
+		&self,
+		backlinks: &[String],
+		all_docs: &[&Document],
+		config: &Config,
+	) -> String {
 		let mut html =
 			String::from("<div class=\"backlinks\">\n<h3>Pages that link here</h3>\n<ul>\n");
 
 		for link in backlinks {
-			html.push_str(&format!(
-				"<li><a href=\"#{}\">{}</a></li>\n",
-				link.to_lowercase().replace(' ', "-"),
-				link
-			));
+			let source = all_docs.iter().find(|d| {
+				d.frontmatter.title.as_deref() == Some(link.as_str())
+					|| d.relative_path.to_string_lossy() == link.as_str()
+			});
+			match source {
+				Some(source) => html.push_str(&format!(
+					"<li><a href=\"/{}\">{}</a></li>\n",
+					output_path(source, config)
+						.to_string_lossy()
+						.replace('\\', "/"),
+					escape_html(link)
+				)),
+				None => html.push_str(&format!("<li>{}</li>\n", escape_html(link))),
+			}
 		}
 
 		html.push_str("</ul>\n</div>");
 		html
 	}
 
+	// Links to the same page in every locale it exists in, untranslated pages point at their fallback
+	fn render_locale_switcher(
+		&self,
+		doc: &Document,
+		all_docs: &[&Document],
+		config: &Config,
+	) -> String {
+		let Some(current) = page_locale(doc, config) else {
+			return String::new();
+		};
+		let key = translation_key(doc);
+
+		let mut options = Vec::new();
+		let locales = std::iter::once(&config.i18n.default_locale).chain(
+			config
+				.i18n
+				.locales
+				.iter()
+				.filter(|l| **l != config.i18n.default_locale),
+		);
+		for locale in locales {
+			let translation = all_docs
+				.iter()
+				.find(|d| document_locale(d, config) == locale && translation_key(d) == key);
+			if let Some(translation) = translation {
+				let href = output_path(translation, config)
+					.to_string_lossy()
+					.replace('\\', "/");
+				let selected = if locale == current { " selected" } else { "" };
+				options.push(format!(
+					"<option value=\"/{}\"{}>{}</option>\n",
+					escape_html(&href),
+					selected,
+					escape_html(locale)
+				));
+			}
+		}
+		if options.len() <= 1 {
+			return String::new();
+		}
+
+		let mut html = String::from(
+			"<select id=\"locale-switcher\" onchange=\"window.location.href = this.value\">\n",
+		);
+		for option in options {
+			html.push_str(&option);
+		}
+		html.push_str("</select>");
+		html
+	}
+
 	fn render_version_selector(
 		&self,
 		versions: &[String],
+		aliases: &BTreeMap<String, String>,
 		current_version: &Option<String>,
 	) -> String {
 		if versions.len() <= 1 {
 			return String::new();
 		}
 
+		// Pages copied to an alias's real version path select the alias
+		let current_version = current_version.as_ref().and_then(|current| {
+			if versions.contains(current) {
+				Some(current)
+			} else {
+				aliases
+					.iter()
+					.find(|(alias, target)| *target == current && versions.contains(alias))
+					.map(|(alias, _)| alias)
+			}
+		});
+
 		let mut html = String::from(
 			"<select id=\"version-selector\" onchange=\"switchVersion(this.value)\">\n",
 		);
 
 		for version in versions {
-			let selected = if current_version
-				.as_ref()
-				.map(|v| v == version)
-				.unwrap_or(false)
-			{
+			let selected = if current_version == Some(version) {
 				" selected"
 			} else {
 				""
@@ -239,3 +963,427 @@ impl TemplateEngine {
 		html
 	}
 }
+
+// None unless [i18n] lists locales
+// Directory nodes have no path, so only a page's own entry can match
+fn is_nav_page(item: &NavigationItem, current_path: &Path) -> bool {
+	!item.path.as_os_str().is_empty() && item.path.components().eq(current_path.components())
+}
+
+fn contains_nav_page(item: &NavigationItem, current_path: &Path) -> bool {
+	item.children
+		.iter()
+		.any(|child| is_nav_page(child, current_path) || contains_nav_page(child, current_path))
+}
+
+fn page_locale<'a>(doc: &'a Document, config: &'a Config) -> Option<&'a str> {
+	if config.i18n.locales.is_empty() {
+		return None;
+	}
+	Some(document_locale(doc, config))
+}
+
+// Generated pages have no source to edit
+fn edit_url(doc: &Document, config: &Config) -> Option<String> {
+	let repository = &config.repository;
+	let url = repository.url.as_deref()?.trim_end_matches('/');
+	if doc.path.as_os_str().is_empty() {
+		return None;
+	}
+	let action = match repository.kind.as_str() {
+		"gitlab" => "-/edit",
+		"gitea" => "_edit",
+		_ => "edit",
+	};
+	let path = Path::new(repository.docs_dir.trim_matches('/')).join(&doc.relative_path);
+	Some(format!(
+		"{}/{}/{}/{}",
+		url,
+		action,
+		repository.branch,
+		path.to_string_lossy().replace('\\', "/")
+	))
+}
+
+fn page_title_or_stem(doc: &Document) -> String {
+	doc.frontmatter.title.clone().unwrap_or_else(|| {
+		doc.relative_path
+			.file_stem()
+			.map(|stem| stem.to_string_lossy().to_string())
+			.unwrap_or_default()
+	})
+}
+
+// chrono only reports a bad format string when it is written out
+fn format_date(write_date: impl Fn(&mut String, &str) -> fmt::Result, config: &Config) -> String {
+	let mut formatted = String::new();
+	if write_date(&mut formatted, &config.build.date_format).is_err() {
+		let fallback = default_date_format();
+		warn!(
+			"Invalid build.date_format '{}', using {}",
+			config.build.date_format, fallback
+		);
+		formatted.clear();
+		write_date(&mut formatted, &fallback).ok();
+	}
+	formatted
+}
+
+// Extra assets are copied flat into assets/css or assets/js under their file name
+pub fn extra_asset_name(path: &str) -> Option<String> {
+	Path::new(path)
+		.file_name()
+		.map(|name| name.to_string_lossy().to_string())
+}
+
+// File names of the bundled stylesheet and script, hashed when assets.fingerprint is set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetManifest {
+	pub css: String,
+	pub js: String,
+}
+
+impl AssetManifest {
+	// Incremental builds render against the names the last full build wrote
+	pub fn load(output_dir: &Path) -> Self {
+		fs::read_to_string(output_dir.join("assets/manifest.json"))
+			.ok()
+			.and_then(|json| serde_json::from_str(&json).ok())
+			.unwrap_or_default()
+	}
+}
+
+impl Default for AssetManifest {
+	fn default() -> Self {
+		Self {
+			css: "style.css".to_string(),
+			js: "app.js".to_string(),
+		}
+	}
+}
+
+// style.css becomes style.{first 8 hex digits of the SHA-256}.css
+pub fn fingerprinted_name(name: &str, bytes: &[u8]) -> String {
+	let hash = format!("{:x}", Sha256::digest(bytes));
+	match name.rsplit_once('.') {
+		Some((stem, extension)) => format!("{}.{}.{}", stem, &hash[..8], extension),
+		None => format!("{}.{}", name, &hash[..8]),
+	}
+}
+
+// Title, date and description of a page in a listing, the caller closes the <li>
+fn document_list_item(doc: &Document, config: &Config) -> String {
+	let href = output_path(doc, config)
+		.to_string_lossy()
+		.replace('\\', "/");
+	let title = doc
+		.frontmatter
+		.title
+		.clone()
+		.unwrap_or_else(|| doc.relative_path.to_string_lossy().to_string());
+	let mut item = format!("<li><a href=\"/{}\">{}</a>", href, escape_html(&title));
+	if let Some(date) = doc.frontmatter.date.filter(|_| config.display.show_date) {
+		item.push_str(&format!(
+			" <time datetime=\"{}\">{}</time>",
+			date,
+			escape_html(&format_date(
+				|out, format| write!(out, "{}", date.format(format)),
+				config
+			))
+		));
+	}
+	if let Some(description) = &doc.frontmatter.description {
+		item.push_str(&format!("<p>{}</p>", escape_html(description)));
+	}
+	item
+}
+
+// Generated listing pages render through the same template as documents
+fn listing_document(title: &str, relative_path: &str, html_content: String) -> Document {
+	Document {
+		frontmatter: Frontmatter {
+			title: Some(title.to_string()),
+			..Default::default()
+		},
+		html_content,
+		relative_path: PathBuf::from(relative_path),
+		..Default::default()
+	}
+}
+
+// Scalars as their plain text, sequences and mappings as JSON
+fn extra_value(value: &serde_yaml::Value) -> String {
+	match value {
+		serde_yaml::Value::Null => String::new(),
+		serde_yaml::Value::Bool(b) => b.to_string(),
+		serde_yaml::Value::Number(n) => n.to_string(),
+		serde_yaml::Value::String(s) => s.clone(),
+		serde_yaml::Value::Tagged(tagged) => extra_value(&tagged.value),
+		serde_yaml::Value::Sequence(_) | serde_yaml::Value::Mapping(_) => {
+			serde_json::to_string(value).unwrap_or_default()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn doc(frontmatter: Frontmatter) -> Document {
+		Document {
+			frontmatter,
+			path: PathBuf::from("docs/guide.md"),
+			relative_path: PathBuf::from("guide.md"),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_edit_url() {
+		let mut config = Config::default();
+		let page = doc(Frontmatter::default());
+		assert_eq!(edit_url(&page, &config), None);
+
+		config.repository.url = Some("https://github.com/night0721/rum/".to_string());
+		assert_eq!(
+			edit_url(&page, &config).unwrap(),
+			"https://github.com/night0721/rum/edit/main/docs/guide.md"
+		);
+
+		config.repository.url = Some("https://gitlab.com/night0721/rum".to_string());
+		config.repository.kind = "gitlab".to_string();
+		config.repository.branch = "develop".to_string();
+		assert_eq!(
+			edit_url(&page, &config).unwrap(),
+			"https://gitlab.com/night0721/rum/-/edit/develop/docs/guide.md"
+		);
+
+		config.repository.url = Some("https://codeberg.org/night0721/rum".to_string());
+		config.repository.kind = "gitea".to_string();
+		config.repository.docs_dir = "site/content/".to_string();
+		assert_eq!(
+			edit_url(&page, &config).unwrap(),
+			"https://codeberg.org/night0721/rum/_edit/develop/site/content/guide.md"
+		);
+
+		let listing = listing_document("Tags", "tags/index.html", String::new());
+		assert_eq!(edit_url(&listing, &config), None);
+	}
+
+	#[test]
+	fn test_social_meta() {
+		let engine = TemplateEngine::from_dir(None).unwrap();
+		let mut config = Config::default();
+		config.sitemap.base_url = Some("https://docs.example.com/".to_string());
+		config.social.twitter_handle = Some("@rumdocs".to_string());
+
+		let full = doc(Frontmatter {
+			title: Some("Guide".to_string()),
+			description: Some("Getting \"started\"".to_string()),
+			image: Some("/images/cover.png".to_string()),
+			..Default::default()
+		});
+		let meta = engine.render_social_meta(&full, "Guide - Docs", &config);
+		for tag in [
+			"<meta property=\"og:type\" content=\"article\">",
+			"<meta property=\"og:title\" content=\"Guide\">",
+			"<meta property=\"og:description\" content=\"Getting &quot;started&quot;\">",
+			"<meta property=\"og:image\" content=\"https://docs.example.com/images/cover.png\">",
+			"<meta name=\"twitter:card\" content=\"summary_large_image\">",
+			"<meta name=\"twitter:site\" content=\"@rumdocs\">",
+			"<meta name=\"twitter:title\" content=\"Guide - Docs\">",
+			"<meta name=\"twitter:description\" content=\"Getting &quot;started&quot;\">",
+			"<meta name=\"twitter:image\" content=\"https://docs.example.com/images/cover.png\">",
+		] {
+			assert!(meta.contains(tag), "missing {} in {}", tag, meta);
+		}
+
+		// Without a description of its own the card falls back to the site's
+		config.site.description = "All about rum".to_string();
+		let bare = doc(Frontmatter {
+			title: Some("Guide".to_string()),
+			..Default::default()
+		});
+		let meta = engine.render_social_meta(&bare, "Guide - Docs", &config);
+		assert!(meta.contains("<meta name=\"twitter:description\" content=\"All about rum\">"));
+		assert!(!meta.contains("twitter:image"));
+
+		config.social.twitter_handle = None;
+		let meta = engine.render_social_meta(&bare, "Guide - Docs", &config);
+		assert!(meta.contains("og:title"));
+		assert!(!meta.contains("og:description"));
+		assert!(!meta.contains("og:image"));
+		assert!(!meta.contains("twitter:"));
+	}
+
+	#[test]
+	fn test_partials() {
+		let dir = std::env::temp_dir().join(format!("rum-partials-{}", std::process::id()));
+		let partials = dir.join("partials");
+		fs::create_dir_all(partials.join("nav")).unwrap();
+		fs::write(
+			dir.join("base.html"),
+			"{{> header.html}}<main>{{ content_html | safe }}</main>{{>nav/links.html}}",
+		)
+		.unwrap();
+		fs::write(partials.join("header.html"), "<h1>{{ title }}</h1>").unwrap();
+		fs::write(
+			partials.join("nav/links.html"),
+			"<nav>{{ site_title }}</nav>",
+		)
+		.unwrap();
+
+		// Partials see the same variables as the page
+		let engine = TemplateEngine::from_dir(Some(&dir)).unwrap();
+		assert!(!engine.base_template().contains("{{>"));
+		let mut page = doc(Frontmatter {
+			title: Some("Guide".to_string()),
+			..Default::default()
+		});
+		page.html_content = "<p>Body</p>".to_string();
+		let config = Config::default();
+		let navigation = NavigationTree::new();
+		let assets = AssetManifest::default();
+		let html = engine
+			.render(&PageContext::standalone(
+				&page,
+				&navigation,
+				&config,
+				&assets,
+			))
+			.unwrap();
+		assert!(html.starts_with("<h1>Guide</h1><main><p>Body</p></main><nav>"));
+		assert!(html.ends_with(&format!("{}</nav>", config.site.title)));
+
+		fs::write(dir.join("base.html"), "{{> missing.html}}").unwrap();
+		let err = TemplateEngine::from_dir(Some(&dir)).err().unwrap();
+		assert_eq!(err.to_string(), "Unknown partial missing.html");
+		fs::write(dir.join("base.html"), "{{> header.html}}").unwrap();
+
+		fs::write(partials.join("header.html"), "{{> header.html}}").unwrap();
+		let err = TemplateEngine::from_dir(Some(&dir)).err().unwrap();
+		assert_eq!(
+			err.to_string(),
+			"Partial include cycle: header.html -> header.html"
+		);
+
+		fs::write(partials.join("header.html"), "{{> footer.html}}").unwrap();
+		fs::write(partials.join("footer.html"), "{{> header.html}}").unwrap();
+		let err = TemplateEngine::from_dir(Some(&dir)).err().unwrap();
+		assert_eq!(
+			err.to_string(),
+			"Partial include cycle: footer.html -> header.html -> footer.html"
+		);
+
+		fs::write(partials.join("footer.html"), "<footer></footer>").unwrap();
+		let err = TemplateEngine::from_dir(Some(&dir)).err().unwrap();
+		assert_eq!(
+			err.to_string(),
+			"Partial header.html includes footer.html, partials cannot include other partials"
+		);
+
+		fs::remove_dir_all(&dir).ok();
+	}
+
+	#[test]
+	fn test_extra_fields() {
+		let dir = std::env::temp_dir().join(format!("rum-extra-fields-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		fs::write(
+			dir.join("base.html"),
+			"{{ extra_api_endpoint | safe }}|{{ extra_beta }}|{{ extra_limit }}|\
+			 {{ extra_regions | safe }}|{{ extra_owner | safe }}|{{ extra_css_html }}",
+		)
+		.unwrap();
+
+		let engine = TemplateEngine::from_dir(Some(&dir)).unwrap();
+		let page = doc(serde_yaml::from_str(
+			"title: API\napi_endpoint: /v2/users\nbeta: true\nlimit: 100\n\
+			 regions: [eu, us]\nowner: {team: core, oncall: 3}\ncss_html: ignored\n",
+		)
+		.unwrap());
+		let config = Config::default();
+		let navigation = NavigationTree::new();
+		let assets = AssetManifest::default();
+		let html = engine
+			.render(&PageContext::standalone(
+				&page,
+				&navigation,
+				&config,
+				&assets,
+			))
+			.unwrap();
+		assert_eq!(
+			html,
+			"/v2/users|true|100|[\"eu\",\"us\"]|{\"team\":\"core\",\"oncall\":3}|"
+		);
+
+		fs::remove_dir_all(&dir).ok();
+	}
+
+	fn deep_navigation() -> NavigationTree {
+		let mut tree = NavigationTree::new();
+		for path in [
+			"top.md",
+			"a/b/c/deep.md",
+			"a/b/c/d/deeper.md",
+			"a/other/x/deeper.md",
+		] {
+			let title = path.trim_end_matches(".md").replace('/', "-");
+			tree.add_path(Path::new(path), title, None, format!("/{}", path));
+		}
+		tree
+	}
+
+	#[test]
+	fn test_sidebar_max_depth() {
+		let engine = TemplateEngine::from_dir(None).unwrap();
+		let navigation = deep_navigation();
+		let shown = |html: &str, titles: &[&str]| {
+			titles
+				.iter()
+				.map(|title| html.contains(&format!(">{}<", title)))
+				.collect::<Vec<_>>()
+		};
+
+		let html = engine.render_sidebar(&navigation, Path::new("top.md"), None, true);
+		assert_eq!(
+			shown(&html, &["a-b-c-d-deeper", "a-other-x-deeper"]),
+			[true, true]
+		);
+
+		// Two levels, nothing below b or other
+		let html = engine.render_sidebar(&navigation, Path::new("top.md"), Some(2), true);
+		assert_eq!(
+			shown(&html, &["top", "a", "b", "other", "c", "x"]),
+			[true, true, true, true, false, false]
+		);
+
+		// The current page's directories open past the limit, the other deep branch stays closed
+		let current = Path::new("a/b/c/d/deeper.md");
+		let html = engine.render_sidebar(&navigation, current, Some(1), true);
+		assert_eq!(
+			shown(
+				&html,
+				&[
+					"a",
+					"b",
+					"c",
+					"a-b-c-deep",
+					"d",
+					"a-b-c-d-deeper",
+					"other",
+					"x"
+				]
+			),
+			[true, true, true, true, true, true, true, false]
+		);
+		assert!(!html.contains("a-other-x-deeper"));
+		// Only the page itself is active, not the other one with the same file name
+		assert_eq!(html.matches("class=\"active\"").count(), 1);
+		assert!(html.contains("<li class=\"active\">\n          <a href=\"/a/b/c/d/deeper.md\">"));
+
+		let html = engine.render_sidebar(&navigation, current, Some(1), false);
+		assert_eq!(shown(&html, &["top", "a", "b"]), [true, true, false]);
+	}
+}
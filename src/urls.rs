@@ -0,0 +1,196 @@
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::content::Document;
+use crate::utils::slugify;
+
+static TOKEN_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r":(slug|title|date|version|path)").unwrap());
+
+// Output locations from `[urls] pattern`, e.g. /docs/:version/:slug/index.html
+pub struct UrlResolver;
+
+impl UrlResolver {
+	// Tokens without a value (no date, no version) drop their whole path segment.
+	// A pattern ending in / gets index.html, anything else not ending in .html gets it appended.
+	pub fn resolve(doc: &Document, pattern: &str) -> PathBuf {
+		let stem = doc
+			.relative_path
+			.file_stem()
+			.map(|s| s.to_string_lossy().to_string())
+			.unwrap_or_default();
+
+		let mut segments = Vec::new();
+		for segment in pattern.replace('\\', "/").split('/') {
+			let mut missing = false;
+			let value = TOKEN_REGEX.replace_all(segment, |caps: &regex::Captures| {
+				let value = match &caps[1] {
+					"slug" => Some(sanitize(&stem)),
+					"title" => {
+						let title = doc.frontmatter.title.as_deref().map(slugify);
+						Some(
+							title
+								.filter(|t| !t.is_empty())
+								.unwrap_or_else(|| sanitize(&stem)),
+						)
+					}
+					"date" => doc
+						.frontmatter
+						.date
+						.map(|date| date.format("%Y/%m/%d").to_string()),
+					"version" => doc.version.as_deref().map(sanitize),
+					_ => Some(source_segments(&doc.relative_path.with_extension("")).join("/")),
+				};
+				value.unwrap_or_else(|| {
+					missing = true;
+					String::new()
+				})
+			});
+			if missing {
+				continue;
+			}
+			// :date and :path expand to several segments
+			segments.extend(
+				value
+					.split('/')
+					.filter(|s| !s.is_empty() && *s != "." && *s != "..")
+					.map(str::to_string),
+			);
+		}
+
+		match segments.last_mut() {
+			Some(last) if !pattern.ends_with('/') => {
+				if !last.ends_with(".html") {
+					last.push_str(".html");
+				}
+			}
+			_ => segments.push("index.html".to_string()),
+		}
+		segments.iter().collect()
+	}
+}
+
+// Token values are single path segments, separators would let them escape their place in the pattern
+fn sanitize(value: &str) -> String {
+	value
+		.chars()
+		.filter(|c| !c.is_control())
+		.map(|c| {
+			if matches!(c, '/' | '\\' | ':') {
+				'-'
+			} else {
+				c
+			}
+		})
+		.collect::<String>()
+		.trim_matches('.')
+		.to_string()
+}
+
+fn source_segments(path: &Path) -> Vec<String> {
+	path.components()
+		.map(|c| sanitize(&c.as_os_str().to_string_lossy()))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::content::Frontmatter;
+	use chrono::NaiveDate;
+
+	fn doc(path: &str, title: Option<&str>, date: Option<&str>, version: Option<&str>) -> Document {
+		Document {
+			frontmatter: Frontmatter {
+				title: title.map(str::to_string),
+				date: date.map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap()),
+				..Default::default()
+			},
+			path: Path::new("docs").join(path),
+			relative_path: PathBuf::from(path),
+			version: version.map(str::to_string),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_tokens() {
+		let d = doc(
+			"guide/Getting Started.md",
+			Some("Getting Started: The Basics"),
+			Some("2024-03-05"),
+			Some("v2"),
+		);
+		assert_eq!(
+			UrlResolver::resolve(&d, "/docs/:version/:slug/index.html"),
+			PathBuf::from("docs/v2/Getting Started/index.html")
+		);
+		assert_eq!(
+			UrlResolver::resolve(&d, ":title/"),
+			PathBuf::from("getting-started-the-basics/index.html")
+		);
+		assert_eq!(
+			UrlResolver::resolve(&d, "blog/:date/:title.html"),
+			PathBuf::from("blog/2024/03/05/getting-started-the-basics.html")
+		);
+		assert_eq!(
+			UrlResolver::resolve(&d, ":path"),
+			PathBuf::from("guide/Getting Started.html")
+		);
+		assert_eq!(
+			UrlResolver::resolve(&d, ":version-:slug"),
+			PathBuf::from("v2-Getting Started.html")
+		);
+
+		let d = doc("release-1.2.md", None, None, None);
+		assert_eq!(
+			UrlResolver::resolve(&d, ":slug"),
+			PathBuf::from("release-1.2.html")
+		);
+	}
+
+	#[test]
+	fn test_missing_tokens() {
+		let d = doc("guide/setup.md", None, None, None);
+		assert_eq!(
+			UrlResolver::resolve(&d, "/docs/:version/:slug/index.html"),
+			PathBuf::from("docs/setup/index.html")
+		);
+		assert_eq!(
+			UrlResolver::resolve(&d, "blog/:date/:title/"),
+			PathBuf::from("blog/setup/index.html")
+		);
+		assert_eq!(
+			UrlResolver::resolve(&d, ":version"),
+			PathBuf::from("index.html")
+		);
+	}
+
+	#[test]
+	fn test_sanitization() {
+		let d = doc("a/../b.md", Some("../../etc"), None, Some("../../v1"));
+		assert_eq!(
+			UrlResolver::resolve(&d, "../:version/./:slug"),
+			PathBuf::from("-..-v1/b.html")
+		);
+		assert_eq!(
+			UrlResolver::resolve(&d, ":title"),
+			PathBuf::from("etc.html")
+		);
+		assert_eq!(
+			UrlResolver::resolve(&d, ":path/"),
+			PathBuf::from("a/b/index.html")
+		);
+
+		let d = doc("notes.md", Some("C:\\Windows\\x"), None, Some("a/b"));
+		assert_eq!(
+			UrlResolver::resolve(&d, "\\:version\\:slug"),
+			PathBuf::from("a-b/notes.html")
+		);
+		assert_eq!(
+			UrlResolver::resolve(&d, ":title"),
+			PathBuf::from("cwindowsx.html")
+		);
+	}
+}
@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use unicode_normalization::UnicodeNormalization;
+
+pub fn escape_html(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for ch in text.chars() {
+		match ch {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&#39;"),
+			_ => escaped.push(ch),
+		}
+	}
+	escaped
+}
+
+pub fn absolute_url(base_url: Option<&str>, path: &str) -> String {
+	if path.starts_with("http://") || path.starts_with("https://") {
+		return path.to_string();
+	}
+
+	match base_url {
+		Some(base) => format!(
+			"{}/{}",
+			base.trim_end_matches('/'),
+			path.trim_start_matches('/')
+		),
+		None => path.to_string(),
+	}
+}
+
+// Loose BCP 47 shape check: ASCII alphanumeric subtags of 1-8 chars joined by '-'
+pub fn is_valid_lang(tag: &str) -> bool {
+	tag.split('-')
+		.all(|sub| (1..=8).contains(&sub.len()) && sub.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+// Through a temporary file and a rename, so a reader sees the old contents or the new, never half
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+	let tmp = path.with_extension(format!("tmp-{}", std::process::id()));
+	fs::write(&tmp, contents)?;
+	fs::rename(&tmp, path)
+}
+
+// UTC timestamp in RFC 3339 form, e.g. 2024-01-15T10:30:00Z
+pub fn rfc3339(time: SystemTime) -> String {
+	let secs = time
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or_default();
+	let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+
+	// Civil date from days since the epoch, after Howard Hinnant's days_from_civil inverse
+	let z = days + 719468;
+	let era = z.div_euclid(146097);
+	let doe = z.rem_euclid(146097);
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + i64::from(month <= 2);
+
+	format!(
+		"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+		year,
+		month,
+		day,
+		rem / 3600,
+		rem % 3600 / 60,
+		rem % 60
+	)
+}
+
+// NFKD first so accented letters keep their base letter and lose the combining mark
+pub fn slugify(text: &str) -> String {
+	let mut slug = String::new();
+	for ch in text.nfkd().collect::<String>().to_lowercase().chars() {
+		if ch.is_alphanumeric() {
+			slug.push(ch);
+		} else if (ch.is_whitespace() || ch == '-' || ch == '_') && !slug.ends_with('-') {
+			slug.push('-');
+		}
+	}
+	slug.trim_matches('-').to_string()
+}
+
+// Hands out slugs that are unique within one scope, e.g. the headings of a page.
+// Repeats get -2, -3 and so on.
+#[derive(Debug, Default)]
+pub struct SlugDeduplicator {
+	used: HashSet<String>,
+}
+
+impl SlugDeduplicator {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	// Marks a slug as taken without renaming it, for ids the author wrote by hand
+	pub fn reserve(&mut self, slug: &str) {
+		self.used.insert(slug.to_string());
+	}
+
+	pub fn unique(&mut self, slug: &str) -> String {
+		let mut unique = slug.to_string();
+		let mut n = 1;
+		while self.used.contains(&unique) {
+			n += 1;
+			unique = format!("{}-{}", slug, n);
+		}
+		self.used.insert(unique.clone());
+		unique
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn test_slugify() {
+		assert_eq!(slugify("Getting Started"), "getting-started");
+		assert_eq!(slugify("  What's new in v2.0?  "), "whats-new-in-v20");
+		assert_eq!(slugify("a -- b\t\nc"), "a-b-c");
+		assert_eq!(slugify("snake_case_name"), "snake-case-name");
+		assert_eq!(slugify("__init__ method"), "init-method");
+		assert_eq!(
+			slugify("-- leading and trailing --"),
+			"leading-and-trailing"
+		);
+
+		// Unicode
+		assert_eq!(slugify("Café Crème"), "cafe-creme");
+		assert_eq!(slugify("Ｆｕｌｌ Ｗｉｄｔｈ"), "full-width");
+		assert_eq!(slugify("ﬁle ligature"), "file-ligature");
+		assert_eq!(slugify("Straße"), "straße");
+		assert_eq!(slugify("e\u{301}te\u{301}"), "ete");
+		assert_eq!(slugify("Ångström Über"), "angstrom-uber");
+		assert_eq!(slugify("日本語 文書"), "日本語-文書");
+		assert_eq!(slugify("Привет Мир"), "привет-мир");
+
+		// Nothing left to slug
+		assert_eq!(slugify(""), "");
+		assert_eq!(slugify("   "), "");
+		assert_eq!(slugify("?!... ---"), "");
+		assert_eq!(slugify("!@#$%^&*()"), "");
+	}
+
+	#[test]
+	fn test_slug_deduplicator() {
+		let mut slugs = SlugDeduplicator::new();
+		assert_eq!(slugs.unique("intro"), "intro");
+		assert_eq!(slugs.unique("intro"), "intro-2");
+		assert_eq!(slugs.unique("intro"), "intro-3");
+
+		// A generated suffix can collide with a real heading
+		assert_eq!(slugs.unique("intro-2"), "intro-2-2");
+		assert_eq!(slugs.unique("setup-2"), "setup-2");
+		assert_eq!(slugs.unique("setup"), "setup");
+		assert_eq!(slugs.unique("setup"), "setup-3");
+
+		// Reserved slugs are never handed out again
+		let mut slugs = SlugDeduplicator::new();
+		slugs.reserve("usage");
+		assert_eq!(slugs.unique("usage"), "usage-2");
+
+		// Empty slugs still deduplicate
+		assert_eq!(slugs.unique(""), "");
+		assert_eq!(slugs.unique(""), "-2");
+
+		// Each scope starts fresh
+		assert_eq!(SlugDeduplicator::new().unique("intro"), "intro");
+	}
+
+	#[test]
+	fn test_rfc3339() {
+		let time = UNIX_EPOCH + Duration::from_secs(1705314600);
+		assert_eq!(rfc3339(time), "2024-01-15T10:30:00Z");
+		assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+	}
+}
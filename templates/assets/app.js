@@ -6,6 +6,7 @@ const html = document.documentElement;
 const savedTheme = localStorage.getItem('theme') || 'light';
 html.setAttribute('data-theme', savedTheme);
 updateThemeIcon(savedTheme);
+updateSyntaxTheme(savedTheme, localStorage.getItem('theme') !== null);
 
 themeToggle.addEventListener('click', () => {
     const currentTheme = html.getAttribute('data-theme');
@@ -13,6 +14,7 @@ themeToggle.addEventListener('click', () => {
     html.setAttribute('data-theme', newTheme);
     localStorage.setItem('theme', newTheme);
     updateThemeIcon(newTheme);
+    updateSyntaxTheme(newTheme, true);
 });
 
 function updateThemeIcon(theme) {
@@ -22,6 +24,17 @@ function updateThemeIcon(theme) {
     }
 }
 
+// Syntax stylesheets follow the system preference until a theme is picked explicitly
+function updateSyntaxTheme(theme, explicit) {
+    const light = document.getElementById('syntax-light');
+    const dark = document.getElementById('syntax-dark');
+    if (!light || !dark || !explicit) {
+        return;
+    }
+    light.media = theme === 'dark' ? 'not all' : 'all';
+    dark.media = theme === 'dark' ? 'all' : 'not all';
+}
+
 // Search Functionality
 let searchIndex = [];
 let fuse = null;
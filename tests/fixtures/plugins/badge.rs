@@ -0,0 +1,124 @@
+// Guest for the wasm-plugins tests, implements {{badge color="red" label="foo"}}. Rebuild badge.wasm with
+// rustc --edition 2021 --target wasm32-unknown-unknown --crate-type cdylib -O -C panic=abort \
+//   -C strip=symbols tests/fixtures/plugins/badge.rs -o tests/fixtures/plugins/badge.wasm
+#![no_std]
+
+use core::arch::wasm32::unreachable;
+use core::ptr::{addr_of_mut, copy_nonoverlapping};
+use core::slice;
+
+const HEAP_SIZE: usize = 64 * 1024;
+static mut HEAP: [u8; HEAP_SIZE] = [0; HEAP_SIZE];
+static mut NEXT: usize = 0;
+static mut LIVE: i32 = 0;
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+	unreachable()
+}
+
+// Bump allocator, the heap is reused once everything handed out has been freed
+#[no_mangle]
+pub unsafe extern "C" fn alloc(len: i32) -> i32 {
+	let start = NEXT;
+	let end = start + len as usize;
+	if end > HEAP_SIZE {
+		unreachable()
+	}
+	NEXT = end;
+	LIVE += 1;
+	addr_of_mut!(HEAP).cast::<u8>().add(start) as i32
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dealloc(_ptr: i32, _len: i32) {
+	LIVE -= 1;
+	if LIVE == 0 {
+		NEXT = 0;
+	}
+}
+
+// Allocations the host has not freed yet, the tests check it returns to zero
+#[no_mangle]
+pub unsafe extern "C" fn live_allocations() -> i32 {
+	LIVE
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn process_shortcode(
+	name_ptr: i32,
+	name_len: i32,
+	args_ptr: i32,
+	args_len: i32,
+	body_ptr: i32,
+	body_len: i32,
+) -> i64 {
+	if bytes(name_ptr, name_len) != b"badge" {
+		return 0;
+	}
+	let args = bytes(args_ptr, args_len);
+	let color = attribute(args, b"color").unwrap_or(b"gray");
+	let label = attribute(args, b"label").unwrap_or(bytes(body_ptr, body_len));
+
+	let mut out = Output {
+		buf: [0; 1024],
+		len: 0,
+	};
+	out.push(b"<span class=\"badge badge-");
+	for &c in color {
+		if c.is_ascii_alphanumeric() || c == b'-' {
+			out.push(&[c]);
+		}
+	}
+	out.push(b"\">");
+	for &c in label {
+		match c {
+			b'&' => out.push(b"&amp;"),
+			b'<' => out.push(b"&lt;"),
+			b'>' => out.push(b"&gt;"),
+			b'"' => out.push(b"&quot;"),
+			_ => out.push(&[c]),
+		}
+	}
+	out.push(b"</span>");
+
+	let ptr = alloc(out.len as i32);
+	copy_nonoverlapping(out.buf.as_ptr(), ptr as *mut u8, out.len);
+	((ptr as u32 as i64) << 32) | out.len as i64
+}
+
+unsafe fn bytes<'a>(ptr: i32, len: i32) -> &'a [u8] {
+	if len == 0 {
+		return &[];
+	}
+	slice::from_raw_parts(ptr as *const u8, len as usize)
+}
+
+// The value of key="value" in the shortcode's arguments
+fn attribute<'a>(args: &'a [u8], key: &[u8]) -> Option<&'a [u8]> {
+	let mut i = 0;
+	while i + key.len() + 2 <= args.len() {
+		let at_word = i == 0 || args[i - 1].is_ascii_whitespace();
+		if at_word && args[i..].starts_with(key) && args[i + key.len()..].starts_with(b"=\"") {
+			let start = i + key.len() + 2;
+			let end = args[start..].iter().position(|&c| c == b'"')? + start;
+			return Some(&args[start..end]);
+		}
+		i += 1;
+	}
+	None
+}
+
+struct Output {
+	buf: [u8; 1024],
+	len: usize,
+}
+
+impl Output {
+	// Anything past the buffer is cut off
+	fn push(&mut self, bytes: &[u8]) {
+		let n = bytes.len().min(self.buf.len() - self.len);
+		self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+		self.len += n;
+	}
+}